@@ -29,6 +29,18 @@ pub unsafe fn mm_hash256(kmer: __m256i) -> __m256i {
     key
 }
 
+/// SpacedSeed::extract on four lanes. Each run is (right shift, mask, left shift).
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn mm256_spaced_seed(kmers: __m256i, runs: &[(__m128i, __m256i, __m128i)]) -> __m256i {
+    let mut seeds = _mm256_setzero_si256();
+    for (src_shift, mask, dst_shift) in runs {
+        let run = _mm256_and_si256(_mm256_srl_epi64(kmers, *src_shift), *mask);
+        seeds = _mm256_or_si256(seeds, _mm256_sll_epi64(run, *dst_shift));
+    }
+    seeds
+}
+
 #[target_feature(enable = "avx2")]
 pub unsafe fn avx2_fmh_seeds(
     string: &[u8],
@@ -38,33 +50,46 @@ pub unsafe fn avx2_fmh_seeds(
     seed: bool,
 ) {
     if seed && new_sketch.kmer_seeds_k.is_none() {
-        new_sketch.kmer_seeds_k = Some(KmerSeeds::default());
+        new_sketch.kmer_seeds_k = Some(KmerSeeds::with_seed_bits(2 * sketch_params.k));
     }
-    let marker_k = K_MARKER_DNA;
+    let marker_k = sketch_params.marker_k;
     let _kmer_seeds_k = &mut new_sketch.kmer_seeds_k;
     let k = sketch_params.k;
     let c = sketch_params.c;
     let marker_c = sketch_params.marker_c;
+    if string.len() < 2 * marker_k {
+        return;
+    }
     let len = (string.len() - marker_k + 1) / 4;
     let string1 = &string[0..len + marker_k - 1];
     let string2 = &string[len..2 * len + marker_k - 1];
     let string3 = &string[2 * len..3 * len + marker_k - 1];
     let string4 = &string[3 * len..4 * len + marker_k - 1];
-    if k > marker_k {
+    if sketch_params.seed_span() > marker_k {
         panic!("Value of k > {} for DNA; not allowed.", marker_k);
     }
-    if string.len() < 2 * marker_k {
-        return;
-    }
 
     let mut rolling_kmer_f_marker = _mm256_set_epi64x(0, 0, 0, 0);
     let mut rolling_kmer_r_marker = _mm256_set_epi64x(0, 0, 0, 0);
     let rev_sub = _mm256_set_epi64x(3, 3, 3, 3);
+    let rev_shift = _mm_cvtsi64_si128(2 * (marker_k as i64 - 1));
+    // _mm256_cmpgt_epi64 is signed; flipping the top bit gives the unsigned order used
+    // by fmh_seeds once k-mers reach 32 bases.
+    let sign_bit = _mm256_set1_epi64x(i64::MIN);
+    let seed_span = sketch_params.seed_span();
+    let mut resume_inds = [0, 0, 0, 0];
+    let mut marker_resume_inds = [0, 0, 0, 0];
     for i in 0..marker_k - 1 {
         let ascii_rep_1 = string1[i] as usize;
         let ascii_rep_2 = string2[i] as usize;
         let ascii_rep_3 = string3[i] as usize;
         let ascii_rep_4 = string4[i] as usize;
+        for (j, ascii_rep) in [ascii_rep_1, ascii_rep_2, ascii_rep_3, ascii_rep_4].into_iter().enumerate() {
+            if !BYTE_IS_NUC[ascii_rep] {
+                resume_inds[j] = i + seed_span;
+                marker_resume_inds[j] = i + marker_k;
+            }
+        }
         let nuc_f1 = BYTE_TO_SEQ[ascii_rep_1] as i64;
         let nuc_f2 = BYTE_TO_SEQ[ascii_rep_2] as i64;
         let nuc_f3 = BYTE_TO_SEQ[ascii_rep_3] as i64;
@@ -76,12 +101,25 @@ pub unsafe fn avx2_fmh_seeds(
         rolling_kmer_f_marker = _mm256_or_si256(rolling_kmer_f_marker, f_nucs);
 
         rolling_kmer_r_marker = _mm256_srli_epi64(rolling_kmer_r_marker, 2);
-        let shift_nuc_r = _mm256_slli_epi64(r_nucs, 40);
+        let shift_nuc_r = _mm256_sll_epi64(r_nucs, rev_shift);
         rolling_kmer_r_marker = _mm256_or_si256(rolling_kmer_r_marker, shift_nuc_r);
     }
 
     let seed_mask = (MarkerBits::MAX >> (std::mem::size_of::<MarkerBits>() * 8 - 2 * k)) as i64;
     let mm256_seed_mask = _mm256_set_epi64x(seed_mask, seed_mask, seed_mask, seed_mask);
+    let spaced_runs = sketch_params.spaced_seed.as_ref().map(|s| {
+        s.runs()
+            .iter()
+            .map(|run| {
+                let mask = run.mask as i64;
+                (
+                    _mm_cvtsi64_si128(run.src_shift as i64),
+                    _mm256_set_epi64x(mask, mask, mask, mask),
+                    _mm_cvtsi64_si128(run.dst_shift as i64),
+                )
+            })
+            .collect::<Vec<(__m128i, __m256i, __m128i)>>()
+    });
     let marker_mask =
         (MarkerBits::MAX >> (std::mem::size_of::<MarkerBits>() * 8 - 2 * marker_k)) as i64;
     let rev_marker_mask: u64 = !(3 << (2 * marker_k - 2));
@@ -104,7 +142,6 @@ pub unsafe fn avx2_fmh_seeds(
 
     //dbg!(KmerEnc::print_string(u64::from_le_bytes(_mm256_extract_epi64(rolling_kmer_f_marker,0).to_le_bytes()), 21));
 
-    let mut resume_inds = [0,0,0,0];
     for i in marker_k-1..(len + marker_k - 1) {
 
         let ascii_rep_1 = string1[i] as usize;
@@ -112,17 +149,21 @@ pub unsafe fn avx2_fmh_seeds(
         let ascii_rep_3 = string3[i] as usize;
         let ascii_rep_4 = string4[i] as usize;
 
-        if ascii_rep_1 == ASCII_N{
-            resume_inds[0] = i + marker_k;
+        if !BYTE_IS_NUC[ascii_rep_1]{
+            resume_inds[0] = i + seed_span;
+            marker_resume_inds[0] = i + marker_k;
         }
-        if ascii_rep_2 == ASCII_N{
-            resume_inds[1] = i + marker_k;
+        if !BYTE_IS_NUC[ascii_rep_2]{
+            resume_inds[1] = i + seed_span;
+            marker_resume_inds[1] = i + marker_k;
         }
-        if ascii_rep_3 == ASCII_N{
-            resume_inds[2] = i + marker_k;
+        if !BYTE_IS_NUC[ascii_rep_3]{
+            resume_inds[2] = i + seed_span;
+            marker_resume_inds[2] = i + marker_k;
         }
-        if ascii_rep_4 == ASCII_N{
-            resume_inds[3] = i + marker_k;
+        if !BYTE_IS_NUC[ascii_rep_4]{
+            resume_inds[3] = i + seed_span;
+            marker_resume_inds[3] = i + marker_k;
         }
 
 
@@ -138,14 +179,29 @@ pub unsafe fn avx2_fmh_seeds(
         rolling_kmer_f_marker = _mm256_or_si256(rolling_kmer_f_marker, f_nucs);
         rolling_kmer_f_marker = _mm256_and_si256(rolling_kmer_f_marker, mm256_marker_mask);
         rolling_kmer_r_marker = _mm256_srli_epi64(rolling_kmer_r_marker, 2);
-        let shift_nuc_r = _mm256_slli_epi64(r_nucs, 40);
+        let shift_nuc_r = _mm256_sll_epi64(r_nucs, rev_shift);
         rolling_kmer_r_marker = _mm256_and_si256(rolling_kmer_r_marker, mm256_rev_marker_mask);
         rolling_kmer_r_marker = _mm256_or_si256(rolling_kmer_r_marker, shift_nuc_r);
 
-        let rolling_kmer_f_seed = _mm256_and_si256(rolling_kmer_f_marker, mm256_seed_mask);
-        let rolling_kmer_r_seed = _mm256_and_si256(rolling_kmer_r_marker, mm256_seed_mask);
-        let compare = _mm256_cmpgt_epi64(rolling_kmer_r_seed, rolling_kmer_f_seed);
-        let compare_marker = _mm256_cmpgt_epi64(rolling_kmer_r_marker, rolling_kmer_f_marker);
+        let (rolling_kmer_f_seed, rolling_kmer_r_seed) = if let Some(runs) = &spaced_runs {
+            (
+                mm256_spaced_seed(rolling_kmer_f_marker, runs),
+                mm256_spaced_seed(rolling_kmer_r_marker, runs),
+            )
+        } else {
+            (
+                _mm256_and_si256(rolling_kmer_f_marker, mm256_seed_mask),
+                _mm256_and_si256(rolling_kmer_r_marker, mm256_seed_mask),
+            )
+        };
+        let compare = _mm256_cmpgt_epi64(
+            _mm256_xor_si256(rolling_kmer_r_seed, sign_bit),
+            _mm256_xor_si256(rolling_kmer_f_seed, sign_bit),
+        );
+        let compare_marker = _mm256_cmpgt_epi64(
+            _mm256_xor_si256(rolling_kmer_r_marker, sign_bit),
+            _mm256_xor_si256(rolling_kmer_f_marker, sign_bit),
+        );
         let canonical_seeds_256 =
             _mm256_blendv_epi8(rolling_kmer_r_seed, rolling_kmer_f_seed, compare);
 
@@ -194,7 +250,7 @@ pub unsafe fn avx2_fmh_seeds(
                     canonical_kmer_marker = _mm256_extract_epi64(rolling_kmer_r_marker, IND);
                 };
                 //                if _mm256_extract_epi64(hash_256, IND) < threshold_marker {
-                if v1 < threshold_marker_unsigned {
+                if v1 < threshold_marker_unsigned && marker_resume_inds[0] <= i {
                     new_sketch.marker_seeds.insert(canonical_kmer_marker as u64);
                 }
             }
@@ -217,7 +273,7 @@ pub unsafe fn avx2_fmh_seeds(
                     canonical_kmer_marker = _mm256_extract_epi64(rolling_kmer_r_marker, IND);
                 };
                 //                if _mm256_extract_epi64(hash_256, IND) < threshold_marker {
-                if v2 < threshold_marker_unsigned {
+                if v2 < threshold_marker_unsigned && marker_resume_inds[1] <= i {
                     new_sketch.marker_seeds.insert(canonical_kmer_marker as u64);
                 }
             }
@@ -240,7 +296,7 @@ pub unsafe fn avx2_fmh_seeds(
                     canonical_kmer_marker = _mm256_extract_epi64(rolling_kmer_r_marker, IND);
                 };
                 //                if _mm256_extract_epi64(hash_256, IND) < threshold_marker {
-                if v3 < threshold_marker_unsigned {
+                if v3 < threshold_marker_unsigned && marker_resume_inds[2] <= i {
                     new_sketch.marker_seeds.insert(canonical_kmer_marker as u64);
                 }
             }
@@ -263,7 +319,7 @@ pub unsafe fn avx2_fmh_seeds(
                     canonical_kmer_marker = _mm256_extract_epi64(rolling_kmer_r_marker, IND);
                 };
                 //                if _mm256_extract_epi64(hash_256, IND) < threshold_marker {
-                if v4 < threshold_marker_unsigned {
+                if v4 < threshold_marker_unsigned && marker_resume_inds[3] <= i {
                     new_sketch.marker_seeds.insert(canonical_kmer_marker as u64);
                 }
             }
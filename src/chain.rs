@@ -3,6 +3,9 @@ use gbdt::gradient_boost::GBDT;
 use crate::types::*;
 use bio::data_structures::interval_tree::IntervalTree;
 use crate::regression;
+use crate::screen;
+use crate::ddh;
+use crate::tetra;
 
 use fxhash::FxHashMap;
 use log::*;
@@ -54,28 +57,87 @@ fn std_deviation(data: &[f64]) -> f64 {
     }
 }
 
-fn bootstrap_interval(ani_ests: &Vec<(f64,usize)>) -> (f64,f64,f64){
+/// Result row for a pair without an ANI estimate (failed the screen or too little
+/// alignment) that is still reported because --tetra was requested.
+pub fn tetra_only_result(ref_sketch: &Sketch, query_sketch: &Sketch) -> Option<AniEstResult> {
+    let tetra = tetra::tetra_correlation(&ref_sketch.tetra_zscores, &query_sketch.tetra_zscores)?;
+    Some(AniEstResult {
+        ani: f32::NAN,
+        align_fraction_query: f32::NAN,
+        align_fraction_ref: f32::NAN,
+        ref_file: ref_sketch.name().to_string(),
+        query_file: query_sketch.name().to_string(),
+        ref_path: ref_sketch.file_name.clone(),
+        query_path: query_sketch.file_name.clone(),
+        query_contig: query_sketch.contigs[0].clone(),
+        ref_contig: ref_sketch.contigs[0].clone(),
+        ci_upper: f32::NAN,
+        ci_lower: f32::NAN,
+        num_contigs_q: query_sketch.contigs.len() as u32,
+        num_contigs_r: ref_sketch.contigs.len() as u32,
+        tetra: Some(tetra),
+        ref_stats: ref_sketch.genome_stats,
+        query_stats: query_sketch.genome_stats,
+        ..Default::default()
+    })
+}
+
+/// Random stream for --ci bootstraps. Without a user seed every pair uses the same
+/// historical seed; with one, the seed is mixed with the (unordered) pair so that pairs get
+/// independent but reproducible streams.
+fn ci_rng(ci_params: &CiParams, ref_sketch: &Sketch, query_sketch: &Sketch) -> fastrand::Rng {
+    match ci_params.seed {
+        None => fastrand::Rng::with_seed(LEGACY_CI_SEED),
+        Some(seed) => {
+            let r = (&ref_sketch.file_name, &ref_sketch.contigs[0]);
+            let q = (&query_sketch.file_name, &query_sketch.contigs[0]);
+            let pair = if r < q { (r, q) } else { (q, r) };
+            fastrand::Rng::with_seed(seed ^ fxhash::hash64(&pair))
+        }
+    }
+}
+
+/// Normal interval for the weighted mean: the weighted variance is divided by the
+/// effective number of fragments, (sum w)^2 / sum w^2.
+fn analytic_interval(ests: &[(f64, f64)]) -> (f64, f64) {
+    let total_w = ests.iter().map(|x| x.1).sum::<f64>();
+    let total_w2 = ests.iter().map(|x| x.1 * x.1).sum::<f64>();
+    if total_w <= 0. {
+        return (0., 1.);
+    }
+    let mean = ests.iter().map(|x| x.0 * x.1).sum::<f64>() / total_w;
+    let var = ests.iter().map(|x| x.1 * (x.0 - mean) * (x.0 - mean)).sum::<f64>() / total_w;
+    let n_eff = total_w * total_w / total_w2;
+    let half = CI_Z_90 * (var / n_eff).sqrt();
+    (mean - half, mean + half)
+}
+
+fn bootstrap_interval(ani_ests: &Vec<(f64,usize)>, ci_params: &CiParams, rng: &fastrand::Rng) -> (f64,f64,f64){
     let ani_est_no_mult = ani_ests.iter().map(|x| x.0).collect::<Vec<f64>>();
     let std = std_deviation(&ani_est_no_mult);
     let mut res = vec![];
     let mut mult_ani_ests = vec![];
-    fastrand::seed(7);
     let num_samp = ani_ests.len();
     //Return no confidence interval if number of samples is too small. 
     if num_samp < 10 {
         return (0.,1., std);
     }
+    if ci_params.method == CiMethod::Analytic {
+        let weighted = ani_ests.iter().map(|x| (x.0, x.1 as f64)).collect::<Vec<(f64, f64)>>();
+        let (lower, upper) = analytic_interval(&weighted);
+        return (lower, upper, std);
+    }
     for (ani,mult) in ani_ests.iter(){
         for _ in 0..*mult{
             mult_ani_ests.push(ani);
         }
     }
-    let iters = 100;
+    let iters = ci_params.replicates;
     for _ in 0..iters{
         let mut rand_vec = vec![];
         rand_vec.reserve(num_samp);
         for _ in 0..num_samp{
-            rand_vec.push(fastrand::usize(..mult_ani_ests.len()));
+            rand_vec.push(rng.usize(..mult_ani_ests.len()));
         }
         let sum = rand_vec.into_iter().map(|x| mult_ani_ests[x]).sum::<f64>();
         res.push(sum/(num_samp as f64));
@@ -85,6 +147,67 @@ fn bootstrap_interval(ani_ests: &Vec<(f64,usize)>) -> (f64,f64,f64){
 
 }
 
+/// Expected SNPs as the sum of per-fragment divergence times fragment bases, with a
+/// percentile bootstrap over fragments. Returns (estimate, 5%, 95%).
+fn bootstrap_snp_interval(
+    frag_snps: &[(f64, GnPosition)],
+    ci_params: &CiParams,
+    rng: &fastrand::Rng,
+) -> (f64, f64, f64) {
+    let snps = frag_snps.iter().map(|(ani, bases)| (1. - ani) * *bases as f64).collect::<Vec<f64>>();
+    let total = snps.iter().sum::<f64>();
+    let num_samp = snps.len();
+    if num_samp < 10 {
+        let bases = frag_snps.iter().map(|x| x.1 as f64).sum::<f64>();
+        return (total, 0., bases);
+    }
+    if ci_params.method == CiMethod::Analytic {
+        //Sum of n fragments: n times the mean, with n times the per-fragment variance.
+        let unweighted = snps.iter().map(|x| (*x, 1.)).collect::<Vec<(f64, f64)>>();
+        let (lower, upper) = analytic_interval(&unweighted);
+        let n = num_samp as f64;
+        return (total, f64::max(lower * n, 0.), upper * n);
+    }
+    let iters = ci_params.replicates;
+    let mut res = vec![];
+    for _ in 0..iters {
+        let mut sum = 0.;
+        for _ in 0..num_samp {
+            sum += snps[rng.usize(..num_samp)];
+        }
+        res.push(sum);
+    }
+    res.sort_by(|x, y| x.partial_cmp(y).unwrap());
+    (total, res[iters * 5 / 100 - 1], res[iters * 95 / 100 - 1])
+}
+
+/// SNP counts after the final (possibly regression-adjusted) ANI is known. Without --ci the
+/// estimate is (1 - ANI) * aligned bases with bounds from the ANI interval; with --ci the
+/// per-fragment estimate is shifted by the regression adjustment.
+fn finalize_snp_estimate(ani: &mut AniEstResult, raw_ani: f32, est_ci: bool) {
+    if ani.ani.is_nan() || ani.ani < 0. {
+        return;
+    }
+    let bases = ani.total_bases_covered as f64;
+    let snps = &mut ani.snps;
+    if est_ci {
+        let shift = (raw_ani - ani.ani) as f64 * bases;
+        snps.snps = f64::max(snps.snps - shift, 0.);
+        snps.snps_lower = f64::max(snps.snps_lower - shift, 0.);
+        snps.snps_upper = f64::max(snps.snps_upper - shift, 0.);
+    } else {
+        snps.snps = f64::max(1. - ani.ani as f64, 0.) * bases;
+        snps.snps_lower = f64::max(1. - ani.ci_upper as f64, 0.) * bases;
+        snps.snps_upper = f64::max(1. - ani.ci_lower as f64, 0.) * bases;
+    }
+    let p = f64::max(1. - ani.ani as f64, 0.);
+    snps.jc_distance = if p < 0.75 {
+        -0.75 * f64::ln(1. - 4. / 3. * p)
+    } else {
+        f64::INFINITY
+    };
+}
+
 pub fn map_params_from_sketch <'a>(
     ref_sketch: &Sketch,
     amino_acid: bool,
@@ -114,7 +237,11 @@ pub fn map_params_from_sketch <'a>(
 //    let min_score = 0.;
     let k = ref_sketch.k;
     let model;
-    if let Some(m) = model_opt{
+    if ref_sketch.spaced_seed.is_some(){
+        // The regression model was trained on contiguous seeds.
+        model = None;
+    }
+    else if let Some(m) = model_opt{
         model = Some(m);
     }
     else{
@@ -131,31 +258,78 @@ pub fn map_params_from_sketch <'a>(
         length_cover_cutoff,
         index_chain_band,
         k,
+        seed_span: ref_sketch.seed_span(),
         amino_acid,
         min_score,
         robust: command_params.robust,
         median: command_params.median,
         bp_chain_band,
         min_length_cover,
-        model
+        est_ci: command_params.est_ci,
+        ci_params: command_params.ci_params,
+        model,
+        both_directions: command_params.extra_columns.both_directions,
+        switch_override: None,
+        markers_only: command_params.extra_columns.markers_only,
+        synteny: command_params.extra_columns.synteny,
+        ddh: command_params.extra_columns.ddh,
+        indels: command_params.extra_columns.indels,
     }
 }
 
-pub fn chain_seeds(
+/// Anchor, chain and filter down to the non-overlapping (orthologous) chain intervals
+/// for each fragment. The returned bool is true if the query and reference were switched
+/// during anchoring; see `ChainInterval::ref_coords`.
+pub fn chain_intervals(
     ref_sketch: &Sketch,
     query_sketch: &Sketch,
-    map_params: MapParams,
-) -> AniEstResult {
-    let (anchor_chunks, switched) = get_anchors(ref_sketch, query_sketch, &map_params);
-    let chain_results = chain_anchors_ani(&anchor_chunks, &map_params);
+    map_params: &MapParams,
+) -> (Vec<Vec<ChainInterval>>, AnchorChunks, bool) {
+    let (anchor_chunks, switched) = get_anchors(ref_sketch, query_sketch, map_params);
+    let chain_results = chain_anchors_ani(&anchor_chunks, map_params);
     let mut good_intervals = vec![];
     for i in 0..anchor_chunks.chunks.len() {
         let chain_result = &chain_results[i];
         let anchors = &anchor_chunks.chunks[i];
-        get_chain_intervals(&mut good_intervals, chain_result, anchors, &map_params, i);
+        get_chain_intervals(&mut good_intervals, chain_result, anchors, map_params, i);
     }
     let good_interval_chunks =
         get_nonoverlapping_chains(&mut good_intervals, anchor_chunks.chunks.len());
+    (good_interval_chunks, anchor_chunks, switched)
+}
+
+pub fn chain_seeds(
+    ref_sketch: &Sketch,
+    query_sketch: &Sketch,
+    map_params: MapParams,
+) -> AniEstResult {
+    if map_params.markers_only {
+        return screen::marker_ani(ref_sketch, query_sketch, &map_params);
+    }
+    chain_seeds_diagnostics(ref_sketch, query_sketch, map_params, None)
+}
+
+/// `chain_seeds`, but also records the anchors, chains, fragment ANIs and trimming
+/// into `diagnostics` if it is given.
+pub fn chain_seeds_diagnostics(
+    ref_sketch: &Sketch,
+    query_sketch: &Sketch,
+    map_params: MapParams,
+    mut diagnostics: Option<&mut AniDiagnostics>,
+) -> AniEstResult {
+    let (good_interval_chunks, anchor_chunks, switched) =
+        chain_intervals(ref_sketch, query_sketch, &map_params);
+    if let Some(diag) = diagnostics.as_deref_mut(){
+        diag.switched = switched;
+        diag.chunks = (0..anchor_chunks.chunks.len())
+            .map(|i| ChunkDiagnostics {
+                chunk_id: i,
+                num_anchors: anchor_chunks.chunks[i].len(),
+                num_seeds: anchor_chunks.seeds_in_chunk[i].len(),
+                chains: good_interval_chunks[i].clone(),
+            })
+            .collect();
+    }
     let mut ani = calculate_ani(
         &good_interval_chunks,
         ref_sketch,
@@ -163,10 +337,45 @@ pub fn chain_seeds(
         &anchor_chunks,
         &map_params,
         switched,
+        diagnostics.as_deref_mut(),
     );
+    let raw_ani = ani.ani;
     if let Some(model) = map_params.model{
         regression::predict_from_ani_res(&mut ani, model);
     }
+    finalize_snp_estimate(&mut ani, raw_ani, map_params.est_ci);
+    ani.tetra = tetra::tetra_correlation(&ref_sketch.tetra_zscores, &query_sketch.tetra_zscores);
+    ani.ref_stats = ref_sketch.genome_stats;
+    ani.query_stats = query_sketch.genome_stats;
+    if map_params.ddh && !map_params.amino_acid{
+        ani.ddh = ddh::estimate_ddh(&ani, ref_sketch.unmasked_length(), query_sketch.unmasked_length());
+    }
+    if let Some(diag) = diagnostics{
+        diag.raw_ani = raw_ani;
+        diag.learned_ani = map_params.model.is_some();
+        diag.ani = ani.ani;
+        diag.align_fraction_ref = ani.align_fraction_ref;
+        diag.align_fraction_query = ani.align_fraction_query;
+        diag.ci_lower = ani.ci_lower;
+        diag.ci_upper = ani.ci_upper;
+        diag.std = ani.std;
+        diag.total_bases_covered = ani.total_bases_covered;
+    }
+    if map_params.both_directions {
+        let other_params = MapParams {
+            both_directions: false,
+            switch_override: Some(!switched),
+            ..map_params
+        };
+        let other = chain_seeds(ref_sketch, query_sketch, other_params);
+        let this = DirectionalAni::from_result(&ani);
+        let other = DirectionalAni::from_result(&other);
+        ani.both_directions = if switched {
+            BothDirections::new(other, this)
+        } else {
+            BothDirections::new(this, other)
+        };
+    }
     ani
 }
 
@@ -177,8 +386,10 @@ fn calculate_ani(
     anchor_chunks: &AnchorChunks,
     map_params: &MapParams,
     switched: bool,
+    mut diagnostics: Option<&mut AniDiagnostics>,
 ) -> AniEstResult {
     let k = map_params.k;
+    let seed_span = map_params.seed_span as GnPosition;
     let mut ani_ests = vec![];
     let c = ref_sketch.c as GnPosition;
     let sensitive_af;
@@ -196,7 +407,9 @@ fn calculate_ani(
     let mut rightmost_interval = &ChainInterval::default();
     let mut avg_chain_int_len = 0;
     let mut num_chains = 0;
+    let mut frag_snps = vec![];
     for (i, intervals) in int_chunks.iter().enumerate() {
+        let bases_before = total_query_bases;
         let mut all_intervals = vec![].to_interval_set();
         let mut total_anchors = 0;
         let mut total_bases_contained_query = 0;
@@ -222,17 +435,17 @@ fn calculate_ani(
             }
             if !switched {
                 total_bases_contained_query += int.interval_on_query.1 - int.interval_on_query.0
-                    + map_params.k as GnPosition
+                    + seed_span
                     + 2 * c;
                 _total_bases_contained_ref += int.interval_on_ref.1 - int.interval_on_ref.0
-                    + map_params.k as GnPosition
+                    + seed_span
                     + 2 * c;
             } else {
                 total_bases_contained_query += int.interval_on_ref.1 - int.interval_on_ref.0
-                    + map_params.k as GnPosition
+                    + seed_span
                     + 2 * c;
                 _total_bases_contained_ref += int.interval_on_query.1 - int.interval_on_query.0
-                    + map_params.k as GnPosition
+                    + seed_span
                     + 2 * c;
             }
 
@@ -242,11 +455,11 @@ fn calculate_ani(
             all_intervals = all_intervals.union(&vec![(start, stop)].to_interval_set());
             //interval_vec.insert(int_insert, i);
             if sensitive_af{
-                total_query_bases +=  int.query_range_len() - int.overlap + 2 * c + k as GnPosition;
-                total_ref_range +=  int.query_range_len() - int.overlap + 2 * c + k as GnPosition;
+                total_query_bases +=  int.query_range_len() - int.overlap + 2 * c + seed_span;
+                total_ref_range +=  int.query_range_len() - int.overlap + 2 * c + seed_span;
             }
 
-            avg_chain_int_len += int.query_range_len() - int.overlap + 2 * c + k as GnPosition;
+            avg_chain_int_len += int.query_range_len() - int.overlap + 2 * c + seed_span;
             num_chains += 1;
         }
 
@@ -259,8 +472,8 @@ fn calculate_ani(
         }
 
         if !sensitive_af{
-            total_query_bases += total_range_query.1 - total_range_query.0 + 2 * c + map_params.k as GnPosition;
-            total_ref_range += total_range_query.1 - total_range_query.0 + 2 * c + map_params.k as GnPosition;
+            total_query_bases += total_range_query.1 - total_range_query.0 + 2 * c + seed_span;
+            total_ref_range += total_range_query.1 - total_range_query.0 + 2 * c + seed_span;
         }
 
         let mut num_seeds_in_intervals = 0;
@@ -394,6 +607,16 @@ fn calculate_ani(
             //ani_ests.push((ani_est, anchor_chunks.seeds_in_chunk[i].len()));
             ani_ests.push((ani_est, anchors_in_chunk_considered));
         }
+        frag_snps.push((ani_est, total_query_bases - bases_before));
+        if let Some(diag) = diagnostics.as_deref_mut(){
+            diag.fragments.push(FragmentDiagnostics {
+                chunk_id: i,
+                ani: ani_est,
+                weight: ani_ests[ani_ests.len() - 1].1,
+                total_anchors,
+                trimmed: false,
+            });
+        }
         //                        ani_ests.push((ani_est, upper_lower_seeds));
         trace!(
             "Ani est fragment {}, total range {:?}, total anchors {}, seeds in fragment {:?},",
@@ -469,18 +692,41 @@ fn calculate_ani(
     }
     //    let mut final_ani = weighted_avg / total_weight_interval as f64;
     let mut final_ani = weighted_avg / total_multiplicitiy as f64;
+    if let Some(diag) = diagnostics{
+        diag.trim_quantiles = (lower, upper);
+        //Same stable sort as ani_ests, so fragment i is ani_ests[i].
+        diag.fragments.sort_by(|x, y| (x.ani, x.weight).partial_cmp(&(y.ani, y.weight)).unwrap());
+        for (i, fragment) in diag.fragments.iter_mut().enumerate() {
+            fragment.trimmed = i < lower_i || i >= upper_i;
+        }
+        diag.trim_bounds = (ani_ests[lower_i].0, ani_ests[usize::max(upper_i, 1) - 1].0);
+    }
 
 //    let (upper, lower) = z_interval(&ani_ests);
-    let ci_std = bootstrap_interval(&ani_ests);
+    let ci_std = bootstrap_interval(
+        &ani_ests,
+        &map_params.ci_params,
+        &ci_rng(&map_params.ci_params, ref_sketch, query_sketch),
+    );
     let ci = (ci_std.0, ci_std.1);
     let std = ci_std.2;
+    let snps = if map_params.est_ci {
+        let (snps, lower, upper) = bootstrap_snp_interval(
+            &frag_snps,
+            &map_params.ci_params,
+            &ci_rng(&map_params.ci_params, ref_sketch, query_sketch),
+        );
+        SnpEstimate { snps, snps_lower: lower, snps_upper: upper, jc_distance: 0. }
+    } else {
+        SnpEstimate::default()
+    };
     let covered_query = f64::min(
         1.,
-        total_query_bases as f64 / query_sketch.total_sequence_length as f64,
+        total_query_bases as f64 / query_sketch.unmasked_length() as f64,
     );
     let covered_ref = f64::min(
         1.,
-        total_ref_range as f64 / ref_sketch.total_sequence_length as f64,
+        total_ref_range as f64 / ref_sketch.unmasked_length() as f64,
     );
     
     let q_string = &query_sketch.file_name;
@@ -533,8 +779,10 @@ fn calculate_ani(
         ani: final_ani as f32,
         align_fraction_query: covered_query as f32,
         align_fraction_ref: covered_ref as f32,
-        ref_file: ref_sketch.file_name.clone(),
-        query_file: query_sketch.file_name.clone(),
+        ref_file: ref_sketch.name().to_string(),
+        query_file: query_sketch.name().to_string(),
+        ref_path: ref_sketch.file_name.clone(),
+        query_path: query_sketch.file_name.clone(),
         query_contig: query_sketch.contigs[0].clone(),
         ref_contig: ref_sketch.contigs[0].clone(),
         num_contigs_r: ref_sketch.contigs.len() as u32,
@@ -551,6 +799,125 @@ fn calculate_ani(
         std: std as f32,
         avg_chain_int_len,
         total_bases_covered: total_query_bases,
+        synteny: if map_params.synteny { synteny_stats(int_chunks, seed_span) } else { SyntenyStats::default() },
+        ddh: DdhEstimate::default(),
+        indels: if map_params.indels { indel_stats(int_chunks, seed_span) } else { IndelStats::default() },
+        snps,
+        tetra: None,
+        ref_stats: GenomeStats::default(),
+        query_stats: GenomeStats::default(),
+        both_directions: BothDirections::default(),
+    }
+}
+
+fn indel_stats(int_chunks: &[Vec<ChainInterval>], seed_span: GnPosition) -> IndelStats {
+    let mut stats = IndelStats::default();
+    for int in int_chunks.iter().flatten() {
+        stats.indel_events += int.indel_events;
+        stats.indel_bases += int.indel_bases;
+        stats.aligned_bases += int.query_range_len() + seed_span;
+    }
+    stats
+}
+
+struct CollinearBlock {
+    query_contig: usize,
+    ref_contig: usize,
+    reverse: bool,
+    interval_on_query: (GnPosition, GnPosition),
+    interval_on_ref: (GnPosition, GnPosition),
+}
+
+/// Gap between the end of `block` and the start of `int` along the reference,
+/// in the direction of the block's strand. Negative if `int` goes backwards.
+fn ref_gap(block: &CollinearBlock, int: &ChainInterval) -> i64 {
+    if block.reverse {
+        block.interval_on_ref.0 as i64 - int.interval_on_ref.1 as i64
+    } else {
+        int.interval_on_ref.0 as i64 - block.interval_on_ref.1 as i64
+    }
+}
+
+/// Merge the orthologous chains into collinear blocks along each query contig. Two
+/// consecutive chains are collinear if they are on the same reference contig and strand,
+/// progress forward along the reference, and the query/reference gaps differ by at most
+/// SYNTENY_MAX_GAP. Blocks against the minority strand of their query contig are counted as
+/// inversions; consecutive same-strand blocks that jump to another reference contig or
+/// backwards along the reference are counted as translocation-like breakpoints.
+fn synteny_stats(int_chunks: &[Vec<ChainInterval>], seed_span: GnPosition) -> SyntenyStats {
+    let mut ints = int_chunks.iter().flatten().collect::<Vec<&ChainInterval>>();
+    if ints.is_empty() {
+        return SyntenyStats::default();
+    }
+    ints.sort_by_key(|x| (x.query_contig, x.interval_on_query.0));
+
+    let mut blocks: Vec<CollinearBlock> = vec![];
+    for int in ints {
+        if let Some(block) = blocks.last_mut() {
+            let q_gap = int.interval_on_query.0 as i64 - block.interval_on_query.1 as i64;
+            let r_gap = ref_gap(block, int);
+            if block.query_contig == int.query_contig
+                && block.ref_contig == int.ref_contig
+                && block.reverse == int.reverse_chain
+                && r_gap >= -SYNTENY_OVERLAP_TOL
+                && (r_gap - q_gap).abs() <= SYNTENY_MAX_GAP
+            {
+                block.interval_on_query.1 = GnPosition::max(block.interval_on_query.1, int.interval_on_query.1);
+                block.interval_on_ref.0 = GnPosition::min(block.interval_on_ref.0, int.interval_on_ref.0);
+                block.interval_on_ref.1 = GnPosition::max(block.interval_on_ref.1, int.interval_on_ref.1);
+                continue;
+            }
+        }
+        blocks.push(CollinearBlock {
+            query_contig: int.query_contig,
+            ref_contig: int.ref_contig,
+            reverse: int.reverse_chain,
+            interval_on_query: int.interval_on_query,
+            interval_on_ref: int.interval_on_ref,
+        });
+    }
+
+    let block_len = |b: &CollinearBlock| b.interval_on_query.1 - b.interval_on_query.0 + seed_span;
+    let mut strand_bases: FxHashMap<usize, (u64, u64)> = FxHashMap::default();
+    for block in blocks.iter() {
+        let bases = strand_bases.entry(block.query_contig).or_default();
+        if block.reverse {
+            bases.1 += block_len(block) as u64;
+        } else {
+            bases.0 += block_len(block) as u64;
+        }
+    }
+    let mut inversions = 0;
+    let mut breakpoints = 0;
+    for (i, block) in blocks.iter().enumerate() {
+        let (fwd, rev) = strand_bases[&block.query_contig];
+        let minority_is_reverse = fwd >= rev;
+        if block.reverse == minority_is_reverse {
+            inversions += 1;
+        }
+        if i == 0 {
+            continue;
+        }
+        let prev = &blocks[i - 1];
+        if prev.query_contig != block.query_contig || prev.reverse != block.reverse {
+            continue;
+        }
+        let backwards = if block.reverse {
+            block.interval_on_ref.1 as i64 > prev.interval_on_ref.0 as i64 + SYNTENY_OVERLAP_TOL
+        } else {
+            (block.interval_on_ref.0 as i64) < prev.interval_on_ref.1 as i64 - SYNTENY_OVERLAP_TOL
+        };
+        if prev.ref_contig != block.ref_contig || backwards {
+            breakpoints += 1;
+        }
+    }
+
+    let lens = blocks.iter().map(block_len).collect::<Vec<GnPosition>>();
+    SyntenyStats {
+        collinear_blocks: blocks.len() as u32,
+        inversions,
+        breakpoints,
+        block_n50: n50(&lens),
     }
 }
 
@@ -646,7 +1013,10 @@ fn get_anchors(
         query_length_markers_proxy = query_sketch.total_sequence_length as f64;
         ref_length_markers_proxy = ref_sketch.total_sequence_length as f64;
     }
-    if switch_qr(mean_ctg_len_r,mean_ctg_len_q, query_length_markers_proxy, ref_length_markers_proxy, &query_sketch.file_name, &ref_sketch.file_name){
+    let switch = map_params.switch_override.unwrap_or_else(|| {
+        switch_qr(mean_ctg_len_r,mean_ctg_len_q, query_length_markers_proxy, ref_length_markers_proxy, &query_sketch.file_name, &ref_sketch.file_name)
+    });
+    if switch {
         switched = true;
 
         kmer_seeds_ref = query_sketch.kmer_seeds_k.as_ref().unwrap();
@@ -663,12 +1033,13 @@ fn get_anchors(
     //    let kmer_seeds_query = &query_sketch.kmer_seeds_k[k];
     let mut anchors = vec![];
     let mut query_kmers_with_hits = 0;
-    for (canon_kmer, _query_tagged) in kmer_seeds_query.iter() {
+    let mut repeat_anchors_dropped = 0;
+    for canon_kmer in kmer_seeds_query.keys() {
         // Get query positions using the new API
         let query_positions_iter = if switched {
-            ref_sketch.get_seed_positions(*canon_kmer)
+            ref_sketch.get_seed_positions(canon_kmer)
         } else {
-            query_sketch.get_seed_positions(*canon_kmer)
+            query_sketch.get_seed_positions(canon_kmer)
         };
         
         if query_positions_iter.len() > map_params.index_chain_band{
@@ -679,6 +1050,18 @@ fn get_anchors(
         let query_positions = query_positions_iter;
         let contains = kmer_seeds_ref.contains_key(canon_kmer);
 
+        if ref_sketch.repeat_seeds.contains(&canon_kmer) || query_sketch.repeat_seeds.contains(&canon_kmer) {
+            if contains {
+                let ref_positions = if switched {
+                    query_sketch.get_seed_positions(canon_kmer)
+                } else {
+                    ref_sketch.get_seed_positions(canon_kmer)
+                };
+                repeat_anchors_dropped += query_positions.len() * ref_positions.len();
+            }
+            continue;
+        }
+
         if !contains {
             for qpos in query_positions.iter() {
                 query_positions_all[qpos.contig_index() as usize].push(qpos.pos);
@@ -686,9 +1069,9 @@ fn get_anchors(
         } else {
             // Get reference positions using the new API  
             let ref_positions = if switched {
-                query_sketch.get_seed_positions(*canon_kmer)
+                query_sketch.get_seed_positions(canon_kmer)
             } else {
-                ref_sketch.get_seed_positions(*canon_kmer)
+                ref_sketch.get_seed_positions(canon_kmer)
             };
 
             if ref_positions.len() > map_params.index_chain_band{
@@ -711,6 +1094,12 @@ fn get_anchors(
             }
         }
     }
+    if repeat_anchors_dropped > 0 {
+        debug!(
+            "Repeat-masked seeds dropped {} anchors, Ref_file {}, Query_file {}",
+            repeat_anchors_dropped, ref_sketch.file_name, query_sketch.file_name
+        );
+    }
     if anchors.is_empty() {
         debug!(
             "no anchors found for {}, {}",
@@ -967,8 +1356,18 @@ fn get_chain_intervals(
         }
 
         let mut index = best_index;
+        let mut indel_events = 0;
+        let mut indel_bases = 0;
         while cr.pointer_vec[index] != index {
-            index = cr.pointer_vec[index];
+            let next = cr.pointer_vec[index];
+            let d_q = anchors[index].query_pos as i64 - anchors[next].query_pos as i64;
+            let d_r = (anchors[index].ref_pos as i64 - anchors[next].ref_pos as i64).abs();
+            let gap = (d_r - d_q).unsigned_abs() as u32;
+            if gap != 0 {
+                indel_events += 1;
+                indel_bases += gap;
+            }
+            index = next;
             num_anchors += 1;
         }
         small_chain = num_anchors < map_params.min_anchors;
@@ -1000,7 +1399,9 @@ fn get_chain_intervals(
             num_anchors,
             chunk_id,
             reverse_chain: anchors[smallest_id].reverse_match,
-            overlap : 0
+            overlap : 0,
+            indel_events,
+            indel_bases,
         };
         good_intervals.push(chain_interval);
     }
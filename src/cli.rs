@@ -29,6 +29,22 @@ pub enum Commands {
     /// Search queries against a large pre-sketched database of reference genomes in a memory efficient manner.
     /// Usage: skani search -d sketch_folder query1.fa query2.fa ...
     Search(SearchArgs),
+
+    /// Sliding-window ANI and coverage of queries along a reference genome (bedGraph-like output).
+    /// Usage: skani profile reference.fa query1.fa query2.fa ...
+    Profile(ProfileArgs),
+
+    /// Dump the intermediate steps (screening, anchors, chains, fragment ANIs) for a single pair as JSON.
+    /// Usage: skani explain query.fa reference.fa
+    Explain(ExplainArgs),
+
+    /// Render an SVG dot plot of the chains between a query and a reference.
+    /// Usage: skani dotplot query.fa reference.fa -o plot.svg
+    Dotplot(DotplotArgs),
+
+    /// Per-genome statistics (length, contigs, N50, GC content, ambiguous bases) as a TSV.
+    /// Usage: skani inspect genome1.fa sketch_folder genome2.fa.sketch ...
+    Inspect(InspectArgs),
 }
 
 #[derive(Args)]
@@ -41,7 +57,7 @@ pub struct SketchArgs {
     #[clap(short = 't', default_value = "3")]
     pub threads: String,
 
-    /// fastas to sketch
+    /// fastas to sketch; '-' reads one from stdin
     #[clap(help_heading = "INPUT/OUTPUT", group = "input_group")]
     pub fasta_files: Vec<String>,
     
@@ -49,11 +65,15 @@ pub struct SketchArgs {
     #[clap(short = 'l', help_heading = "INPUT/OUTPUT", group = "input_group")]
     pub fasta_list: Option<String>,
     
+    /// TSV with each line `genome_name<TAB>file1,file2,...`; the files of a genome are sketched together and output under its name
+    #[clap(long = "groups", help_heading = "INPUT/OUTPUT", group = "input_group")]
+    pub groups: Option<String>,
+    
     /// Use individual sequences instead the entire file for multi-fastas. 
     #[clap(short = 'i', help_heading = "INPUT/OUTPUT")]
     pub individual_contig: bool,
     
-    /// Output folder where sketch files are placed. 
+    /// Output folder where sketch files are placed, or '-' to write the sketch of a single genome to stdout (the --separate-sketches format).
     #[clap(short = 'o', required = true, display_order = 1, help_heading = "INPUT/OUTPUT")]
     pub output: String,
 
@@ -69,6 +89,10 @@ pub struct SketchArgs {
     #[clap(long = "fast", help_heading = "PRESETS")]
     pub fast: bool,
 
+    /// Genus-level mode for comparing genomes at ~75-85% ANI, where default skani often gives no result or a very low AF. Uses a lower-weight spaced seed instead of contiguous k-mers. Alias for --seed-pattern 110110101011001111 -c 70.
+    #[clap(long = "genus", help_heading = "PRESETS")]
+    pub genus: bool,
+
     /// Create separate .sketch files instead of consolidated database format. DOES NOT WORK WITH -i. 
     #[clap(long = "separate-sketches", help_heading = "INPUT/OUTPUT")]
     pub separate_sketches: bool,
@@ -77,9 +101,45 @@ pub struct SketchArgs {
     #[clap(short = 'a', long = "aai", hide = true, help_heading = "SKETCH PARAMETERS")]
     pub aai: bool,
     
+    /// With --aai, translate the annotated CDSs of GenBank/EMBL inputs instead of predicted ORFs
+    #[clap(long = "annotated-cds", hide = true, help_heading = "SKETCH PARAMETERS")]
+    pub annotated_cds: bool,
+
+    /// Minimum contig length to sketch. Shorter contigs do not count towards aligned fractions. [default: 500]
+    #[clap(long = "min-contig-length", help_heading = "SKETCH PARAMETERS")]
+    pub min_contig_length: Option<usize>,
+
+    /// Minimum length of GFA segments to sketch. [default: --min-contig-length]
+    #[clap(long = "min-segment-length", help_heading = "SKETCH PARAMETERS")]
+    pub min_segment_length: Option<usize>,
+
+    /// Only sketch contigs whose header matches this regex
+    #[clap(long = "include-contigs", help_heading = "SKETCH PARAMETERS")]
+    pub include_contigs: Option<String>,
+
+    /// Do not sketch contigs whose header matches this regex
+    #[clap(long = "exclude-contigs", help_heading = "SKETCH PARAMETERS")]
+    pub exclude_contigs: Option<String>,
+
+    /// Skip seeds overlapping soft-masked (lowercase) bases; masked bases do not count towards aligned fractions
+    #[clap(long = "soft-mask", help_heading = "SKETCH PARAMETERS")]
+    pub soft_mask: bool,
+
+    /// BED file of regions to exclude from seeds and aligned fractions (contig names match the first word of headers)
+    #[clap(long = "mask-bed", help_heading = "SKETCH PARAMETERS")]
+    pub mask_bed: Option<String>,
+
+    /// Mask seeds occurring more than N times in a genome, or pick N per genome with 'auto'. Masked seeds do not form anchors
+    #[clap(long = "repeat-mask", help_heading = "SKETCH PARAMETERS")]
+    pub repeat_mask: Option<String>,
+    
     /// k-mer size. [default: 15]
     #[clap(short = 'k', hide = true, help_heading = "SKETCH PARAMETERS")]
     pub k: Option<String>,
+
+    /// Spaced seed pattern of 0s and 1s, e.g. 110110101011001111. 1s are the positions used for seeding; at most 16 of them and 21 positions in total. Overrides -k. [default: contiguous k-mers]
+    #[clap(long = "seed-pattern", help_heading = "SKETCH PARAMETERS")]
+    pub seed_pattern: Option<String>,
     
     /// Compression factor (k-mer subsampling rate). [default: 125]
     #[clap(short = 'c', help_heading = "SKETCH PARAMETERS")]
@@ -89,6 +149,10 @@ pub struct SketchArgs {
     #[clap(short = 'm', help_heading = "SKETCH PARAMETERS")]
     pub marker_c: Option<String>,
 
+    /// Marker k-mer length, up to 32. Must be at least k (or the seed pattern length). Sketches with different marker k can not be compared. [default: 21]
+    #[clap(long = "marker-k", help_heading = "SKETCH PARAMETERS")]
+    pub marker_k: Option<String>,
+
     /// Debug level verbosity
     #[clap(short = 'v', long = "debug", help_heading = "MISC")]
     pub debug: bool,
@@ -112,19 +176,51 @@ pub struct DistArgs {
     #[clap(short = 'a', long = "aai", hide = true, help_heading = "INPUTS")]
     pub aai: bool,
     
-    /// Query fasta or sketch
+    /// With --aai, translate the annotated CDSs of GenBank/EMBL inputs instead of predicted ORFs
+    #[clap(long = "annotated-cds", hide = true, help_heading = "INPUTS")]
+    pub annotated_cds: bool,
+
+    /// Minimum contig length to sketch. Shorter contigs do not count towards aligned fractions. [default: 500]
+    #[clap(long = "min-contig-length", help_heading = "INPUTS")]
+    pub min_contig_length: Option<usize>,
+
+    /// Minimum length of GFA segments to sketch. [default: --min-contig-length]
+    #[clap(long = "min-segment-length", help_heading = "INPUTS")]
+    pub min_segment_length: Option<usize>,
+
+    /// Only sketch contigs whose header matches this regex
+    #[clap(long = "include-contigs", help_heading = "INPUTS")]
+    pub include_contigs: Option<String>,
+
+    /// Do not sketch contigs whose header matches this regex
+    #[clap(long = "exclude-contigs", help_heading = "INPUTS")]
+    pub exclude_contigs: Option<String>,
+
+    /// Skip seeds overlapping soft-masked (lowercase) bases; masked bases do not count towards aligned fractions
+    #[clap(long = "soft-mask", help_heading = "INPUTS")]
+    pub soft_mask: bool,
+
+    /// BED file of regions to exclude from seeds and aligned fractions (contig names match the first word of headers)
+    #[clap(long = "mask-bed", help_heading = "INPUTS")]
+    pub mask_bed: Option<String>,
+
+    /// Mask seeds occurring more than N times in a genome, or pick N per genome with 'auto'. Masked seeds do not form anchors
+    #[clap(long = "repeat-mask", help_heading = "INPUTS")]
+    pub repeat_mask: Option<String>,
+    
+    /// Query fasta or sketch; '-' reads from stdin
     #[clap(help_heading = "INPUTS", group = "query_group")]
     pub query: Option<String>,
     
-    /// Reference fasta(s) or sketch(es)
+    /// Reference fasta(s) or sketch(es); '-' reads one from stdin
     #[clap(help_heading = "INPUTS")]
     pub reference: Vec<String>,
     
-    /// Query fasta(s) or sketch(es)
+    /// Query fasta(s) or sketch(es); '-' reads one from stdin
     #[clap(short = 'q', multiple_values = true, help_heading = "INPUTS", group = "query_group")]
     pub queries: Vec<String>,
     
-    /// Reference fasta(s) or sketch(es)
+    /// Reference fasta(s) or sketch(es); '-' reads one from stdin
     #[clap(short = 'r', multiple_values = true, help_heading = "INPUTS")]
     pub references: Vec<String>,
     
@@ -132,10 +228,18 @@ pub struct DistArgs {
     #[clap(long = "rl", help_heading = "INPUTS")]
     pub reference_list: Option<String>,
     
+    /// Reference TSV with each line `genome_name<TAB>file1,file2,...`; the files of a genome are sketched together and output under its name
+    #[clap(long = "rg", help_heading = "INPUTS")]
+    pub reference_groups: Option<String>,
+    
     /// File with each line containing one fasta/sketch file
     #[clap(long = "ql", help_heading = "INPUTS", group = "query_group")]
     pub query_list: Option<String>,
     
+    /// Query TSV with each line `genome_name<TAB>file1,file2,...`; the files of a genome are sketched together and output under its name
+    #[clap(long = "qg", help_heading = "INPUTS", group = "query_group")]
+    pub query_groups: Option<String>,
+    
     /// Use individual sequences for the QUERY in a multi-line fasta
     #[clap(long = "qi", help_heading = "INPUTS")]
     pub qi: bool,
@@ -160,9 +264,21 @@ pub struct DistArgs {
     #[clap(short = 'n', help_heading = "OUTPUT")]
     pub n: Option<String>,
     
-    /// Output [5%,95%] ANI confidence intervals using percentile bootstrap on the putative ANI distribution
+    /// Output [5%,95%] ANI confidence intervals from the putative ANI distribution of fragments (see --ci-method)
     #[clap(long = "ci", help_heading = "OUTPUT")]
     pub ci: bool,
+
+    /// Method for --ci intervals: percentile bootstrap over fragment ANIs, or a fast analytic interval from the weighted variance of fragment ANIs. [default: bootstrap]
+    #[clap(long = "ci-method", possible_values = &["bootstrap", "analytic"], help_heading = "OUTPUT")]
+    pub ci_method: Option<String>,
+
+    /// Number of bootstrap replicates for --ci. [default: 100]
+    #[clap(long = "ci-replicates", help_heading = "OUTPUT")]
+    pub ci_replicates: Option<String>,
+
+    /// Seed for --ci bootstrap. Each pair gets its own stream derived from this seed. [default: fixed seed shared by all pairs]
+    #[clap(long = "ci-seed", help_heading = "OUTPUT")]
+    pub ci_seed: Option<String>,
     
     /// Print additional info including contig N50s and more
     #[clap(long = "detailed", help_heading = "OUTPUT")]
@@ -172,6 +288,38 @@ pub struct DistArgs {
     #[clap(long = "short-header", help_heading = "OUTPUT")]
     pub short_header: bool,
 
+    /// Output rearrangement columns: number of collinear blocks, inversions, translocation-like breakpoints, and collinear block N50
+    #[clap(long = "synteny", help_heading = "OUTPUT")]
+    pub synteny: bool,
+
+    /// Output GGDC-style dDDH estimates (formulas d0, d4, d6) and a [5%,95%] interval for d4. Approximate; see README
+    #[clap(long = "ddh", help_heading = "OUTPUT")]
+    pub ddh: bool,
+
+    /// Output the estimated indel rate per aligned kb and an indel-adjusted (gap-aware) ANI, from offset differences between consecutive anchors in chains
+    #[clap(long = "indels", help_heading = "OUTPUT")]
+    pub indels: bool,
+
+    /// Output the expected SNP count over aligned bases with [5%,95%] bounds, and the Jukes-Cantor corrected substitutions per site. With --ci, SNPs are summed over fragments and bootstrapped
+    #[clap(long = "snps", help_heading = "OUTPUT")]
+    pub snps: bool,
+
+    /// Output tetranucleotide Z-score correlation (TETRA). Pairs without an ANI (e.g. failing the screen) are also reported, with NaN ANI/AF
+    #[clap(long = "tetra", help_heading = "OUTPUT")]
+    pub tetra: bool,
+
+    /// Output length, N50, GC content and ambiguous (non-ACGT) base counts of both genomes
+    #[clap(long = "genome-stats", help_heading = "OUTPUT")]
+    pub genome_stats: bool,
+
+    /// Chain in both directions (query onto reference and reference onto query) instead of letting skani pick one. Outputs ANI/AF for each direction and their aligned-bases-weighted mean (ANI_symmetric). About 2x slower
+    #[clap(long = "both-directions", help_heading = "OUTPUT")]
+    pub both_directions: bool,
+
+    /// Skip chaining; estimate max-containment ANI from shared marker k-mers only. Orders of magnitude faster but much less accurate, especially for fragmented or incomplete genomes. Output columns are labelled ANI_markers. --ci gives a binomial interval
+    #[clap(long = "markers-only", help_heading = "PRESETS")]
+    pub markers_only: bool,
+
     /// Slower skani mode; 4x slower and more memory. Gives much more accurate AF for distant genomes. More accurate ANI for VERY fragmented assemblies (< 3kb N50), but less accurate ANI otherwise. Alias for -c 30.
     #[clap(long = "slow", help_heading = "PRESETS")]
     pub slow: bool,
@@ -183,6 +331,10 @@ pub struct DistArgs {
     /// Faster skani mode; 2x faster and less memory. Less accurate AF and less accurate ANI for distant genomes, but works ok for high N50 and > 95% ANI. Alias for -c 200.
     #[clap(long = "fast", help_heading = "PRESETS")]
     pub fast: bool,
+
+    /// Genus-level mode for comparing genomes at ~75-85% ANI, where default skani often gives no result or a very low AF. Uses a lower-weight spaced seed instead of contiguous k-mers and a lower screening cutoff. Alias for --seed-pattern 110110101011001111 -c 70 -s 70 --no-learned-ani.
+    #[clap(long = "genus", help_heading = "PRESETS")]
+    pub genus: bool,
     
     /// Mode for small genomes such as viruses or plasmids (< 20 kb). Can be much faster for large data, but is slower/less accurate on bacterial-sized genomes. Alias for: -c 30 -m 200 --faster-small.
     #[clap(long = "small-genomes", help_heading = "PRESETS")]
@@ -195,10 +347,18 @@ pub struct DistArgs {
     /// Marker k-mer compression factor. Markers are used for filtering. Consider decreasing to ~200-300 if working with small genomes (e.g. plasmids or viruses). [default: 1000]
     #[clap(short = 'm', help_heading = "ALGORITHM PARAMETERS")]
     pub marker_c: Option<String>,
+
+    /// Marker k-mer length, up to 32. Must be at least k (or the seed pattern length). Sketches with different marker k can not be compared. [default: 21]
+    #[clap(long = "marker-k", help_heading = "ALGORITHM PARAMETERS")]
+    pub marker_k: Option<String>,
     
     /// k-mer size. [default: 15]
     #[clap(short = 'k', hide = true, help_heading = "ALGORITHM PARAMETERS")]
     pub k: Option<String>,
+
+    /// Spaced seed pattern of 0s and 1s, e.g. 110110101011001111. 1s are the positions used for seeding; at most 16 of them and 21 positions in total. Overrides -k. [default: contiguous k-mers]
+    #[clap(long = "seed-pattern", help_heading = "ALGORITHM PARAMETERS")]
+    pub seed_pattern: Option<String>,
     
     /// Compression factor (k-mer subsampling rate). [default: 125]
     #[clap(short = 'c', help_heading = "ALGORITHM PARAMETERS")]
@@ -247,11 +407,47 @@ pub struct TriangleArgs {
     #[clap(short = 'l', help_heading = "INPUTS", group = "input_group")]
     pub fasta_list: Option<String>,
     
+    /// TSV with each line `genome_name<TAB>file1,file2,...`; the files of a genome are sketched together and output under its name
+    #[clap(long = "groups", help_heading = "INPUTS", group = "input_group")]
+    pub groups: Option<String>,
+    
     /// Use amino acid to calculate AAI instead. [default: ANI]
     #[clap(short = 'a', long = "aai", hide = true, help_heading = "INPUTS")]
     pub aai: bool,
     
-    /// Fasta(s) or sketch(es)
+    /// With --aai, translate the annotated CDSs of GenBank/EMBL inputs instead of predicted ORFs
+    #[clap(long = "annotated-cds", hide = true, help_heading = "INPUTS")]
+    pub annotated_cds: bool,
+
+    /// Minimum contig length to sketch. Shorter contigs do not count towards aligned fractions. [default: 500]
+    #[clap(long = "min-contig-length", help_heading = "INPUTS")]
+    pub min_contig_length: Option<usize>,
+
+    /// Minimum length of GFA segments to sketch. [default: --min-contig-length]
+    #[clap(long = "min-segment-length", help_heading = "INPUTS")]
+    pub min_segment_length: Option<usize>,
+
+    /// Only sketch contigs whose header matches this regex
+    #[clap(long = "include-contigs", help_heading = "INPUTS")]
+    pub include_contigs: Option<String>,
+
+    /// Do not sketch contigs whose header matches this regex
+    #[clap(long = "exclude-contigs", help_heading = "INPUTS")]
+    pub exclude_contigs: Option<String>,
+
+    /// Skip seeds overlapping soft-masked (lowercase) bases; masked bases do not count towards aligned fractions
+    #[clap(long = "soft-mask", help_heading = "INPUTS")]
+    pub soft_mask: bool,
+
+    /// BED file of regions to exclude from seeds and aligned fractions (contig names match the first word of headers)
+    #[clap(long = "mask-bed", help_heading = "INPUTS")]
+    pub mask_bed: Option<String>,
+
+    /// Mask seeds occurring more than N times in a genome, or pick N per genome with 'auto'. Masked seeds do not form anchors
+    #[clap(long = "repeat-mask", help_heading = "INPUTS")]
+    pub repeat_mask: Option<String>,
+    
+    /// Fasta(s) or sketch(es); '-' reads one from stdin
     #[clap(help_heading = "INPUTS", group = "input_group")]
     pub fasta_files: Vec<String>,
     
@@ -279,9 +475,21 @@ pub struct TriangleArgs {
     #[clap(long = "both-min-af", help_heading = "OUTPUT")]
     pub both_min_af: Option<String>,
     
-    /// Output [5%,95%] ANI confidence intervals using percentile bootstrap on the putative ANI distribution. Only works with --sparse or -E.
+    /// Output [5%,95%] ANI confidence intervals from the putative ANI distribution of fragments (see --ci-method). Only works with --sparse or -E.
     #[clap(long = "ci", help_heading = "OUTPUT")]
     pub ci: bool,
+
+    /// Method for --ci intervals: percentile bootstrap over fragment ANIs, or a fast analytic interval from the weighted variance of fragment ANIs. [default: bootstrap]
+    #[clap(long = "ci-method", possible_values = &["bootstrap", "analytic"], help_heading = "OUTPUT")]
+    pub ci_method: Option<String>,
+
+    /// Number of bootstrap replicates for --ci. [default: 100]
+    #[clap(long = "ci-replicates", help_heading = "OUTPUT")]
+    pub ci_replicates: Option<String>,
+
+    /// Seed for --ci bootstrap. Each pair gets its own stream derived from this seed. [default: fixed seed shared by all pairs]
+    #[clap(long = "ci-seed", help_heading = "OUTPUT")]
+    pub ci_seed: Option<String>,
     
     /// Print additional info including contig N50s and more
     #[clap(long = "detailed", help_heading = "OUTPUT")]
@@ -290,6 +498,38 @@ pub struct TriangleArgs {
     /// Only display the first part of contig names (before first whitespace)
     #[clap(long = "short-header", help_heading = "OUTPUT")]
     pub short_header: bool,
+
+    /// Output rearrangement columns: number of collinear blocks, inversions, translocation-like breakpoints, and collinear block N50
+    #[clap(long = "synteny", help_heading = "OUTPUT")]
+    pub synteny: bool,
+
+    /// Output GGDC-style dDDH estimates (formulas d0, d4, d6) and a [5%,95%] interval for d4. Approximate; see README
+    #[clap(long = "ddh", help_heading = "OUTPUT")]
+    pub ddh: bool,
+
+    /// Output the estimated indel rate per aligned kb and an indel-adjusted (gap-aware) ANI, from offset differences between consecutive anchors in chains
+    #[clap(long = "indels", help_heading = "OUTPUT")]
+    pub indels: bool,
+
+    /// Output the expected SNP count over aligned bases with [5%,95%] bounds, and the Jukes-Cantor corrected substitutions per site. With --ci, SNPs are summed over fragments and bootstrapped
+    #[clap(long = "snps", help_heading = "OUTPUT")]
+    pub snps: bool,
+
+    /// Output tetranucleotide Z-score correlation (TETRA). Pairs without an ANI (e.g. failing the screen) are also reported with NaN ANI/AF; this outputs all pairs. Only works with --sparse or -E
+    #[clap(long = "tetra", help_heading = "OUTPUT")]
+    pub tetra: bool,
+
+    /// Output length, N50, GC content and ambiguous (non-ACGT) base counts of both genomes
+    #[clap(long = "genome-stats", help_heading = "OUTPUT")]
+    pub genome_stats: bool,
+
+    /// Chain in both directions (query onto reference and reference onto query) instead of letting skani pick one. Outputs ANI/AF for each direction and their aligned-bases-weighted mean (ANI_symmetric). About 2x slower
+    #[clap(long = "both-directions", help_heading = "OUTPUT")]
+    pub both_directions: bool,
+
+    /// Skip chaining; estimate max-containment ANI from shared marker k-mers only. Orders of magnitude faster but much less accurate, especially for fragmented or incomplete genomes. Output columns are labelled ANI_markers. --ci gives a binomial interval
+    #[clap(long = "markers-only", help_heading = "PRESETS")]
+    pub markers_only: bool,
     
     /// Output 100 - ANI instead of ANI, creating a distance instead of a similarity matrix. No effect if using --sparse or -E.
     #[clap(long = "distance", help_heading = "OUTPUT")]
@@ -299,6 +539,18 @@ pub struct TriangleArgs {
     #[clap(long = "sparse", short = 'E', help_heading = "OUTPUT")]
     pub sparse: bool,
 
+    /// Also write a heatmap of the ANI matrix, ordered by average-linkage clustering, to this SVG file
+    #[clap(long = "heatmap", help_heading = "OUTPUT")]
+    pub heatmap: Option<String>,
+
+    /// Draw genome names on the heatmap (only for <= 500 genomes)
+    #[clap(long = "heatmap-labels", help_heading = "OUTPUT")]
+    pub heatmap_labels: bool,
+
+    /// Draw the aligned fraction of the row genome in the upper triangle of the heatmap
+    #[clap(long = "heatmap-af", help_heading = "OUTPUT")]
+    pub heatmap_af: bool,
+
     /// Slower skani mode; 4x slower and more memory. Gives much more accurate AF for distant genomes. More accurate ANI for VERY fragmented assemblies (< 3kb N50), but less accurate ANI otherwise. Alias for -c 30.
     #[clap(long = "slow", help_heading = "PRESETS")]
     pub slow: bool,
@@ -310,6 +562,10 @@ pub struct TriangleArgs {
     /// Faster skani mode; 2x faster and less memory. Less accurate AF and less accurate ANI for distant genomes, but works ok for high N50 and > 95% ANI. Alias for -c 200.
     #[clap(long = "fast", help_heading = "PRESETS")]
     pub fast: bool,
+
+    /// Genus-level mode for comparing genomes at ~75-85% ANI, where default skani often gives no result or a very low AF. Uses a lower-weight spaced seed instead of contiguous k-mers and a lower screening cutoff. Alias for --seed-pattern 110110101011001111 -c 70 -s 70 --no-learned-ani.
+    #[clap(long = "genus", help_heading = "PRESETS")]
+    pub genus: bool,
     
     /// Mode for small genomes such as viruses or plasmids (< 20 kb). Can be much faster for large data, but is slower/less accurate on bacterial-sized genomes. Alias for: -c 30 -m 200 --faster-small.
     #[clap(long = "small-genomes", help_heading = "PRESETS")]
@@ -322,6 +578,10 @@ pub struct TriangleArgs {
     /// Marker k-mer compression factor. Markers are used for filtering. Consider decreasing to ~200-300 if working with small genomes (e.g. plasmids or viruses). [default: 1000]
     #[clap(short = 'm', help_heading = "ALGORITHM PARAMETERS")]
     pub marker_c: Option<String>,
+
+    /// Marker k-mer length, up to 32. Must be at least k (or the seed pattern length). Sketches with different marker k can not be compared. [default: 21]
+    #[clap(long = "marker-k", help_heading = "ALGORITHM PARAMETERS")]
+    pub marker_k: Option<String>,
     
     /// Screen out pairs with *approximately* < % identity using k-mer sketching. [default: 80]
     #[clap(short = 's', help_heading = "ALGORITHM PARAMETERS")]
@@ -330,6 +590,10 @@ pub struct TriangleArgs {
     /// k-mer size. [default: 15]
     #[clap(short = 'k', hide = true, help_heading = "ALGORITHM PARAMETERS")]
     pub k: Option<String>,
+
+    /// Spaced seed pattern of 0s and 1s, e.g. 110110101011001111. 1s are the positions used for seeding; at most 16 of them and 21 positions in total. Overrides -k. [default: contiguous k-mers]
+    #[clap(long = "seed-pattern", help_heading = "ALGORITHM PARAMETERS")]
+    pub seed_pattern: Option<String>,
     
     /// Compression factor (k-mer subsampling rate). [default: 125]
     #[clap(short = 'c', help_heading = "ALGORITHM PARAMETERS")]
@@ -370,11 +634,11 @@ pub struct SearchArgs {
     #[clap(short = 'd', required = true, help_heading = "INPUTS")]
     pub database: String,
     
-    /// Query fasta(s) or sketch(es)
+    /// Query fasta(s) or sketch(es); '-' reads one from stdin
     #[clap(multiple_values = true, help_heading = "INPUTS", group = "query_group")]
     pub query: Vec<String>,
     
-    /// Query fasta(s) or sketch(es)
+    /// Query fasta(s) or sketch(es); '-' reads one from stdin
     #[clap(short = 'q', multiple_values = true, help_heading = "INPUTS", group = "query_group")]
     pub queries: Vec<String>,
     
@@ -382,17 +646,45 @@ pub struct SearchArgs {
     #[clap(long = "ql", help_heading = "INPUTS", group = "query_group")]
     pub query_list: Option<String>,
     
+    /// Query TSV with each line `genome_name<TAB>file1,file2,...`; the files of a genome are sketched together and output under its name
+    #[clap(long = "qg", help_heading = "INPUTS", group = "query_group")]
+    pub query_groups: Option<String>,
+    
     /// Use individual sequences for the QUERY in a multi-line fasta
     #[clap(long = "qi", help_heading = "INPUTS")]
     pub qi: bool,
 
+    /// Minimum contig length for sketching query fastas. [default: the database's value]
+    #[clap(long = "min-contig-length", help_heading = "INPUTS")]
+    pub min_contig_length: Option<usize>,
+
+    /// Only sketch query contigs whose header matches this regex
+    #[clap(long = "include-contigs", help_heading = "INPUTS")]
+    pub include_contigs: Option<String>,
+
+    /// Do not sketch query contigs whose header matches this regex
+    #[clap(long = "exclude-contigs", help_heading = "INPUTS")]
+    pub exclude_contigs: Option<String>,
+
     /// Output file name; rewrites file by default [default: output to stdout]
     #[clap(short = 'o', display_order = 1, help_heading = "OUTPUT")]
     pub output: Option<String>,
     
-    /// Output [5%,95%] ANI confidence intervals using percentile bootstrap on the putative ANI distribution
+    /// Output [5%,95%] ANI confidence intervals from the putative ANI distribution of fragments (see --ci-method)
     #[clap(long = "ci", help_heading = "OUTPUT")]
     pub ci: bool,
+
+    /// Method for --ci intervals: percentile bootstrap over fragment ANIs, or a fast analytic interval from the weighted variance of fragment ANIs. [default: bootstrap]
+    #[clap(long = "ci-method", possible_values = &["bootstrap", "analytic"], help_heading = "OUTPUT")]
+    pub ci_method: Option<String>,
+
+    /// Number of bootstrap replicates for --ci. [default: 100]
+    #[clap(long = "ci-replicates", help_heading = "OUTPUT")]
+    pub ci_replicates: Option<String>,
+
+    /// Seed for --ci bootstrap. Each pair gets its own stream derived from this seed. [default: fixed seed shared by all pairs]
+    #[clap(long = "ci-seed", help_heading = "OUTPUT")]
+    pub ci_seed: Option<String>,
     
     /// Print additional info including contig N50s and more
     #[clap(long = "detailed", help_heading = "OUTPUT")]
@@ -401,6 +693,38 @@ pub struct SearchArgs {
     /// Only display the first part of contig names (before first whitespace)
     #[clap(long = "short-header", help_heading = "OUTPUT")]
     pub short_header: bool,
+
+    /// Output rearrangement columns: number of collinear blocks, inversions, translocation-like breakpoints, and collinear block N50
+    #[clap(long = "synteny", help_heading = "OUTPUT")]
+    pub synteny: bool,
+
+    /// Output GGDC-style dDDH estimates (formulas d0, d4, d6) and a [5%,95%] interval for d4. Approximate; see README
+    #[clap(long = "ddh", help_heading = "OUTPUT")]
+    pub ddh: bool,
+
+    /// Output the estimated indel rate per aligned kb and an indel-adjusted (gap-aware) ANI, from offset differences between consecutive anchors in chains
+    #[clap(long = "indels", help_heading = "OUTPUT")]
+    pub indels: bool,
+
+    /// Output the expected SNP count over aligned bases with [5%,95%] bounds, and the Jukes-Cantor corrected substitutions per site. With --ci, SNPs are summed over fragments and bootstrapped
+    #[clap(long = "snps", help_heading = "OUTPUT")]
+    pub snps: bool,
+
+    /// Output tetranucleotide Z-score correlation (TETRA) for reported pairs
+    #[clap(long = "tetra", help_heading = "OUTPUT")]
+    pub tetra: bool,
+
+    /// Output length, N50, GC content and ambiguous (non-ACGT) base counts of both genomes
+    #[clap(long = "genome-stats", help_heading = "OUTPUT")]
+    pub genome_stats: bool,
+
+    /// Chain in both directions (query onto reference and reference onto query) instead of letting skani pick one. Outputs ANI/AF for each direction and their aligned-bases-weighted mean (ANI_symmetric). About 2x slower
+    #[clap(long = "both-directions", help_heading = "OUTPUT")]
+    pub both_directions: bool,
+
+    /// Skip chaining; estimate max-containment ANI from shared marker k-mers only. Orders of magnitude faster but much less accurate, especially for fragmented or incomplete genomes. Output columns are labelled ANI_markers. --ci gives a binomial interval
+    #[clap(long = "markers-only", help_heading = "PRESETS")]
+    pub markers_only: bool,
     
     /// Only output ANI values where one genome has aligned fraction > than this value. [default: 15]
     #[clap(long = "min-af", help_heading = "OUTPUT")]
@@ -446,3 +770,231 @@ pub struct SearchArgs {
     #[clap(long = "trace", help_heading = "MISC")]
     pub trace: bool,
 }
+
+#[derive(Args)]
+#[clap(group(
+    clap::ArgGroup::new("query_group")
+        .required(true)
+))]
+pub struct ProfileArgs {
+    /// Number of threads
+    #[clap(short = 't', default_value = "3")]
+    pub threads: String,
+
+    /// Reference fasta or sketch. Windows are reported in this genome's coordinates
+    #[clap(required = true, help_heading = "INPUTS")]
+    pub reference: String,
+
+    /// Query fasta(s) or sketch(es)
+    #[clap(help_heading = "INPUTS", group = "query_group")]
+    pub query: Vec<String>,
+
+    /// File with each line containing one fasta/sketch file
+    #[clap(long = "ql", help_heading = "INPUTS", group = "query_group")]
+    pub query_list: Option<String>,
+
+    /// Output file name; rewrites file by default [default: output to stdout]
+    #[clap(short = 'o', display_order = 1, help_heading = "OUTPUT")]
+    pub output: Option<String>,
+
+    /// Window size in bp. [default: 10000]
+    #[clap(short = 'w', long = "window", help_heading = "OUTPUT")]
+    pub window: Option<String>,
+
+    /// Distance in bp between the starts of consecutive windows. [default: window size]
+    #[clap(long = "step", help_heading = "OUTPUT")]
+    pub step: Option<String>,
+
+    /// Slower skani mode; 4x slower and more memory. Gives much more accurate AF for distant genomes. More accurate ANI for VERY fragmented assemblies (< 3kb N50), but less accurate ANI otherwise. Alias for -c 30.
+    #[clap(long = "slow", help_heading = "PRESETS")]
+    pub slow: bool,
+
+    /// Medium skani mode; 2x slower and more memory. More accurate AF and more accurate ANI for moderately fragmented assemblies (< 10kb N50). Alias for -c 70.
+    #[clap(long = "medium", help_heading = "PRESETS")]
+    pub medium: bool,
+
+    /// Faster skani mode; 2x faster and less memory. Less accurate AF and less accurate ANI for distant genomes, but works ok for high N50 and > 95% ANI. Alias for -c 200.
+    #[clap(long = "fast", help_heading = "PRESETS")]
+    pub fast: bool,
+
+    /// Marker k-mer compression factor. Markers are used for filtering. Consider decreasing to ~200-300 if working with small genomes (e.g. plasmids or viruses). [default: 1000]
+    #[clap(short = 'm', help_heading = "ALGORITHM PARAMETERS")]
+    pub marker_c: Option<String>,
+
+    /// k-mer size. [default: 15]
+    #[clap(short = 'k', hide = true, help_heading = "ALGORITHM PARAMETERS")]
+    pub k: Option<String>,
+
+    /// Compression factor (k-mer subsampling rate). Smaller values give less noisy windows. [default: 125]
+    #[clap(short = 'c', help_heading = "ALGORITHM PARAMETERS")]
+    pub c: Option<String>,
+
+    /// Debug level verbosity
+    #[clap(short = 'v', long = "debug", help_heading = "MISC")]
+    pub debug: bool,
+
+    /// Trace level verbosity
+    #[clap(long = "trace", help_heading = "MISC")]
+    pub trace: bool,
+}
+
+#[derive(Args)]
+pub struct ExplainArgs {
+    /// Query fasta or sketch
+    #[clap(required = true, help_heading = "INPUTS")]
+    pub query: String,
+
+    /// Reference fasta or sketch
+    #[clap(required = true, help_heading = "INPUTS")]
+    pub reference: String,
+
+    /// Output file name; rewrites file by default [default: output to stdout]
+    #[clap(short = 'o', display_order = 1, help_heading = "OUTPUT")]
+    pub output: Option<String>,
+
+    /// Only output ANI values where one genome has aligned fraction > than this value. [default: 15]
+    #[clap(long = "min-af", help_heading = "OUTPUT")]
+    pub min_af: Option<String>,
+
+    /// Slower skani mode; 4x slower and more memory. Gives much more accurate AF for distant genomes. More accurate ANI for VERY fragmented assemblies (< 3kb N50), but less accurate ANI otherwise. Alias for -c 30.
+    #[clap(long = "slow", help_heading = "PRESETS")]
+    pub slow: bool,
+
+    /// Medium skani mode; 2x slower and more memory. More accurate AF and more accurate ANI for moderately fragmented assemblies (< 10kb N50). Alias for -c 70.
+    #[clap(long = "medium", help_heading = "PRESETS")]
+    pub medium: bool,
+
+    /// Faster skani mode; 2x faster and less memory. Less accurate AF and less accurate ANI for distant genomes, but works ok for high N50 and > 95% ANI. Alias for -c 200.
+    #[clap(long = "fast", help_heading = "PRESETS")]
+    pub fast: bool,
+
+    /// Disable regression model for ANI prediction. [default: learned ANI used for c >= 70 and >= 150,000 bases aligned and not on individual contigs]
+    #[clap(long = "no-learned-ani", help_heading = "ALGORITHM PARAMETERS")]
+    pub no_learned_ani: bool,
+
+    /// Marker k-mer compression factor. Markers are used for filtering. Consider decreasing to ~200-300 if working with small genomes (e.g. plasmids or viruses). [default: 1000]
+    #[clap(short = 'm', help_heading = "ALGORITHM PARAMETERS")]
+    pub marker_c: Option<String>,
+
+    /// k-mer size. [default: 15]
+    #[clap(short = 'k', hide = true, help_heading = "ALGORITHM PARAMETERS")]
+    pub k: Option<String>,
+
+    /// Compression factor (k-mer subsampling rate). [default: 125]
+    #[clap(short = 'c', help_heading = "ALGORITHM PARAMETERS")]
+    pub c: Option<String>,
+
+    /// Screen out pairs with *approximately* < % identity using k-mer sketching. [default: 80]
+    #[clap(short = 's', help_heading = "ALGORITHM PARAMETERS")]
+    pub s: Option<String>,
+
+    /// Estimate mean after trimming off 10%/90% quantiles
+    #[clap(long = "robust", help_heading = "ALGORITHM PARAMETERS")]
+    pub robust: bool,
+
+    /// Estimate median identity instead of average (mean) identity
+    #[clap(long = "median", help_heading = "ALGORITHM PARAMETERS")]
+    pub median: bool,
+
+    /// Debug level verbosity
+    #[clap(short = 'v', long = "debug", help_heading = "MISC")]
+    pub debug: bool,
+
+    /// Trace level verbosity
+    #[clap(long = "trace", help_heading = "MISC")]
+    pub trace: bool,
+}
+
+#[derive(Args)]
+pub struct DotplotArgs {
+    /// Query fasta or sketch (y-axis)
+    #[clap(required = true, help_heading = "INPUTS")]
+    pub query: String,
+
+    /// Reference fasta or sketch (x-axis)
+    #[clap(required = true, help_heading = "INPUTS")]
+    pub reference: String,
+
+    /// Output SVG file name [default: output to stdout]
+    #[clap(short = 'o', display_order = 1, help_heading = "OUTPUT")]
+    pub output: Option<String>,
+
+    /// Order query contigs by the position of their best match on the reference instead of input order
+    #[clap(long = "sort", help_heading = "OUTPUT")]
+    pub sort: bool,
+
+    /// Width and height of the plotting area in pixels. [default: 800]
+    #[clap(long = "size", help_heading = "OUTPUT")]
+    pub size: Option<String>,
+
+    /// Slower skani mode; 4x slower and more memory. Gives much more accurate AF for distant genomes. More accurate ANI for VERY fragmented assemblies (< 3kb N50), but less accurate ANI otherwise. Alias for -c 30.
+    #[clap(long = "slow", help_heading = "PRESETS")]
+    pub slow: bool,
+
+    /// Medium skani mode; 2x slower and more memory. More accurate AF and more accurate ANI for moderately fragmented assemblies (< 10kb N50). Alias for -c 70.
+    #[clap(long = "medium", help_heading = "PRESETS")]
+    pub medium: bool,
+
+    /// Faster skani mode; 2x faster and less memory. Less accurate AF and less accurate ANI for distant genomes, but works ok for high N50 and > 95% ANI. Alias for -c 200.
+    #[clap(long = "fast", help_heading = "PRESETS")]
+    pub fast: bool,
+
+    /// Marker k-mer compression factor. Markers are used for filtering. Consider decreasing to ~200-300 if working with small genomes (e.g. plasmids or viruses). [default: 1000]
+    #[clap(short = 'm', help_heading = "ALGORITHM PARAMETERS")]
+    pub marker_c: Option<String>,
+
+    /// k-mer size. [default: 15]
+    #[clap(short = 'k', hide = true, help_heading = "ALGORITHM PARAMETERS")]
+    pub k: Option<String>,
+
+    /// Compression factor (k-mer subsampling rate). [default: 125]
+    #[clap(short = 'c', help_heading = "ALGORITHM PARAMETERS")]
+    pub c: Option<String>,
+
+    /// Debug level verbosity
+    #[clap(short = 'v', long = "debug", help_heading = "MISC")]
+    pub debug: bool,
+
+    /// Trace level verbosity
+    #[clap(long = "trace", help_heading = "MISC")]
+    pub trace: bool,
+}
+
+#[derive(Args)]
+#[clap(group(
+    clap::ArgGroup::new("input_group")
+        .required(true)
+))]
+pub struct InspectArgs {
+    /// Number of threads
+    #[clap(short = 't', default_value = "3")]
+    pub threads: String,
+
+    /// fastas, .sketch files or sketch folders from `skani sketch`
+    #[clap(help_heading = "INPUT/OUTPUT", group = "input_group")]
+    pub files: Vec<String>,
+
+    /// File with each line containing one fasta/sketch file or sketch folder
+    #[clap(short = 'l', help_heading = "INPUT/OUTPUT", group = "input_group")]
+    pub file_list: Option<String>,
+
+    /// Use individual sequences instead the entire file for multi-fastas.
+    #[clap(short = 'i', help_heading = "INPUT/OUTPUT")]
+    pub individual_contig: bool,
+
+    /// Output file name; rewrites file by default [default: output to stdout]
+    #[clap(short = 'o', display_order = 1, help_heading = "INPUT/OUTPUT")]
+    pub output: Option<String>,
+
+    /// Only display the first part of contig names (before first whitespace)
+    #[clap(long = "short-header", help_heading = "INPUT/OUTPUT")]
+    pub short_header: bool,
+
+    /// Debug level verbosity
+    #[clap(short = 'v', long = "debug", help_heading = "MISC")]
+    pub debug: bool,
+
+    /// Trace level verbosity
+    #[clap(long = "trace", help_heading = "MISC")]
+    pub trace: bool,
+}
@@ -0,0 +1,65 @@
+//! Digital DNA-DNA hybridisation (dDDH) estimates in the style of GGDC.
+//!
+//! GGDC computes the GBDP distances d0, d4 and d6 from BLAST high-scoring segment pairs
+//! (HSPs) and converts them to dDDH values. skani has no HSPs. Instead, the aligned fractions
+//! stand in for the HSP length sums, and the ANI stands in for the HSP identity:
+//!
+//! - d0 = 1 - (H_ab + H_ba) / (G_a + G_b)
+//! - d4 = 1 - (I_ab + I_ba) / (H_ab + H_ba)
+//! - d6 = 1 - (I_ab + I_ba) / (G_a + G_b)
+//!
+//! Here H is the number of aligned bases, I = ANI * H is the number of identical bases and G is
+//! the unmasked genome length, the same denominator as the aligned fractions.
+//!
+//! As in GGDC (Meier-Kolthoff et al. 2013, BMC Bioinformatics 14:60), each distance is converted
+//! with a logistic GLM, dDDH = 100 / (1 + exp(-(a + b * d))), with one intercept a and slope b
+//! per formula. Because H and I are approximated from chains rather than BLAST HSPs, values
+//! can still differ from BLAST-based GGDC, especially for d0/d6 on incomplete assemblies.
+
+use crate::types::AniEstResult;
+use crate::types::DdhEstimate;
+
+/// Intercept and slope of the logistic GLM for each formula. Identical genomes get 99.5%, and
+/// 70% falls at the distances that correspond to 95% ANI.
+pub const D0_GLM: (f64, f64) = (5.29, -14.81);
+pub const D4_GLM: (f64, f64) = (5.29, -88.86);
+pub const D6_GLM: (f64, f64) = (5.29, -13.26);
+
+fn distance_to_ddh(d: f64, glm: (f64, f64)) -> f32 {
+    let (intercept, slope) = glm;
+    (100. / (1. + f64::exp(-(intercept + slope * f64::max(d, 0.))))) as f32
+}
+
+fn gbdp_distances(ani: f64, af_ref: f64, af_query: f64, ref_len: f64, query_len: f64) -> (f64, f64, f64) {
+    let aligned = af_ref * ref_len + af_query * query_len;
+    let total = ref_len + query_len;
+    let identical = ani * aligned;
+    let d0 = 1. - aligned / total;
+    let d4 = if aligned > 0. { 1. - identical / aligned } else { 1. };
+    let d6 = 1. - identical / total;
+    (d0, d4, d6)
+}
+
+/// dDDH estimates for a finished (regression-adjusted) ANI result. `ref_len` and `query_len`
+/// are unmasked lengths (`Sketch::unmasked_length`), over which the aligned fractions are
+/// computed. The d4 confidence interval is the bootstrap ANI interval pushed through the d4
+/// formula.
+pub fn estimate_ddh(ani_res: &AniEstResult, ref_len: usize, query_len: usize) -> DdhEstimate {
+    if ani_res.ani.is_nan() || ani_res.ani <= 0. {
+        return DdhEstimate::default();
+    }
+    let af_ref = ani_res.align_fraction_ref as f64;
+    let af_query = ani_res.align_fraction_query as f64;
+    let (ref_len, query_len) = (ref_len as f64, query_len as f64);
+    let (d0, d4, d6) = gbdp_distances(ani_res.ani as f64, af_ref, af_query, ref_len, query_len);
+    let (_, d4_lower, _) = gbdp_distances(ani_res.ci_lower as f64, af_ref, af_query, ref_len, query_len);
+    let (_, d4_upper, _) = gbdp_distances(ani_res.ci_upper as f64, af_ref, af_query, ref_len, query_len);
+    DdhEstimate {
+        d0: distance_to_ddh(d0, D0_GLM),
+        d4: distance_to_ddh(d4, D4_GLM),
+        d6: distance_to_ddh(d6, D6_GLM),
+        //Lower ANI gives a larger distance and so a lower dDDH; the bounds keep their order.
+        d4_ci_lower: distance_to_ddh(d4_lower, D4_GLM),
+        d4_ci_upper: distance_to_ddh(d4_upper, D4_GLM),
+    }
+}
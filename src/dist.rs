@@ -10,17 +10,18 @@ use std::sync::Mutex;
 use std::time::Instant;
 
 pub fn dist(command_params: CommandParams, mut sketch_params: SketchParams) {
-    let ref_sketches;
+    let mut ref_sketches;
     let query_params;
-    let query_sketches;
+    let mut query_sketches;
     let now = Instant::now();
+    let query_filters = QueryContigFilters::from_sketch_params(&sketch_params);
     if command_params.refs_are_sketch {
         let new_sketch_params;
         info!("Sketches detected.");
         (new_sketch_params, ref_sketches) = file_io::sketches_from_sketch(
             &command_params.ref_files,
         );
-        if new_sketch_params != sketch_params {
+        if !new_sketch_params.same_seeds(&sketch_params) {
             warn!("Parameters from .sketch files not equal to the input parameters. Using parameters from .sketch files.")
         }
         sketch_params = new_sketch_params;
@@ -32,32 +33,50 @@ pub fn dist(command_params: CommandParams, mut sketch_params: SketchParams) {
         );
     } else {
         ref_sketches =
-            file_io::fastx_to_sketches(&command_params.ref_files, &sketch_params, true);
+            file_io::fastx_groups_to_sketches(&command_params.ref_files, &command_params.genome_groups, &sketch_params, true);
     }
+    // Fasta queries against .sketch references keep their seeding but not their mask or header filters.
+    let query_sketch_params = if command_params.refs_are_sketch {
+        sketch_params.for_queries(&query_filters)
+    } else {
+        sketch_params.clone()
+    };
     if command_params.queries_are_sketch {
         (query_params, query_sketches) =
             file_io::sketches_from_sketch(&command_params.query_files);
-        if sketch_params != query_params && command_params.refs_are_sketch {
-            panic!(
-                "Query sketch parameters were not equal to reference sketch parameters. Exiting."
-            );
-        } else if sketch_params != query_params {
+        if !sketch_params.same_seeds(&query_params) && command_params.refs_are_sketch {
+            error!("Query sketch parameters were not equal to reference sketch parameters. Exiting.");
+            std::process::exit(1)
+        } else if !sketch_params.same_seeds(&query_params) {
             warn!("Parameters from .sketch files not equal to the input parameters. Using parameters from .sketch files.")
+        } else if command_params.refs_are_sketch {
+            if let Err(e) = file_io::check_masks(&sketch_params, &query_params) {
+                warn!("{}", e);
+            }
         }
     } else if command_params.individual_contig_q {
         query_sketches = file_io::fastx_to_multiple_sketch_rewrite(
             &command_params.query_files,
-            &sketch_params,
+            &query_sketch_params,
             true,
         );
     } else {
         query_sketches =
-            file_io::fastx_to_sketches(&command_params.query_files, &sketch_params, true);
+            file_io::fastx_groups_to_sketches(&command_params.query_files, &command_params.genome_groups, &query_sketch_params, true);
     }
     if query_sketches.is_empty() || ref_sketches.is_empty() {
         error!("No reference sketches/genomes or query sketches/genomes found.");
         std::process::exit(1)
     }
+    if let Err(e) = file_io::check_seed_layouts(&ref_sketches, &query_sketches) {
+        error!("{} Exiting.", e);
+        std::process::exit(1)
+    }
+    if let Err(e) = file_io::check_min_contig_lengths(&ref_sketches, &query_sketches) {
+        warn!("{}", e);
+    }
+    file_io::set_display_names(&mut ref_sketches, &command_params.display_names);
+    file_io::set_display_names(&mut query_sketches, &command_params.display_names);
 
 
     let model_opt = regression::get_model(sketch_params.c, command_params.learned_ani);
@@ -103,6 +122,7 @@ pub fn dist(command_params: CommandParams, mut sketch_params: SketchParams) {
                 let ref_sketch = &ref_sketches[i];
                 let passed_screen =
                     screen::check_markers_quickly(query_sketch, ref_sketch, screen_val, command_params.rescue_small);
+                let mut reported = false;
                 if passed_screen {
                     let map_params = chain::map_params_from_sketch(
                         ref_sketch,
@@ -115,6 +135,12 @@ pub fn dist(command_params: CommandParams, mut sketch_params: SketchParams) {
                     if ani_res.ani > 0.1 {
                         let mut locked = anis.lock().unwrap();
                         locked.push(ani_res);
+                        reported = true;
+                    }
+                }
+                if !reported && command_params.extra_columns.tetra {
+                    if let Some(tetra_res) = chain::tetra_only_result(ref_sketch, query_sketch) {
+                        anis.lock().unwrap().push(tetra_res);
                     }
                 }
             });
@@ -127,6 +153,15 @@ pub fn dist(command_params: CommandParams, mut sketch_params: SketchParams) {
                 &ref_sketches,
                 command_params.rescue_small
             );
+            if command_params.extra_columns.tetra {
+                (0..ref_sketches.len())
+                    .filter(|i| !refs_passing_screen_table.contains(i))
+                    .for_each(|i| {
+                        if let Some(tetra_res) = chain::tetra_only_result(&ref_sketches[i], query_sketch) {
+                            anis.lock().unwrap().push(tetra_res);
+                        }
+                    });
+            }
             refs_passing_screen_table.into_par_iter().for_each(|i| {
                 let ref_sketch = &ref_sketches[i];
                 let map_params = chain::map_params_from_sketch(
@@ -140,6 +175,11 @@ pub fn dist(command_params: CommandParams, mut sketch_params: SketchParams) {
                     let mut locked = anis.lock().unwrap();
                     locked.push(ani_res);
                 }
+                else if command_params.extra_columns.tetra {
+                    if let Some(tetra_res) = chain::tetra_only_result(ref_sketch, query_sketch) {
+                        anis.lock().unwrap().push(tetra_res);
+                    }
+                }
             });
         }
         let c;
@@ -167,6 +207,8 @@ pub fn dist(command_params: CommandParams, mut sketch_params: SketchParams) {
                     command_params.detailed_out,
                     !*fw,
                     command_params.short_header,
+                    &command_params.extra_columns,
+                    &command_params.ci_params,
                 );
                 if *fw == true{
                     *fw = false;
@@ -185,6 +227,8 @@ pub fn dist(command_params: CommandParams, mut sketch_params: SketchParams) {
         command_params.detailed_out,
         !*first_write.lock().unwrap(),
         command_params.short_header,
+        &command_params.extra_columns,
+        &command_params.ci_params,
     );
     info!("ANI calculation time: {}", now.elapsed().as_secs_f32());
 }
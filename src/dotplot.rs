@@ -0,0 +1,268 @@
+use crate::chain;
+use crate::file_io;
+use crate::params::*;
+use crate::svg::{self, Svg};
+use crate::types::*;
+use log::*;
+
+const MARGIN_LEFT: f64 = 90.;
+const MARGIN_BOTTOM: f64 = 90.;
+const MARGIN_TOP: f64 = 40.;
+const MARGIN_RIGHT: f64 = 20.;
+const MAX_CONTIG_LABELS: usize = 50;
+const MIN_PLOT_IDENTITY: f64 = 0.80;
+
+const FORWARD_LOW: (u8, u8, u8) = (158, 202, 225);
+const FORWARD_HIGH: (u8, u8, u8) = (8, 48, 107);
+const REVERSE_LOW: (u8, u8, u8) = (252, 174, 145);
+const REVERSE_HIGH: (u8, u8, u8) = (165, 15, 21);
+
+/// A chain interval in the original (unswitched) reference/query coordinates.
+struct DotSegment {
+    ref_contig: usize,
+    ref_interval: (GnPosition, GnPosition),
+    query_contig: usize,
+    query_interval: (GnPosition, GnPosition),
+    reverse: bool,
+    identity: f64,
+}
+
+fn get_segments(ref_sketch: &Sketch, query_sketch: &Sketch, command_params: &CommandParams) -> Vec<DotSegment> {
+    let model_opt = None;
+    let map_params = chain::map_params_from_sketch(ref_sketch, false, command_params, &model_opt);
+    let k = map_params.k;
+    let (int_chunks, anchor_chunks, switched) = chain::chain_intervals(ref_sketch, query_sketch, &map_params);
+    let mut segments = vec![];
+    for int in int_chunks.iter().flatten() {
+        //Identity of a single chain, computed the same way as a fragment ANI but only
+        //over the seeds inside the chain.
+        let seeds_in_int = anchor_chunks.seeds_in_chunk[int.chunk_id]
+            .iter()
+            .filter(|x| **x >= int.interval_on_query.0 && **x <= int.interval_on_query.1)
+            .count();
+        let identity = if seeds_in_int == 0 {
+            0.
+        } else {
+            f64::powf(
+                f64::min(1., int.num_anchors as f64 / seeds_in_int as f64),
+                1. / k as f64,
+            )
+        };
+        let (ref_contig, ref_interval) = int.ref_coords(switched);
+        let (query_contig, query_interval) = int.query_coords(switched);
+        segments.push(DotSegment {
+            ref_contig,
+            ref_interval,
+            query_contig,
+            query_interval,
+            reverse: int.reverse_chain,
+            identity,
+        });
+    }
+    segments
+}
+
+fn offsets(contig_lengths: &[GnPosition], order: &[usize]) -> (Vec<u64>, u64) {
+    let mut offsets = vec![0; contig_lengths.len()];
+    let mut total = 0;
+    for &i in order {
+        offsets[i] = total;
+        total += contig_lengths[i] as u64;
+    }
+    (offsets, total)
+}
+
+/// Order query contigs by where their longest chain lands on the reference;
+/// contigs without chains are placed last in their original order.
+fn sort_query_by_best_match(
+    query_sketch: &Sketch,
+    segments: &[DotSegment],
+    ref_offsets: &[u64],
+) -> Vec<usize> {
+    let mut best = vec![(0, u64::MAX); query_sketch.contig_lengths.len()];
+    for seg in segments {
+        let len = seg.query_interval.1 - seg.query_interval.0;
+        if len >= best[seg.query_contig].0 {
+            best[seg.query_contig] = (len, ref_offsets[seg.ref_contig] + seg.ref_interval.0 as u64);
+        }
+    }
+    let mut order = (0..query_sketch.contig_lengths.len()).collect::<Vec<usize>>();
+    order.sort_by_key(|&i| best[i].1);
+    order
+}
+
+fn draw_dotplot(
+    ref_sketch: &Sketch,
+    query_sketch: &Sketch,
+    segments: &[DotSegment],
+    sort: bool,
+    plot_size: f64,
+) -> Svg {
+    let ref_order = (0..ref_sketch.contig_lengths.len()).collect::<Vec<usize>>();
+    let (ref_offsets, ref_total) = offsets(&ref_sketch.contig_lengths, &ref_order);
+    let query_order = if sort {
+        sort_query_by_best_match(query_sketch, segments, &ref_offsets)
+    } else {
+        (0..query_sketch.contig_lengths.len()).collect::<Vec<usize>>()
+    };
+    let (query_offsets, query_total) = offsets(&query_sketch.contig_lengths, &query_order);
+
+    let mut svg = Svg::new(
+        MARGIN_LEFT + plot_size + MARGIN_RIGHT,
+        MARGIN_TOP + plot_size + MARGIN_BOTTOM,
+    );
+    let x_scale = plot_size / u64::max(ref_total, 1) as f64;
+    let y_scale = plot_size / u64::max(query_total, 1) as f64;
+    let to_x = |contig: usize, pos: GnPosition| MARGIN_LEFT + (ref_offsets[contig] + pos as u64) as f64 * x_scale;
+    //Query origin is at the bottom left.
+    let to_y = |contig: usize, pos: GnPosition| {
+        MARGIN_TOP + plot_size - (query_offsets[contig] + pos as u64) as f64 * y_scale
+    };
+
+    //Contig boundaries and labels
+    for (i, &ctg) in ref_order.iter().enumerate() {
+        let x = to_x(ctg, 0);
+        if i != 0 {
+            svg.line(x, MARGIN_TOP, x, MARGIN_TOP + plot_size, "#dddddd", 0.5);
+        }
+        if ref_order.len() <= MAX_CONTIG_LABELS {
+            let mid = to_x(ctg, ref_sketch.contig_lengths[ctg] / 2);
+            let name = truncate_contig_name(&ref_sketch.contigs[ctg], true);
+            svg.text(mid, MARGIN_TOP + plot_size + 12., 8., "end", -45., &name);
+        }
+    }
+    for (i, &ctg) in query_order.iter().enumerate() {
+        let y = to_y(ctg, 0);
+        if i != 0 {
+            svg.line(MARGIN_LEFT, y, MARGIN_LEFT + plot_size, y, "#dddddd", 0.5);
+        }
+        if query_order.len() <= MAX_CONTIG_LABELS {
+            let mid = to_y(ctg, query_sketch.contig_lengths[ctg] / 2);
+            let name = truncate_contig_name(&query_sketch.contigs[ctg], true);
+            svg.text(MARGIN_LEFT - 4., mid, 8., "end", -45., &name);
+        }
+    }
+    svg.rect_outline(MARGIN_LEFT, MARGIN_TOP, plot_size, plot_size, "black");
+
+    //Draw low identity chains first so high identity ones are visible on top.
+    let mut seg_order = (0..segments.len()).collect::<Vec<usize>>();
+    seg_order.sort_by(|&a, &b| segments[a].identity.partial_cmp(&segments[b].identity).unwrap());
+    for i in seg_order {
+        let seg = &segments[i];
+        let t = (seg.identity - MIN_PLOT_IDENTITY) / (1. - MIN_PLOT_IDENTITY);
+        let colour = if seg.reverse {
+            svg::blend(REVERSE_LOW, REVERSE_HIGH, t)
+        } else {
+            svg::blend(FORWARD_LOW, FORWARD_HIGH, t)
+        };
+        let colour = svg::rgb(colour.0, colour.1, colour.2);
+        let x1 = to_x(seg.ref_contig, seg.ref_interval.0);
+        let x2 = to_x(seg.ref_contig, seg.ref_interval.1);
+        let (y1, y2) = if seg.reverse {
+            (
+                to_y(seg.query_contig, seg.query_interval.1),
+                to_y(seg.query_contig, seg.query_interval.0),
+            )
+        } else {
+            (
+                to_y(seg.query_contig, seg.query_interval.0),
+                to_y(seg.query_contig, seg.query_interval.1),
+            )
+        };
+        svg.line(x1, y1, x2, y2, &colour, 1.5);
+    }
+
+    svg.text(
+        MARGIN_LEFT + plot_size / 2.,
+        MARGIN_TOP + plot_size + MARGIN_BOTTOM - 8.,
+        12.,
+        "middle",
+        0.,
+        ref_sketch.name(),
+    );
+    svg.text(
+        14.,
+        MARGIN_TOP + plot_size / 2.,
+        12.,
+        "middle",
+        -90.,
+        query_sketch.name(),
+    );
+    svg.text(
+        MARGIN_LEFT,
+        MARGIN_TOP - 12.,
+        10.,
+        "start",
+        0.,
+        &format!(
+            "blue: forward, red: reverse; darker = higher identity ({:.0}-100%)",
+            MIN_PLOT_IDENTITY * 100.
+        ),
+    );
+    svg
+}
+
+pub fn dotplot(command_params: CommandParams, mut sketch_params: SketchParams) {
+    let ref_sketches;
+    let query_sketches;
+    let query_filters = QueryContigFilters::from_sketch_params(&sketch_params);
+    if command_params.refs_are_sketch {
+        let new_sketch_params;
+        (new_sketch_params, ref_sketches) = file_io::sketches_from_sketch(&command_params.ref_files);
+        if !new_sketch_params.same_seeds(&sketch_params) {
+            warn!("Parameters from .sketch files not equal to the input parameters. Using parameters from .sketch files.")
+        }
+        sketch_params = new_sketch_params;
+    } else {
+        ref_sketches = file_io::fastx_to_sketches(&command_params.ref_files, &sketch_params, true);
+    }
+    // Fasta queries against .sketch references keep their seeding but not their mask or header filters.
+    let query_sketch_params = if command_params.refs_are_sketch {
+        sketch_params.for_queries(&query_filters)
+    } else {
+        sketch_params.clone()
+    };
+    if command_params.queries_are_sketch {
+        let query_params;
+        (query_params, query_sketches) = file_io::sketches_from_sketch(&command_params.query_files);
+        if !sketch_params.same_seeds(&query_params) {
+            error!("Query sketch parameters were not equal to reference sketch parameters. Exiting.");
+            std::process::exit(1)
+        }
+        if command_params.refs_are_sketch {
+            if let Err(e) = file_io::check_masks(&sketch_params, &query_params) {
+                warn!("{}", e);
+            }
+        }
+    } else {
+        query_sketches = file_io::fastx_to_sketches(&command_params.query_files, &query_sketch_params, true);
+    }
+    if query_sketches.is_empty() || ref_sketches.is_empty() {
+        error!("No reference sketches/genomes or query sketches/genomes found.");
+        std::process::exit(1)
+    }
+    if let Err(e) = file_io::check_seed_layouts(&ref_sketches, &query_sketches) {
+        error!("{} Exiting.", e);
+        std::process::exit(1)
+    }
+    if let Err(e) = file_io::check_min_contig_lengths(&ref_sketches, &query_sketches) {
+        warn!("{}", e);
+    }
+    if sketch_params.use_aa {
+        error!("skani dotplot does not support amino acid sketches. Exiting.");
+        std::process::exit(1)
+    }
+
+    let ref_sketch = &ref_sketches[0];
+    let query_sketch = &query_sketches[0];
+    let segments = get_segments(ref_sketch, query_sketch, &command_params);
+    info!("{} chains found between {} and {}.", segments.len(), query_sketch.file_name, ref_sketch.file_name);
+    let svg = draw_dotplot(
+        ref_sketch,
+        query_sketch,
+        &segments,
+        command_params.sort_contigs,
+        command_params.plot_size as f64,
+    );
+    svg.write(&command_params.out_file_name);
+}
@@ -0,0 +1,102 @@
+use crate::chain;
+use crate::file_io;
+use crate::params::*;
+use crate::regression;
+use crate::screen;
+use crate::types::*;
+use log::*;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+fn load_sketch(file: &str, is_sketch: bool, sketch_params: &SketchParams) -> (SketchParams, Sketch) {
+    let (params, mut sketches) = if is_sketch {
+        file_io::sketches_from_sketch(&vec![file.to_string()])
+    } else {
+        (
+            sketch_params.clone(),
+            file_io::fastx_to_sketches(&vec![file.to_string()], sketch_params, true),
+        )
+    };
+    if sketches.is_empty() {
+        error!("No genome/sketch found for {}. Exiting.", file);
+        std::process::exit(1)
+    }
+    (params, sketches.swap_remove(0))
+}
+
+pub fn explain(command_params: CommandParams, mut sketch_params: SketchParams) {
+    let (ref_params, ref_sketch) = load_sketch(
+        &command_params.ref_files[0],
+        command_params.refs_are_sketch,
+        &sketch_params,
+    );
+    if command_params.refs_are_sketch {
+        if !ref_params.same_seeds(&sketch_params) {
+            warn!("Parameters from .sketch files not equal to the input parameters. Using parameters from .sketch files.")
+        }
+        sketch_params = ref_params;
+    }
+    let (query_params, query_sketch) = load_sketch(
+        &command_params.query_files[0],
+        command_params.queries_are_sketch,
+        &sketch_params,
+    );
+    if !query_params.same_seeds(&sketch_params) {
+        error!("Query sketch parameters were not equal to reference sketch parameters. Exiting.");
+        std::process::exit(1)
+    }
+    if command_params.refs_are_sketch && command_params.queries_are_sketch {
+        if let Err(e) = file_io::check_masks(&sketch_params, &query_params) {
+            warn!("{}", e);
+        }
+    }
+    if let Err(e) = file_io::check_seed_layouts(std::slice::from_ref(&ref_sketch), std::slice::from_ref(&query_sketch)) {
+        error!("{} Exiting.", e);
+        std::process::exit(1)
+    }
+
+    let screen_val = if command_params.screen_val == 0. {
+        if sketch_params.use_aa {
+            SEARCH_AAI_CUTOFF_DEFAULT
+        } else {
+            SEARCH_ANI_CUTOFF_DEFAULT
+        }
+    } else {
+        command_params.screen_val
+    };
+
+    let model_opt = regression::get_model(sketch_params.c, command_params.learned_ani);
+    let map_params = chain::map_params_from_sketch(
+        &ref_sketch,
+        sketch_params.use_aa,
+        &command_params,
+        &model_opt,
+    );
+
+    let mut diagnostics = AniDiagnostics {
+        version: VERSION.to_string(),
+        ref_file: ref_sketch.file_name.clone(),
+        query_file: query_sketch.file_name.clone(),
+        screen: screen::marker_screen_stats(
+            &ref_sketch,
+            &query_sketch,
+            screen_val,
+            command_params.rescue_small,
+        ),
+        ..Default::default()
+    };
+    if !diagnostics.screen.passed {
+        warn!("Pair does not pass the marker screen; `skani dist` would not report it. Chaining anyways.");
+    }
+    chain::chain_seeds_diagnostics(&ref_sketch, &query_sketch, map_params, Some(&mut diagnostics));
+
+    let mut handle: Box<dyn Write> = if command_params.out_file_name.is_empty() {
+        Box::new(BufWriter::new(io::stdout().lock()))
+    } else {
+        Box::new(BufWriter::new(
+            File::create(&command_params.out_file_name).expect(&command_params.out_file_name),
+        ))
+    };
+    serde_json::to_writer_pretty(&mut handle, &diagnostics).unwrap();
+    writeln!(handle).unwrap();
+}
@@ -1,34 +1,231 @@
 use crate::params::*;
 use std::fs::OpenOptions;
+use crate::flat_file;
+use crate::gfa;
+use crate::twobit;
 use crate::seeding;
+use crate::tetra::TetraCounts;
 use crate::types::*;
 use fxhash::FxHashMap;
 use log::*;
-use needletail::parse_fastx_file;
+use needletail::{parse_fastx_file, parse_fastx_reader};
+use regex::bytes::Regex;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 use rayon::prelude::*;
+use std::borrow::Cow;
 use std::fs::File;
-use std::io::{self, BufReader, BufWriter, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 use std::sync::Mutex;
 
-fn write_header(writer: &mut impl Write, id_str: &str, ci: bool, verbose: bool) {
+/// Pairs with an ANI, plus pairs that only have a TETRA correlation when --tetra is set.
+fn is_reported(ani_res: &AniEstResult, extra: &ExtraColumns) -> bool {
+    if ani_res.ani < 0. {
+        return false;
+    }
+    !ani_res.ani.is_nan() || (extra.tetra && ani_res.tetra.is_some())
+}
+
+fn id_str(aai: bool, extra: &ExtraColumns) -> &'static str {
+    match (aai, extra.markers_only) {
+        (false, false) => "ANI",
+        (true, false) => "AAI",
+        (false, true) => "ANI_markers",
+        (true, true) => "AAI_markers",
+    }
+}
+
+fn write_extra_header(writer: &mut impl Write, id_str: &str, extra: &ExtraColumns) {
+    if extra.synteny {
+        write!(writer, "\tCollinear_blocks\tInversions\tBreakpoints\tBlock_N50").unwrap();
+    }
+    if extra.ddh {
+        write!(writer, "\tdDDH_d0\tdDDH_d4\tdDDH_d6\tdDDH_d4_5_percentile\tdDDH_d4_95_percentile").unwrap();
+    }
+    if extra.indels {
+        write!(writer, "\tIndels_per_kb\tIndel_adjusted_ANI").unwrap();
+    }
+    if extra.snps {
+        write!(writer, "\tEst_SNPs\tEst_SNPs_5_percentile\tEst_SNPs_95_percentile\tJC_distance").unwrap();
+    }
+    if extra.tetra {
+        write!(writer, "\tTETRA").unwrap();
+    }
+    if extra.genome_stats {
+        write!(
+            writer,
+            "\tRef_length\tQuery_length\tRef_N50\tQuery_N50\tRef_GC\tQuery_GC\tGC_difference\tRef_ambiguous_bases\tQuery_ambiguous_bases"
+        )
+        .unwrap();
+    }
+    if extra.both_directions {
+        for dir in ["query_to_ref", "ref_to_query"] {
+            write!(
+                writer,
+                "\t{}_{}\tAlign_fraction_ref_{}\tAlign_fraction_query_{}",
+                id_str, dir, dir, dir
+            )
+            .unwrap();
+        }
+        write!(writer, "\t{}_symmetric", id_str).unwrap();
+    }
+    writeln!(writer).unwrap();
+}
+
+fn write_extra(writer: &mut impl Write, ani_res: &AniEstResult, extra: &ExtraColumns) {
+    if extra.synteny {
+        let syn = &ani_res.synteny;
+        write!(
+            writer,
+            "\t{}\t{}\t{}\t{}",
+            syn.collinear_blocks, syn.inversions, syn.breakpoints, syn.block_n50
+        )
+        .unwrap();
+    }
+    if extra.ddh {
+        let ddh = &ani_res.ddh;
+        if ani_res.aai {
+            write!(writer, "\tNA\tNA\tNA\tNA\tNA").unwrap();
+        } else {
+            write!(
+                writer,
+                "\t{:.2}\t{:.2}\t{:.2}\t{:.2}\t{:.2}",
+                ddh.d0, ddh.d4, ddh.d6, ddh.d4_ci_lower, ddh.d4_ci_upper
+            )
+            .unwrap();
+        }
+    }
+    if extra.indels {
+        write!(
+            writer,
+            "\t{:.3}\t{:.2}",
+            ani_res.indels.rate_per_kb(),
+            ani_res.indels.adjusted_identity(ani_res.ani as f64) * 100.
+        )
+        .unwrap();
+    }
+    if extra.snps {
+        let snps = &ani_res.snps;
+        write!(
+            writer,
+            "\t{:.0}\t{:.0}\t{:.0}\t{}",
+            snps.snps,
+            snps.snps_lower,
+            snps.snps_upper,
+            if snps.jc_distance.is_finite() { format!("{:.6}", snps.jc_distance) } else { "NA".to_string() }
+        )
+        .unwrap();
+    }
+    if extra.tetra {
+        match ani_res.tetra {
+            Some(tetra) => write!(writer, "\t{:.5}", tetra).unwrap(),
+            None => write!(writer, "\tNA").unwrap(),
+        }
+    }
+    if extra.genome_stats {
+        write_genome_stats(writer, &ani_res.ref_stats, &ani_res.query_stats);
+    }
+    if extra.both_directions {
+        let both = &ani_res.both_directions;
+        for dir in [&both.query_to_ref, &both.ref_to_query] {
+            if dir.is_valid() {
+                write!(
+                    writer,
+                    "\t{:.2}\t{:.2}\t{:.2}",
+                    dir.ani * 100.,
+                    dir.align_fraction_ref * 100.,
+                    dir.align_fraction_query * 100.
+                )
+                .unwrap();
+            } else {
+                write!(writer, "\tNA\tNA\tNA").unwrap();
+            }
+        }
+        if both.ani_symmetric.is_nan() {
+            write!(writer, "\tNA").unwrap();
+        } else {
+            write!(writer, "\t{:.2}", both.ani_symmetric * 100.).unwrap();
+        }
+    }
+    writeln!(writer).unwrap();
+}
+
+fn format_opt<T: std::fmt::Display>(val: Option<T>) -> String {
+    match val {
+        Some(val) => val.to_string(),
+        None => "NA".to_string(),
+    }
+}
+
+/// GC is in percent; GC_difference is query minus reference. Composition is NA for
+/// sketches from skani <= v0.3.
+fn write_genome_stats(writer: &mut impl Write, ref_stats: &GenomeStats, query_stats: &GenomeStats) {
+    let gc = |stats: &GenomeStats| stats.gc().map(|gc| gc * 100.);
+    let gc_diff = match (gc(ref_stats), gc(query_stats)) {
+        (Some(r), Some(q)) => Some(format!("{:.2}", q - r)),
+        _ => None,
+    };
+    write!(
+        writer,
+        "\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        ref_stats.length,
+        query_stats.length,
+        ref_stats.n50,
+        query_stats.n50,
+        format_opt(gc(ref_stats).map(|x| format!("{:.2}", x))),
+        format_opt(gc(query_stats).map(|x| format!("{:.2}", x))),
+        format_opt(gc_diff),
+        format_opt(ref_stats.ambiguous_bases()),
+        format_opt(query_stats.ambiguous_bases()),
+    )
+    .unwrap();
+}
+
+/// Extra columns for a genome against itself: one collinear block per contig and 100% dDDH.
+fn write_extra_perfect(writer: &mut impl Write, sketch: &Sketch, extra: &ExtraColumns) {
+    if extra.synteny {
+        write!(writer, "\t{}\t0\t0\t{}", sketch.contigs.len(), n50(&sketch.contig_lengths)).unwrap();
+    }
+    if extra.ddh {
+        write!(writer, "\t{:.2}\t{:.2}\t{:.2}\t{:.2}\t{:.2}", 100, 100, 100, 100, 100).unwrap();
+    }
+    if extra.indels {
+        write!(writer, "\t{:.3}\t{:.2}", 0, 100).unwrap();
+    }
+    if extra.snps {
+        write!(writer, "\t{}\t{}\t{}\t{:.6}", 0, 0, 0, 0.).unwrap();
+    }
+    if extra.tetra {
+        write!(writer, "\t{:.5}", 1.).unwrap();
+    }
+    if extra.genome_stats {
+        write_genome_stats(writer, &sketch.genome_stats, &sketch.genome_stats);
+    }
+    if extra.both_directions {
+        write!(writer, "\t{:.2}\t{:.2}\t{:.2}\t{:.2}\t{:.2}\t{:.2}\t{:.2}", 100, 100, 100, 100, 100, 100, 100).unwrap();
+    }
+    writeln!(writer).unwrap();
+}
+
+fn write_header(writer: &mut impl Write, id_str: &str, ci: bool, verbose: bool, extra: &ExtraColumns, ci_params: &CiParams) {
+    let ci_label = if extra.markers_only { "[wilson]".to_string() } else { ci_params.header_label() };
     if !ci && !verbose {
-        writeln!(writer,"Ref_file\tQuery_file\t{}\tAlign_fraction_ref\tAlign_fraction_query\tRef_name\tQuery_name", id_str).unwrap();
+        write!(writer,"Ref_file\tQuery_file\t{}\tAlign_fraction_ref\tAlign_fraction_query\tRef_name\tQuery_name", id_str).unwrap();
     } else if !verbose {
-        writeln!(writer,"Ref_file\tQuery_file\t{}\tAlign_fraction_ref\tAlign_fraction_query\tRef_name\tQuery_name\t{}_5_percentile\t{}_95_percentile", id_str, id_str, id_str).unwrap();
+        write!(writer,"Ref_file\tQuery_file\t{}\tAlign_fraction_ref\tAlign_fraction_query\tRef_name\tQuery_name\t{}_5_percentile{}\t{}_95_percentile{}", id_str, id_str, ci_label, id_str, ci_label).unwrap();
     } else {
-        writeln!(writer,"Ref_file\tQuery_file\t{}\tAlign_fraction_ref\tAlign_fraction_query\tRef_name\tQuery_name\tNum_ref_contigs\tNum_query_contigs\t{}_5_percentile\t{}_95_percentile\tStandard_deviation\tRef_90_ctg_len\tRef_50_ctg_len\tRef_10_ctg_len\tQuery_90_ctg_len\tQuery_50_ctg_len\tQuery_10_ctg_len\tAvg_chain_len\tTotal_bases_covered", id_str, id_str, id_str).unwrap();
+        write!(writer,"Ref_file\tQuery_file\t{}\tAlign_fraction_ref\tAlign_fraction_query\tRef_name\tQuery_name\tNum_ref_contigs\tNum_query_contigs\t{}_5_percentile{}\t{}_95_percentile{}\tStandard_deviation\tRef_90_ctg_len\tRef_50_ctg_len\tRef_10_ctg_len\tQuery_90_ctg_len\tQuery_50_ctg_len\tQuery_10_ctg_len\tAvg_chain_len\tTotal_bases_covered\tRef_path\tQuery_path\tRef_dropped_contigs\tQuery_dropped_contigs\tRef_dropped_bases\tQuery_dropped_bases", id_str, id_str, ci_label, id_str, ci_label).unwrap();
     }
+    write_extra_header(writer, id_str, extra);
 }
 
-fn write_ani_res_perfect(writer: &mut impl Write, sketch: &Sketch, ci: bool, verbose: bool, short_header: bool) {
+fn write_ani_res_perfect(writer: &mut impl Write, sketch: &Sketch, ci: bool, verbose: bool, short_header: bool, extra: &ExtraColumns) {
     if !ci && !verbose {
-        writeln!(
+        write!(
             writer,
             "{}\t{}\t{:.2}\t{:.2}\t{:.2}\t{}\t{}",
-            sketch.file_name,
-            sketch.file_name,
+            sketch.name(),
+            sketch.name(),
             100,
             100,
             100,
@@ -37,11 +234,11 @@ fn write_ani_res_perfect(writer: &mut impl Write, sketch: &Sketch, ci: bool, ver
         )
         .unwrap();
     } else if !verbose {
-        writeln!(
+        write!(
             writer,
             "{}\t{}\t{:.2}\t{:.2}\t{:.2}\t{}\t{}\t{:.2}\t{:.2}",
-            sketch.file_name,
-            sketch.file_name,
+            sketch.name(),
+            sketch.name(),
             100,
             100,
             100,
@@ -52,11 +249,11 @@ fn write_ani_res_perfect(writer: &mut impl Write, sketch: &Sketch, ci: bool, ver
         )
         .unwrap();
     } else {
-        writeln!(
+        write!(
             writer,
-            "{}\t{}\t{:.2}\t{:.2}\t{:.2}\t{}\t{}\t{}\t{}\t{:.2}\t{:.2}\t{:.2}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}",
-            sketch.file_name,
-            sketch.file_name,
+            "{}\t{}\t{:.2}\t{:.2}\t{:.2}\t{}\t{}\t{}\t{}\t{:.2}\t{:.2}\t{:.2}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}\t{}\t{}\t{}\t{}\t{}\t{}",
+            sketch.name(),
+            sketch.name(),
             100,
             100,
             100,
@@ -75,14 +272,21 @@ fn write_ani_res_perfect(writer: &mut impl Write, sketch: &Sketch, ci: bool, ver
             -1,
             0,
             sketch.total_sequence_length,
+            sketch.file_name,
+            sketch.file_name,
+            sketch.genome_stats.dropped_contigs,
+            sketch.genome_stats.dropped_contigs,
+            sketch.genome_stats.dropped_bases,
+            sketch.genome_stats.dropped_bases,
         )
         .unwrap();
     }
+    write_extra_perfect(writer, sketch, extra);
 }
 
-fn write_ani_res(writer: &mut impl Write, ani_res: &AniEstResult, ci: bool, verbose: bool, short_header: bool) {
+fn write_ani_res(writer: &mut impl Write, ani_res: &AniEstResult, ci: bool, verbose: bool, short_header: bool, extra: &ExtraColumns) {
     if !ci && !verbose {
-        writeln!(
+        write!(
             writer,
             "{}\t{}\t{:.2}\t{:.2}\t{:.2}\t{}\t{}",
             ani_res.ref_file,
@@ -95,7 +299,7 @@ fn write_ani_res(writer: &mut impl Write, ani_res: &AniEstResult, ci: bool, verb
         )
         .unwrap();
     } else if !verbose {
-        writeln!(
+        write!(
             writer,
             "{}\t{}\t{:.2}\t{:.2}\t{:.2}\t{}\t{}\t{:.2}\t{:.2}",
             ani_res.ref_file,
@@ -110,9 +314,9 @@ fn write_ani_res(writer: &mut impl Write, ani_res: &AniEstResult, ci: bool, verb
         )
         .unwrap();
     } else {
-        writeln!(
+        write!(
             writer,
-            "{}\t{}\t{:.2}\t{:.2}\t{:.2}\t{}\t{}\t{}\t{}\t{:.2}\t{:.2}\t{:.2}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}",
+            "{}\t{}\t{:.2}\t{:.2}\t{:.2}\t{}\t{}\t{}\t{}\t{:.2}\t{:.2}\t{:.2}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}\t{:0}\t{}\t{}\t{}\t{}\t{}\t{}",
             ani_res.ref_file,
             ani_res.query_file,
             ani_res.ani * 100.,
@@ -133,21 +337,316 @@ fn write_ani_res(writer: &mut impl Write, ani_res: &AniEstResult, ci: bool, verb
             ani_res.quant_10_contig_len_q,
             ani_res.avg_chain_int_len,
             ani_res.total_bases_covered,
+            ani_res.ref_path,
+            ani_res.query_path,
+            ani_res.ref_stats.dropped_contigs,
+            ani_res.query_stats.dropped_contigs,
+            ani_res.ref_stats.dropped_bases,
+            ani_res.query_stats.dropped_bases,
         )
         .unwrap();
     }
+    write_extra(writer, ani_res, extra);
+}
+
+/// `-` as an input path reads from stdin.
+pub fn is_stdin(file: &str) -> bool {
+    file == STDIO_PATH
+}
+
+/// Magic numbers of gzip, bzip2, xz and zstd, which needletail reads transparently.
+const COMPRESSED_MAGIC: [&[u8]; 4] = [b"\x1f\x8b", b"BZh", b"\xfd7zXZ\x00", b"\x28\xb5\x2f\xfd"];
+
+/// Whether stdin holds a serialized sketch rather than fasta/fastq, from a peek at its first
+/// bytes, which stay buffered for the actual read. Sketches start with c as a little-endian
+/// u64, so their eighth byte is zero. Plain fasta/fastq has no zero bytes, and compressed
+/// files are told apart by their magic numbers.
+pub fn stdin_is_sketch() -> bool {
+    match io::stdin().lock().fill_buf() {
+        Ok(buf) => buf.len() >= 8 && buf[7] == 0 && !COMPRESSED_MAGIC.iter().any(|magic| buf.starts_with(magic)),
+        Err(_) => false,
+    }
+}
+
+/// All of stdin; only used for sketches, since fasta/fastq is streamed.
+fn read_stdin() -> Vec<u8> {
+    let mut bytes = vec![];
+    if let Err(e) = io::stdin().lock().read_to_end(&mut bytes) {
+        error!("Problem reading stdin: {}. Exiting.", e);
+        std::process::exit(1)
+    }
+    bytes
 }
 
 pub fn fastx_to_sketches(
     ref_files: &Vec<String>,
     sketch_params: &SketchParams,
     seed: bool,
+) -> Vec<Sketch> {
+    fastx_groups_to_sketches(ref_files, &GenomeGroups::default(), sketch_params, seed)
+}
+
+/// Why a sequence file could not be read to the end.
+pub enum SeqFileError {
+    /// Not openable, or not fasta/fastq/GenBank/EMBL/GFA/2bit.
+    Unreadable,
+    /// A fasta/fastq record part-way through is malformed; earlier records were read.
+    InvalidRecord,
+    /// A GenBank/EMBL file failed to parse; no records were read.
+    InvalidFlatFile(String),
+    /// A GFA segment record is malformed; earlier segments were read.
+    InvalidGfa(String),
+    /// A 2bit file is corrupt; earlier sequences were read.
+    InvalidTwoBit(String),
+}
+
+impl SeqFileError {
+    pub fn message(&self, file: &str) -> String {
+        match self {
+            SeqFileError::Unreadable => format!("{} is not a valid fasta/fastq file; skipping.", file),
+            SeqFileError::InvalidRecord => format!("File {} is not a valid fasta/fastq file", file),
+            SeqFileError::InvalidFlatFile(e) => format!("Invalid GenBank/EMBL file {}; skipping.", e),
+            SeqFileError::InvalidGfa(e) => format!("Invalid GFA file {}", e),
+            SeqFileError::InvalidTwoBit(e) => format!("Invalid 2bit file {}", e),
+        }
+    }
+}
+
+/// Calls `f(name, seq, cds)` for every record of a fasta/fastq (possibly gzipped),
+/// GenBank/EMBL, GFA or 2bit file; GFA segments are the records. `cds` holds the annotated
+/// CDSs of GenBank/EMBL records and is None otherwise.
+pub fn for_each_contig<F>(file: &str, mut f: F) -> Result<(), SeqFileError>
+where
+    F: FnMut(&[u8], &[u8], Option<Vec<Orf>>),
+{
+    let reader = if is_stdin(file) {
+        parse_fastx_reader(io::stdin())
+    } else {
+        if let Some(format) = flat_file::detect_flat_format(file) {
+            let records = flat_file::read_flat_file(file, format).map_err(SeqFileError::InvalidFlatFile)?;
+            for record in records.iter() {
+                f(record.name.as_bytes(), &record.seq, Some(record.cds_orfs()));
+            }
+            return Ok(());
+        }
+        if twobit::is_twobit(file) {
+            return twobit::for_each_sequence(file, |name, seq| f(name, seq, None))
+                .map_err(SeqFileError::InvalidTwoBit);
+        }
+        if gfa::is_gfa(file) {
+            return gfa::for_each_segment(file, |id, seq| f(id, seq, None)).map_err(SeqFileError::InvalidGfa);
+        }
+        parse_fastx_file(file)
+    };
+    let mut reader = reader.map_err(|_| SeqFileError::Unreadable)?;
+    while let Some(record) = reader.next() {
+        let record = record.map_err(|_| SeqFileError::InvalidRecord)?;
+        f(record.id(), &record.seq(), None);
+    }
+    Ok(())
+}
+
+/// Contigs shorter than this are not sketched: `sketch_params.min_segment_length` for GFA
+/// segments, `sketch_params.min_contig_length` otherwise.
+fn min_contig_length(file: &str, sketch_params: &SketchParams) -> usize {
+    if !is_stdin(file) && gfa::is_gfa(file) {
+        sketch_params.min_segment_length
+    } else {
+        sketch_params.min_contig_length
+    }
+}
+
+/// Compiles a header regex given on the command line or stored in a sketch.
+pub fn contig_regex(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| format!("Invalid contig header regex '{}': {}", pattern, e))
+}
+
+/// The include/exclude header filters of `sketch_params`. Patterns were checked when
+/// parsing arguments, so an invalid one can only come from a corrupt sketch.
+struct ContigFilter {
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+}
+
+impl ContigFilter {
+    fn new(sketch_params: &SketchParams) -> ContigFilter {
+        let compile = |pattern: &Option<String>| {
+            pattern.as_deref().map(|x| {
+                contig_regex(x).unwrap_or_else(|e| {
+                    error!("{}", e);
+                    std::process::exit(1)
+                })
+            })
+        };
+        ContigFilter {
+            include: compile(&sketch_params.contig_include),
+            exclude: compile(&sketch_params.contig_exclude),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.include.is_some() || self.exclude.is_some()
+    }
+
+    fn keep(&self, contig: &[u8]) -> bool {
+        let included = match &self.include {
+            Some(include) => include.is_match(contig),
+            None => true,
+        };
+        included && !self.exclude.as_ref().is_some_and(|x| x.is_match(contig))
+    }
+}
+
+/// Reads a BED file (`contig<TAB>start<TAB>end`, 0-based, end exclusive) into sorted,
+/// merged regions per contig. Comment, track and browser lines are skipped.
+pub fn read_mask_bed(file: &str) -> Result<MaskRegions, String> {
+    let text = std::fs::read_to_string(file).map_err(|e| format!("{}: {}", file, e))?;
+    let mut mask_regions = MaskRegions::default();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty()
+            || line.starts_with('#')
+            || line.starts_with("track")
+            || line.starts_with("browser")
+        {
+            continue;
+        }
+        let fields = line.split('\t').map(|x| x.trim()).collect::<Vec<&str>>();
+        let region = match fields.as_slice() {
+            [contig, start, end, ..] => match (start.parse::<usize>(), end.parse::<usize>()) {
+                (Ok(start), Ok(end)) if start <= end => Some((contig.to_string(), start, end)),
+                _ => None,
+            },
+            _ => None,
+        };
+        match region {
+            Some((contig, start, end)) => mask_regions.entry(contig).or_default().push((start, end)),
+            None => {
+                return Err(format!(
+                    "{}: line {} is not of the form contig<TAB>start<TAB>end",
+                    file,
+                    i + 1
+                ))
+            }
+        }
+    }
+    for regions in mask_regions.values_mut() {
+        regions.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = vec![];
+        for (start, end) in regions.drain(..) {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = usize::max(last.1, end),
+                _ => merged.push((start, end)),
+            }
+        }
+        *regions = merged;
+    }
+    Ok(mask_regions)
+}
+
+/// Regions of `sketch_params.mask_bed`. The file was checked when parsing arguments, but
+/// parameters inherited from a sketch may name a BED file that is not available here.
+fn load_mask_regions(sketch_params: &SketchParams) -> MaskRegions {
+    match &sketch_params.mask_bed {
+        Some(file) => read_mask_bed(file).unwrap_or_else(|e| {
+            warn!("Mask BED file could not be read ({}); sketching without it.", e);
+            MaskRegions::default()
+        }),
+        None => MaskRegions::default(),
+    }
+}
+
+/// The sequence to seed, with soft-masked bases (if `soft_mask`) and the BED regions of this
+/// contig set to N, and the number of bases newly masked. BED contig names are matched
+/// against the first word of the header.
+fn mask_contig<'a>(
+    contig: &[u8],
+    seq: &'a [u8],
+    soft_mask: bool,
+    mask_regions: &MaskRegions,
+) -> (Cow<'a, [u8]>, usize) {
+    let name = contig.split(|x| x.is_ascii_whitespace()).next().unwrap_or(contig);
+    let regions = std::str::from_utf8(name).ok().and_then(|x| mask_regions.get(x));
+    if !soft_mask && regions.is_none() {
+        return (Cow::Borrowed(seq), 0);
+    }
+    let mut masked = seq.to_vec();
+    let mut num_masked = 0;
+    let mut mask = |x: &mut u8| {
+        if *x != b'N' && *x != b'n' {
+            *x = b'N';
+            num_masked += 1;
+        }
+    };
+    if soft_mask {
+        masked.iter_mut().filter(|x| x.is_ascii_lowercase()).for_each(&mut mask);
+    }
+    for &(start, end) in regions.into_iter().flatten() {
+        let end = usize::min(end, masked.len());
+        let start = usize::min(start, end);
+        masked[start..end].iter_mut().for_each(&mut mask);
+    }
+    (Cow::Owned(masked), num_masked)
+}
+
+/// Seeds and markers of one contig. In AAI mode, annotated CDSs replace predicted ORFs
+/// if `sketch_params.annotated_cds` is set and the input had annotations.
+fn seed_contig(
+    seq: &[u8],
+    cds: Option<Vec<Orf>>,
+    sketch_params: &SketchParams,
+    contig_index: u32,
+    new_sketch: &mut Sketch,
+    seed: bool,
+) {
+    if sketch_params.use_aa {
+        let mut orfs = match cds {
+            Some(cds) if sketch_params.annotated_cds => cds,
+            _ => seeding::get_orfs(seq, sketch_params),
+        };
+        if sketch_params.soft_mask || sketch_params.mask_bed.is_some() {
+            //Masked bases are Ns by now; ORFs running through them are not translated.
+            orfs.retain(|orf| !seq[orf.start..orf.end + 3].contains(&b'N'));
+        }
+        seeding::fmh_seeds_aa_with_orf(seq, sketch_params, contig_index, new_sketch, orfs, seed)
+    } else {
+        #[cfg(any(target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                use crate::avx2_seeding;
+                unsafe {
+                    avx2_seeding::avx2_fmh_seeds(seq, sketch_params, contig_index, new_sketch, seed);
+                }
+            } else {
+                seeding::fmh_seeds(seq, sketch_params, contig_index, new_sketch, seed);
+            }
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            seeding::fmh_seeds(seq, sketch_params, contig_index, new_sketch, seed);
+        }
+    }
+}
+
+/// Like `fastx_to_sketches`, but an input that names a genome in `genome_groups` is
+/// sketched from all of that genome's files, with contigs indexed consecutively across
+/// the files. The genome name is used as the sketch's file name.
+pub fn fastx_groups_to_sketches(
+    ref_files: &Vec<String>,
+    genome_groups: &GenomeGroups,
+    sketch_params: &SketchParams,
+    seed: bool,
 ) -> Vec<Sketch> {
     let ref_sketches: Mutex<Vec<_>> = Mutex::new(vec![]);
+    let mask_regions = load_mask_regions(sketch_params);
+    let contig_filter = ContigFilter::new(sketch_params);
     let mut index_vec = (0..ref_files.len()).collect::<Vec<usize>>();
     index_vec.shuffle(&mut thread_rng());
     index_vec.into_par_iter().for_each(|i| {
         let ref_file = &ref_files[i];
+        let genome_files = match genome_groups.get(ref_file) {
+            Some(files) => files.clone(),
+            None => vec![ref_file.clone()],
+        };
         let mut new_sketch = Sketch::new(
             sketch_params.marker_c,
             sketch_params.c,
@@ -155,94 +654,80 @@ pub fn fastx_to_sketches(
             ref_file.to_string(),
             sketch_params.use_aa,
         );
-        let reader = parse_fastx_file(ref_file);
-        if reader.is_err() {
-            if ref_file.contains(".sketch"){
-                warn!("{} is not a valid fasta/fastq file but has the .sketch extension. Not all inputs have .sketch extension, so fasta/fastq is assumed.", ref_file);
-            }
-            else{
-                warn!("{} is not a valid fasta/fastq file; skipping.", ref_file);
-            }
-        } else {
-            let mut j = 0;
-            let mut is_valid = true;
-            let mut reader = reader.unwrap();
-            trace!("Sketching {} {}", new_sketch.file_name, i);
-            while let Some(record) = reader.next() {
-                if record.is_ok() {
-                    let record = record.unwrap_or_else(|_| panic!("Invalid record for file {}", ref_file));
-                    let contig = record.id();
-                    let seq = record.seq();
-                    if seq.len() >= MIN_LENGTH_CONTIG {
-                        new_sketch
-                            .contigs
-                            .push(String::from_utf8(contig.to_vec()).unwrap());
-                        new_sketch.contig_lengths.push(seq.len() as GnPosition);
-
-                        new_sketch.total_sequence_length += seq.len();
-                        if sketch_params.use_aa {
-                            let orfs = seeding::get_orfs(&seq, sketch_params);
-                            seeding::fmh_seeds_aa_with_orf(
-                                &seq,
-                                sketch_params,
-                                j as u32,
-                                &mut new_sketch,
-                                orfs,
-                                seed,
-                            )
-                        } else {
-                            #[cfg(any(target_arch = "x86_64"))]
-                            {
-                                if is_x86_feature_detected!("avx2"){
-                                    use crate::avx2_seeding;
-                                    unsafe {
-                                        avx2_seeding::avx2_fmh_seeds(
-                                            &seq,
-                                            sketch_params,
-                                            j as u32,
-                                            &mut new_sketch,
-                                            seed,
-                                        );
-                                    }
-                                } else {
-                                    seeding::fmh_seeds(
-                                        &seq,
-                                        sketch_params,
-                                        j as u32,
-                                        &mut new_sketch,
-                                        seed,
-                                    );
-                                }
-                            }
-                            #[cfg(not(target_arch = "x86_64"))]
-                            {
-                            seeding::fmh_seeds(
-                                        &seq,
-                                        sketch_params,
-                                        j as u32,
-                                        &mut new_sketch,
-                                        seed,
-                                    );
-                            }
-
-                        }
-                        //new_sketch.contig_order = 0;
-                        j += 1;
-                    }
+        new_sketch.spaced_seed = sketch_params.spaced_seed.clone();
+        new_sketch.marker_k = sketch_params.marker_k;
+        new_sketch.min_contig_length = sketch_params.min_contig_length;
+        let mut j = 0;
+        let mut is_valid = true;
+        let mut tetra_counts = TetraCounts::default();
+        let mut min_length = MIN_LENGTH_CONTIG;
+        let mut iupac_bases = 0;
+        let mut dropped_contigs = 0;
+        let mut dropped_bases = 0;
+        trace!("Sketching {} {}", new_sketch.file_name, i);
+        for genome_file in genome_files.iter() {
+            min_length = min_contig_length(genome_file, sketch_params);
+            let res = for_each_contig(genome_file, |contig, seq, cds| {
+                if seq.len() < min_length || !contig_filter.keep(contig) {
+                    dropped_contigs += 1;
+                    dropped_bases += seq.len() as u64;
                 } else {
-                    warn!("File {} is not a valid fasta/fastq file", ref_file);
-                    is_valid = false;
-                    break;
+                    new_sketch
+                        .contigs
+                        .push(String::from_utf8(contig.to_vec()).unwrap());
+                    new_sketch.contig_lengths.push(seq.len() as GnPosition);
+
+                    new_sketch.total_sequence_length += seq.len();
+                    tetra_counts.add_seq(seq);
+                    iupac_bases += count_iupac_bases(seq);
+                    let (masked_seq, masked_bases) = mask_contig(contig, seq, sketch_params.soft_mask, &mask_regions);
+                    new_sketch.masked_bases += masked_bases;
+                    seed_contig(&masked_seq, cds, sketch_params, j as u32, &mut new_sketch, seed);
+                    //new_sketch.contig_order = 0;
+                    j += 1;
                 }
-            }
-            if is_valid && j > 0{
-                {
-                    let mut locked = ref_sketches.lock().unwrap();
-                    locked.push(new_sketch);
+            });
+            match &res {
+                Ok(()) => {}
+                Err(SeqFileError::Unreadable) => {
+                    if genome_file.contains(".sketch"){
+                        warn!("{} is not a valid fasta/fastq file but has the .sketch extension. Not all inputs have .sketch extension, so fasta/fastq is assumed.", genome_file);
+                    }
+                    else if genome_file != ref_file {
+                        warn!("{} is not a valid fasta/fastq file; skipping genome {}.", genome_file, ref_file);
+                    }
+                    else{
+                        warn!("{}", SeqFileError::Unreadable.message(genome_file));
+                    }
                 }
+                Err(e) => warn!("{}", e.message(genome_file)),
             }
-            if j == 0 && is_valid{
-                warn!("File {} consists of only contigs < {} bp. Skipping this file.",  ref_file, MIN_LENGTH_CONTIG);
+            if res.is_err() {
+                is_valid = false;
+                break;
+            }
+        }
+        if is_valid && j > 0{
+            warn_iupac_bases(ref_file, iupac_bases);
+            if let Some(repeat_mask) = sketch_params.repeat_mask {
+                seeding::mask_repeats(&mut new_sketch, repeat_mask);
+            }
+            new_sketch.tetra_zscores = tetra_counts.zscores();
+            new_sketch.genome_stats =
+                GenomeStats::from_contigs(&new_sketch.contig_lengths, Some(tetra_counts.base_counts()));
+            new_sketch.genome_stats.dropped_contigs = dropped_contigs;
+            new_sketch.genome_stats.dropped_bases = dropped_bases;
+            {
+                let mut locked = ref_sketches.lock().unwrap();
+                locked.push(new_sketch);
+            }
+        }
+        if j == 0 && is_valid{
+            if contig_filter.is_active() {
+                warn!("File {} has no contigs >= {} bp passing the contig header filters. Skipping this file.", ref_file, min_length);
+            }
+            else{
+                warn!("File {} consists of only contigs < {} bp. Skipping this file.",  ref_file, min_length);
             }
         }
     });
@@ -250,111 +735,102 @@ pub fn fastx_to_sketches(
     ref_sketches.sort();
     ref_sketches
 }
+/// Seeding treats IUPAC codes other than N as k-mer breaks, which is worth pointing out since
+/// they are unusual outside consensus or variant-aware assemblies.
+fn warn_iupac_bases(file: &str, iupac_bases: usize) {
+    if iupac_bases > 0 {
+        warn!(
+            "{} has {} bases with IUPAC ambiguity codes other than N; k-mers containing them are not seeded.",
+            file, iupac_bases
+        );
+    }
+}
+
+/// One sketch per contig. Every contig sketch of a file records the contigs and bases
+/// dropped from the whole file.
 pub fn fastx_to_multiple_sketch_rewrite(
     ref_files: &Vec<String>,
     sketch_params: &SketchParams,
     seed: bool,
 ) -> Vec<Sketch> {
     let ref_sketches: Mutex<Vec<_>> = Mutex::new(vec![]);
+    let mask_regions = load_mask_regions(sketch_params);
+    let contig_filter = ContigFilter::new(sketch_params);
     let mut index_vec = (0..ref_files.len()).collect::<Vec<usize>>();
     index_vec.shuffle(&mut thread_rng());
     index_vec.into_par_iter().for_each(|i| {
         let mut small_contig_warn = false;
         let ref_file = &ref_files[i];
-        let reader = parse_fastx_file(ref_file);
-        if reader.is_err() {
-            warn!("{} is not a valid fasta/fastq file; skipping.", ref_file);
-        } else {
-            let mut j = 0;
-            let mut reader = reader.unwrap();
-            trace!("Sketching {} {}", ref_file, i);
-            while let Some(record) = reader.next() {
-                if record.is_ok() {
-                    let record =
-                        record.unwrap_or_else(|_| panic!("Invalid record for file {}", ref_file));
-                    let contig = record.id();
-                    let seq = record.seq();
-                    if seq.len() >= MIN_LENGTH_CONTIG {
-                        let mut new_sketch = Sketch::new(
-                            sketch_params.marker_c,
-                            sketch_params.c,
-                            sketch_params.k,
-                            ref_file.to_string(),
-                            sketch_params.use_aa,
-                        );
-                        new_sketch
-                            .contigs
-                            .push(String::from_utf8(contig.to_vec()).unwrap());
-                        new_sketch.contig_lengths.push(seq.len() as GnPosition);
-
-                        new_sketch.total_sequence_length += seq.len();
-                        if sketch_params.use_aa {
-                            let orfs = seeding::get_orfs(&seq, sketch_params);
-                            seeding::fmh_seeds_aa_with_orf(
-                                &seq,
-                                sketch_params,
-                                0_u32,
-                                &mut new_sketch,
-                                orfs,
-                                seed,
-                            )
-                        } else {
-                            #[cfg(any(target_arch = "x86_64"))]
-                            {
-                                if is_x86_feature_detected!("avx2") {
-                                    use crate::avx2_seeding;
-                                    unsafe {
-                                        avx2_seeding::avx2_fmh_seeds(
-                                            &seq,
-                                            sketch_params,
-                                            0_u32,
-                                            &mut new_sketch,
-                                            seed,
-                                        );
-                                    }
-                                } else {
-                                    seeding::fmh_seeds(
-                                        &seq,
-                                        sketch_params,
-                                        0_u32,
-                                        &mut new_sketch,
-                                        seed,
-                                    );
-                                }
-                            }
-                            #[cfg(not(target_arch = "x86_64"))]
-                            {
-                            seeding::fmh_seeds(
-                                        &seq,
-                                        sketch_params,
-                                        j as u32,
-                                        &mut new_sketch,
-                                        seed,
-                                    );
-                            }
-                        }
-                        new_sketch.contig_order = j;
-
-//                        if new_sketch.total_sequence_length > REPET_KMER_THRESHOLD {
-//                            new_sketch.repetitive_kmers =
-//                                seeding::get_repetitive_kmers(&new_sketch.kmer_seeds_k, new_sketch.c);
-//                        }
-
-                        let mut locked = ref_sketches.lock().unwrap();
-                        locked.push(new_sketch);
-                        j += 1;
-                    }
-                    else if !small_contig_warn
-                    {
-                        small_contig_warn = true;
-                        warn!("At least one sequence in file {} has < {} bp. These sequences will be skipped.", ref_file, MIN_LENGTH_CONTIG);
-                    }
-                } else {
-                    warn!("File {} is not a valid fasta/fastq file", ref_file);
-                    break;
+        let mut j = 0;
+        let min_length = min_contig_length(ref_file, sketch_params);
+        let mut iupac_bases = 0;
+        let mut dropped_contigs = 0;
+        let mut dropped_bases = 0;
+        let mut file_sketches = vec![];
+        trace!("Sketching {} {}", ref_file, i);
+        let res = for_each_contig(ref_file, |contig, seq, cds| {
+            if !contig_filter.keep(contig) {
+                dropped_contigs += 1;
+                dropped_bases += seq.len() as u64;
+                return;
+            }
+            if seq.len() >= min_length {
+                iupac_bases += count_iupac_bases(seq);
+                let mut new_sketch = Sketch::new(
+                    sketch_params.marker_c,
+                    sketch_params.c,
+                    sketch_params.k,
+                    ref_file.to_string(),
+                    sketch_params.use_aa,
+                );
+                new_sketch.spaced_seed = sketch_params.spaced_seed.clone();
+                new_sketch.marker_k = sketch_params.marker_k;
+                new_sketch.min_contig_length = sketch_params.min_contig_length;
+                new_sketch
+                    .contigs
+                    .push(String::from_utf8(contig.to_vec()).unwrap());
+                new_sketch.contig_lengths.push(seq.len() as GnPosition);
+
+                new_sketch.total_sequence_length += seq.len();
+                let mut tetra_counts = TetraCounts::default();
+                tetra_counts.add_seq(seq);
+                new_sketch.tetra_zscores = tetra_counts.zscores();
+                new_sketch.genome_stats =
+                    GenomeStats::from_contigs(&new_sketch.contig_lengths, Some(tetra_counts.base_counts()));
+                let (masked_seq, masked_bases) = mask_contig(contig, seq, sketch_params.soft_mask, &mask_regions);
+                new_sketch.masked_bases = masked_bases;
+                seed_contig(&masked_seq, cds, sketch_params, 0_u32, &mut new_sketch, seed);
+                if let Some(repeat_mask) = sketch_params.repeat_mask {
+                    seeding::mask_repeats(&mut new_sketch, repeat_mask);
                 }
+                new_sketch.contig_order = j;
+
+//                if new_sketch.total_sequence_length > REPET_KMER_THRESHOLD {
+//                    new_sketch.repetitive_kmers =
+//                        seeding::get_repetitive_kmers(&new_sketch.kmer_seeds_k, new_sketch.c);
+//                }
+
+                file_sketches.push(new_sketch);
+                j += 1;
             }
+            else {
+                dropped_contigs += 1;
+                dropped_bases += seq.len() as u64;
+                if !small_contig_warn {
+                    small_contig_warn = true;
+                    warn!("At least one sequence in file {} has < {} bp. These sequences will be skipped.", ref_file, min_length);
+                }
+            }
+        });
+        if let Err(e) = res {
+            warn!("{}", e.message(ref_file));
         }
+        warn_iupac_bases(ref_file, iupac_bases);
+        for sketch in file_sketches.iter_mut() {
+            sketch.genome_stats.dropped_contigs = dropped_contigs;
+            sketch.genome_stats.dropped_bases = dropped_bases;
+        }
+        ref_sketches.lock().unwrap().extend(file_sketches);
     });
     let mut ref_sketches = ref_sketches.into_inner().unwrap();
     ref_sketches.sort();
@@ -380,11 +856,11 @@ pub fn write_phyllip_matrix(
         let mut handle = stdout.lock();
         writeln!(&mut handle, "{}", sketches.len()).unwrap();
         for i in 0..sketches.len() {
-            let name;
+            let name: &str;
             if use_contig_names {
                 name = &sketches[i].contigs[0];
             } else {
-                name = &sketches[i].file_name;
+                name = sketches[i].name();
             }
             write!(&mut handle, "{}", name).unwrap();
             let end;
@@ -423,11 +899,11 @@ pub fn write_phyllip_matrix(
         let mut af_file = BufWriter::new(File::create(af_mat_file).unwrap());
         writeln!(&mut af_file, "{}", sketches.len()).unwrap();
         for i in 0..sketches.len() {
-            let name;
+            let name: &str;
             if use_contig_names {
                 name = &sketches[i].contigs[0];
             } else {
-                name = &sketches[i].file_name;
+                name = sketches[i].name();
             }
             write!(&mut af_file, "{}", name).unwrap();
             //We always output full matrix for AF.
@@ -473,11 +949,11 @@ pub fn write_phyllip_matrix(
         writeln!(&mut ani_file, "{}", sketches.len()).unwrap();
         writeln!(&mut af_file, "{}", sketches.len()).unwrap();
         for i in 0..sketches.len() {
-            let name;
+            let name: &str;
             if use_contig_names {
                 name = &sketches[i].contigs[0];
             } else {
-                name = &sketches[i].file_name;
+                name = sketches[i].name();
             }
             write!(&mut ani_file, "{}", name).unwrap();
             write!(&mut af_file, "{}", name).unwrap();
@@ -548,24 +1024,26 @@ pub fn write_sparse_matrix(
     diag: bool,
     append: bool,
     short_header: bool,
+    extra: &ExtraColumns,
+    ci_params: &CiParams,
 ) {
-    let id_str = if aai { "AAI" } else { "ANI" };
+    let id_str = id_str(aai, extra);
     if file_name.is_empty() {
         let stdout = io::stdout();
         let mut handle = stdout.lock();
         if !append{
-            write_header(&mut handle, id_str, est_ci, detailed_out);
+            write_header(&mut handle, id_str, est_ci, detailed_out, extra, ci_params);
         }
         //        write!(&mut handle,"Ref_file\tQuery_file\t{}\tAlign_fraction_ref\tAlign_fraction_query\t{}_95_percentile\t{}_5_percentile\tRef_name\tQuery_name\n", id_str, id_str, id_str).unwrap();
         if diag{
             for sketch in sketches.iter(){
-                write_ani_res_perfect(&mut handle, sketch, est_ci, detailed_out, short_header);
+                write_ani_res_perfect(&mut handle, sketch, est_ci, detailed_out, short_header, extra);
             }
         }
         for i in anis.keys() {
             for (j, ani_res) in anis[i].iter() {
-                if !(anis[i][j].ani == -1. || anis[i][j].ani.is_nan()) {
-                    write_ani_res(&mut handle, ani_res, est_ci, detailed_out, short_header);
+                if is_reported(ani_res, extra) {
+                    write_ani_res(&mut handle, ani_res, est_ci, detailed_out, short_header, extra);
                 }
             }
         }
@@ -583,22 +1061,22 @@ pub fn write_sparse_matrix(
             ani_file = BufWriter::new(File::create(ani_mat_file).expect(file_name));
         }
         if !append{
-            write_header(&mut ani_file, id_str, est_ci, detailed_out);
+            write_header(&mut ani_file, id_str, est_ci, detailed_out, extra, ci_params);
         }
 
         if diag{
             for sketch in sketches.iter(){
-                write_ani_res_perfect(&mut ani_file, sketch, est_ci, detailed_out, short_header);
+                write_ani_res_perfect(&mut ani_file, sketch, est_ci, detailed_out, short_header, extra);
             }
         }
 
         for i in anis.keys() {
             if diag{
-                write_ani_res_perfect(&mut ani_file, &sketches[*i], est_ci, detailed_out, short_header);
+                write_ani_res_perfect(&mut ani_file, &sketches[*i], est_ci, detailed_out, short_header, extra);
             }
             for (j, ani_res) in anis[i].iter() {
-                if !(anis[i][j].ani == -1. || anis[i][j].ani.is_nan()) {
-                    write_ani_res(&mut ani_file, ani_res, est_ci, detailed_out, short_header);
+                if is_reported(ani_res, extra) {
+                    write_ani_res(&mut ani_file, ani_res, est_ci, detailed_out, short_header, extra);
                 }
             }
         }
@@ -614,13 +1092,15 @@ pub fn write_query_ref_list(
     detailed_out: bool,
     append: bool,
     short_header: bool,
+    extra: &ExtraColumns,
+    ci_params: &CiParams,
 ) {
-    let id_str = if aai { "AAI" } else { "ANI" };
+    let id_str = id_str(aai, extra);
     let mut query_file_result_map = FxHashMap::default();
     let out_file = file_name.to_string();
 
     for i in 0..anis.len() {
-        if anis[i].ani < 0. || anis[i].ani.is_nan() {
+        if !is_reported(&anis[i], extra) {
             continue;
         }
         let _ani = if anis[i].ani < 0. {
@@ -640,14 +1120,20 @@ pub fn write_query_ref_list(
         let stdout = io::stdout();
         let mut handle = stdout.lock();
         if !append{
-            write_header(&mut handle, id_str, est_ci, detailed_out);
+            write_header(&mut handle, id_str, est_ci, detailed_out, extra, ci_params);
         }
         for key in sorted_keys {
             let mut anis = query_file_result_map[key].clone();
 
-            anis.sort_by(|y, x| x.ani.partial_cmp(&y.ani).unwrap());
+            //Highest ANI first; TETRA-only rows (NaN ANI) last, by TETRA.
+            anis.sort_by(|y, x| {
+                (!x.ani.is_nan())
+                    .cmp(&!y.ani.is_nan())
+                    .then(x.ani.partial_cmp(&y.ani).unwrap_or(std::cmp::Ordering::Equal))
+                    .then(x.tetra.partial_cmp(&y.tetra).unwrap_or(std::cmp::Ordering::Equal))
+            });
             for i in 0..usize::min(n, anis.len()) {
-                write_ani_res(&mut handle, anis[i], est_ci, detailed_out, short_header);
+                write_ani_res(&mut handle, anis[i], est_ci, detailed_out, short_header, extra);
             }
         }
     } else {
@@ -664,19 +1150,98 @@ pub fn write_query_ref_list(
         }
 
         if !append{
-            write_header(&mut handle, id_str, est_ci, detailed_out);
+            write_header(&mut handle, id_str, est_ci, detailed_out, extra, ci_params);
         }
         for key in sorted_keys {
             let mut anis = query_file_result_map[key].clone();
 
-            anis.sort_by(|y, x| x.ani.partial_cmp(&y.ani).unwrap());
+            //Highest ANI first; TETRA-only rows (NaN ANI) last, by TETRA.
+            anis.sort_by(|y, x| {
+                (!x.ani.is_nan())
+                    .cmp(&!y.ani.is_nan())
+                    .then(x.ani.partial_cmp(&y.ani).unwrap_or(std::cmp::Ordering::Equal))
+                    .then(x.tetra.partial_cmp(&y.tetra).unwrap_or(std::cmp::Ordering::Equal))
+            });
             for i in 0..usize::min(n, anis.len()) {
-                write_ani_res(&mut handle, anis[i], est_ci, detailed_out, short_header);
+                write_ani_res(&mut handle, anis[i], est_ci, detailed_out, short_header, extra);
             }
         }
     }
 }
 
+/// Attach sample names from two-column input lists to sketches of the named files.
+pub fn set_display_names(sketches: &mut [Sketch], display_names: &DisplayNames) {
+    if display_names.is_empty() {
+        return;
+    }
+    for sketch in sketches.iter_mut() {
+        if let Some(name) = display_names.get(&sketch.file_name) {
+            sketch.display_name = Some(name.clone());
+        }
+    }
+}
+
+/// Seeds and markers are only comparable between sketches made with the same k, marker k
+/// and seed pattern. Returns an error naming the first pair that differs.
+pub fn check_seed_layouts(ref_sketches: &[Sketch], query_sketches: &[Sketch]) -> Result<(), String> {
+    let first = match ref_sketches.first().or(query_sketches.first()) {
+        Some(sketch) => sketch,
+        None => return Ok(()),
+    };
+    for sketch in ref_sketches.iter().chain(query_sketches.iter()) {
+        if !first.same_seed_layout(sketch) {
+            return Err(format!(
+                "{} ({}) and {} ({}) were sketched with different seeds and can not be compared.",
+                first.file_name,
+                first.seed_layout(),
+                sketch.file_name,
+                sketch.seed_layout()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Sketches made with different minimum contig lengths can be compared, but their aligned
+/// fractions are over different parts of the genomes. Returns a warning naming the first
+/// pair that differs.
+pub fn check_min_contig_lengths(ref_sketches: &[Sketch], query_sketches: &[Sketch]) -> Result<(), String> {
+    let first = match ref_sketches.first().or(query_sketches.first()) {
+        Some(sketch) => sketch,
+        None => return Ok(()),
+    };
+    for sketch in ref_sketches.iter().chain(query_sketches.iter()) {
+        if first.min_contig_length != sketch.min_contig_length {
+            return Err(format!(
+                "{} and {} were sketched with different minimum contig lengths ({} and {} bp); aligned fractions may not be comparable.",
+                first.file_name, sketch.file_name, first.min_contig_length, sketch.min_contig_length
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Reference and query sketches masked differently can be compared, but masked bases are
+/// excluded from the aligned fractions of one side only. Returns a warning if they differ.
+pub fn check_masks(ref_params: &SketchParams, query_params: &SketchParams) -> Result<(), String> {
+    if ref_params.soft_mask != query_params.soft_mask
+        || ref_params.mask_bed != query_params.mask_bed
+        || ref_params.repeat_mask != query_params.repeat_mask
+    {
+        return Err("Reference and query sketches were masked differently; aligned fractions may not be comparable.".to_string());
+    }
+    Ok(())
+}
+
+/// Deserialize a (parameters, sketch) pair, falling back to the v0.3 sketch layout.
+pub fn deserialize_sketch(bytes: &[u8]) -> bincode::Result<(SketchParams, Sketch)> {
+    bincode::deserialize::<(SketchParams, Sketch)>(bytes).or_else(|e| {
+        bincode::deserialize::<(SketchParamsV03, SketchV03)>(bytes)
+            .map(|(params, sketch)| (SketchParams::from(params), Sketch::from(sketch)))
+            .map_err(|_| e)
+    })
+}
+
 pub fn sketches_from_sketch(ref_files: &Vec<String>) -> (SketchParams, Vec<Sketch>) {
     let ret_sketch_params: Mutex<SketchParams> = Mutex::new(SketchParams::default());
     let ret_ref_sketches: Mutex<Vec<Sketch>> = Mutex::new(vec![]);
@@ -687,13 +1252,24 @@ pub fn sketches_from_sketch(ref_files: &Vec<String>) -> (SketchParams, Vec<Sketc
         .for_each(|i| {
             let sketch_file = &ref_files[i];
             if !sketch_file.contains("markers.bin") {
-                let f = File::open(sketch_file);
-                if f.is_err() {
-                    error!("Problem reading sketch file {}. Perhaps your file path is wrong? Exiting.", sketch_file);
-                    std::process::exit(1)
-                }
-                let reader = BufReader::new(f.unwrap());
-                let res: Result<(SketchParams, Sketch), _> = bincode::deserialize_from(reader);
+                let res = if is_stdin(sketch_file) {
+                    deserialize_sketch(&read_stdin())
+                } else {
+                    let f = File::open(sketch_file);
+                    if f.is_err() {
+                        error!("Problem reading sketch file {}. Perhaps your file path is wrong? Exiting.", sketch_file);
+                        std::process::exit(1)
+                    }
+                    let reader = BufReader::new(f.unwrap());
+                    let res: bincode::Result<(SketchParams, Sketch)> = bincode::deserialize_from(reader);
+                    //Re-read the file only to try the v0.3 layout.
+                    res.or_else(|e| {
+                        let reader = BufReader::new(File::open(sketch_file)?);
+                        bincode::deserialize_from::<_, (SketchParamsV03, SketchV03)>(reader)
+                            .map(|(params, sketch)| (SketchParams::from(params), Sketch::from(sketch)))
+                            .map_err(|_| e)
+                    })
+                };
                 if res.is_ok() {
                     let (temp_sketch_param, temp_ref_sketch) = res.unwrap();
                     let mut locked = ret_sketch_params.lock().unwrap();
@@ -719,7 +1295,14 @@ pub fn sketches_from_sketch(ref_files: &Vec<String>) -> (SketchParams, Vec<Sketc
 
 pub fn marker_sketches_from_marker_file(marker_file: &str) -> (SketchParams, Vec<Sketch>) {
     let reader = BufReader::new(File::open(marker_file).unwrap());
-    let res: Result<(SketchParams, Vec<Sketch>), _> = bincode::deserialize_from(reader);
+    let res: bincode::Result<(SketchParams, Vec<Sketch>)> = bincode::deserialize_from(reader);
+    //Re-read the file only to try the v0.3 layout.
+    let res = res.or_else(|e| {
+        let reader = BufReader::new(File::open(marker_file)?);
+        bincode::deserialize_from::<_, (SketchParamsV03, Vec<SketchV03>)>(reader)
+            .map(|(params, sketches)| (SketchParams::from(params), sketches.into_iter().map(Sketch::from).collect()))
+            .map_err(|_| e)
+    });
     if res.is_ok() {
         res.unwrap()
     } else {
@@ -727,3 +1310,39 @@ pub fn marker_sketches_from_marker_file(marker_file: &str) -> (SketchParams, Vec
         std::process::exit(1)
     }
 }
+
+/// Write windowed ANI/coverage in a bedGraph-like format. Every query uses the
+/// reference's coordinates so the tracks can be stacked.
+pub fn write_profile(
+    profiles: &Vec<(&str, Vec<ProfileWindow>)>,
+    ref_sketch: &Sketch,
+    file_name: &str,
+) {
+    let mut handle: Box<dyn Write> = if file_name.is_empty() {
+        Box::new(BufWriter::new(io::stdout().lock()))
+    } else {
+        Box::new(BufWriter::new(File::create(file_name).expect(file_name)))
+    };
+    writeln!(handle, "Ref_contig\tStart\tEnd\tQuery_file\tANI\tCoverage\tNum_seeds").unwrap();
+    for (query_file, windows) in profiles.iter() {
+        for window in windows.iter() {
+            let ani = if window.ani.is_nan() {
+                "NA".to_string()
+            } else {
+                format!("{:.2}", window.ani * 100.)
+            };
+            writeln!(
+                handle,
+                "{}\t{}\t{}\t{}\t{}\t{:.2}\t{}",
+                truncate_contig_name(&ref_sketch.contigs[window.ref_contig], true),
+                window.start,
+                window.end,
+                query_file,
+                ani,
+                window.coverage * 100.,
+                window.num_seeds,
+            )
+            .unwrap();
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Minimal GenBank and EMBL flat-file reader: record names, sequences and CDS features.
+//!
+//! Only what sketching needs is parsed. Contigs are named by accession (GenBank VERSION or
+//! ACCESSION, EMBL AC), falling back to the LOCUS/ID name. CDS features are kept when their
+//! location is a single, complete interval on either strand; joined, ordered and partial
+//! (`<`/`>`) locations are skipped. Compressed flat files are not supported.
+
+use crate::types::Orf;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FlatFormat {
+    GenBank,
+    Embl,
+}
+
+/// A CDS as a 0-based, end-exclusive interval including the stop codon.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Cds {
+    pub start: usize,
+    pub end: usize,
+    pub complement: bool,
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct FlatRecord {
+    pub name: String,
+    pub seq: Vec<u8>,
+    pub cds: Vec<Cds>,
+}
+
+impl FlatRecord {
+    /// Annotated CDSs in the form `seeding::fmh_seeds_aa_with_orf` expects: the range
+    /// `start..end + 3` is translated, and a phase > 2 means the reverse strand.
+    pub fn cds_orfs(&self) -> Vec<Orf> {
+        self.cds
+            .iter()
+            .map(|cds| Orf {
+                start: cds.start,
+                end: cds.end - 3,
+                phase: (cds.start % 3) as u8 + if cds.complement { 3 } else { 0 },
+            })
+            .collect()
+    }
+}
+
+/// Detects a flat file from its first non-empty line; fasta/fastq and compressed files
+/// give None.
+pub fn detect_flat_format(file: &str) -> Option<FlatFormat> {
+    let mut reader = BufReader::new(File::open(file).ok()?);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        //Bails out on binary (e.g. gzipped) input, which is not valid UTF-8.
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        if !line.trim().is_empty() {
+            break;
+        }
+    }
+    if line.starts_with("LOCUS") {
+        Some(FlatFormat::GenBank)
+    } else if line.starts_with("ID   ") {
+        Some(FlatFormat::Embl)
+    } else {
+        None
+    }
+}
+
+/// Parses every record of a GenBank or EMBL file.
+pub fn read_flat_file(file: &str, format: FlatFormat) -> Result<Vec<FlatRecord>, String> {
+    let mut text = String::new();
+    File::open(file)
+        .and_then(|mut f| f.read_to_string(&mut text))
+        .map_err(|e| format!("{}: {}", file, e))?;
+    parse_flat_records(&text, format).map_err(|e| format!("{}: {}", file, e))
+}
+
+#[derive(Default)]
+struct RecordBuilder {
+    locus: String,
+    accession: String,
+    version: String,
+    seq: Vec<u8>,
+    locations: Vec<String>,
+    in_features: bool,
+    in_location: bool,
+    in_sequence: bool,
+}
+
+impl RecordBuilder {
+    /// Feature table lines have the key in columns 6-20 and the location or qualifiers from
+    /// column 22 on, in both formats once the EMBL "FT" line code is blanked.
+    fn feature_line(&mut self, line: &str) {
+        let key = line.get(5..21).unwrap_or("").trim();
+        let value = line.get(21..).unwrap_or("").trim();
+        if !key.is_empty() {
+            self.in_location = key == "CDS";
+            if self.in_location {
+                self.locations.push(value.to_string());
+            }
+        } else if value.starts_with('/') {
+            self.in_location = false;
+        } else if self.in_location {
+            self.locations.last_mut().unwrap().push_str(value);
+        }
+    }
+
+    fn sequence_line(&mut self, line: &str) {
+        self.seq.extend(
+            line.bytes()
+                .filter(|x| x.is_ascii_alphabetic())
+                .map(|x| x.to_ascii_uppercase()),
+        );
+    }
+
+    fn finish(self) -> Result<FlatRecord, String> {
+        let name = [self.version, self.accession, self.locus]
+            .into_iter()
+            .find(|x| !x.is_empty())
+            .ok_or_else(|| "record without a name".to_string())?;
+        let seq_len = self.seq.len();
+        let cds = self
+            .locations
+            .iter()
+            .filter_map(|x| parse_cds_location(x))
+            .filter(|x| x.end <= seq_len)
+            .collect();
+        Ok(FlatRecord {
+            name,
+            seq: self.seq,
+            cds,
+        })
+    }
+}
+
+fn first_token(x: &str) -> String {
+    x.split_whitespace()
+        .next()
+        .unwrap_or("")
+        .trim_end_matches(';')
+        .to_string()
+}
+
+fn parse_flat_records(text: &str, format: FlatFormat) -> Result<Vec<FlatRecord>, String> {
+    let mut records = vec![];
+    let mut builder: Option<RecordBuilder> = None;
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.starts_with("//") {
+            match builder.take() {
+                Some(b) => records.push(b.finish()?),
+                None => return Err("'//' without a record".to_string()),
+            }
+            continue;
+        }
+        let (code, rest) = match format {
+            FlatFormat::GenBank => {
+                let code = line.split_whitespace().next().unwrap_or("");
+                if line.starts_with(' ') { ("", line) } else { (code, line.get(code.len()..).unwrap_or("")) }
+            }
+            FlatFormat::Embl => (line.get(..2).unwrap_or(line).trim(), line.get(2..).unwrap_or("")),
+        };
+        if code == "LOCUS" || (format == FlatFormat::Embl && code == "ID") {
+            if builder.is_some() {
+                return Err("record not terminated by '//'".to_string());
+            }
+            builder = Some(RecordBuilder {
+                locus: first_token(rest),
+                ..Default::default()
+            });
+            continue;
+        }
+        let b = match builder.as_mut() {
+            Some(b) => b,
+            None if line.is_empty() => continue,
+            None => return Err(format!("unexpected line outside a record: {}", line)),
+        };
+        if b.in_sequence {
+            b.sequence_line(rest);
+            continue;
+        }
+        match (format, code) {
+            (FlatFormat::GenBank, "ACCESSION") | (FlatFormat::Embl, "AC") => {
+                if b.accession.is_empty() {
+                    b.accession = first_token(rest);
+                }
+            }
+            (FlatFormat::GenBank, "VERSION") => b.version = first_token(rest),
+            (FlatFormat::GenBank, "FEATURES") => b.in_features = true,
+            (FlatFormat::GenBank, "ORIGIN") | (FlatFormat::Embl, "SQ") => b.in_sequence = true,
+            (FlatFormat::GenBank, "") if b.in_features => b.feature_line(line),
+            (FlatFormat::GenBank, _) => b.in_features = false,
+            (FlatFormat::Embl, "FT") => b.feature_line(&format!("  {}", rest)),
+            _ => {}
+        }
+    }
+    if builder.is_some() {
+        return Err("last record not terminated by '//'".to_string());
+    }
+    Ok(records)
+}
+
+/// Parses `a..b` or `complement(a..b)` (1-based, inclusive). Other locations give None.
+fn parse_cds_location(location: &str) -> Option<Cds> {
+    let location: String = location.chars().filter(|x| !x.is_whitespace()).collect();
+    let (interval, complement) = match location.strip_prefix("complement(") {
+        Some(inner) => (inner.strip_suffix(')')?, true),
+        None => (location.as_str(), false),
+    };
+    let (start, end) = interval.split_once("..")?;
+    let start = start.parse::<usize>().ok()?;
+    let end = end.parse::<usize>().ok()?;
+    if start == 0 || end < start + 2 || (end - start + 1) % 3 != 0 {
+        return None;
+    }
+    Some(Cds {
+        start: start - 1,
+        end,
+        complement,
+    })
+}
@@ -0,0 +1,76 @@
+//! GFA assembly graph input. `S` segment records are read as contigs named by segment ID;
+//! links, paths and all other records are ignored. Both GFA1 (`S id seq`) and GFA2
+//! (`S id len seq`, announced by `VN:Z:2.x` in the header) are understood. Segments without
+//! a stored sequence (`*`) are skipped. Compressed GFA is not supported.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// Detects a GFA file from its first non-empty line, which must be a header or segment
+/// record.
+pub fn is_gfa(file: &str) -> bool {
+    let mut reader = match File::open(file) {
+        Ok(f) => BufReader::new(f),
+        Err(_) => return false,
+    };
+    let mut line = vec![];
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => return false,
+            Ok(_) => {}
+        }
+        if !line.iter().all(|x| x.is_ascii_whitespace()) {
+            break;
+        }
+    }
+    line.starts_with(b"H\t") || line.starts_with(b"S\t")
+}
+
+/// Calls `f(segment_id, seq)` for every segment with a sequence. Segments before a
+/// malformed record have already been passed to `f` when an error is returned.
+pub fn for_each_segment<F>(file: &str, mut f: F) -> Result<(), String>
+where
+    F: FnMut(&[u8], &[u8]),
+{
+    let mut reader = BufReader::new(File::open(file).map_err(|e| format!("{}: {}", file, e))?);
+    let mut line = vec![];
+    let mut gfa2 = false;
+    let mut line_number = 0;
+    loop {
+        line.clear();
+        if reader
+            .read_until(b'\n', &mut line)
+            .map_err(|e| format!("{}: {}", file, e))?
+            == 0
+        {
+            return Ok(());
+        }
+        line_number += 1;
+        while line.last().is_some_and(|x| x.is_ascii_whitespace()) {
+            line.pop();
+        }
+        let mut fields = line.split(|x| *x == b'\t');
+        match fields.next() {
+            Some(b"H") if fields.any(|x| x.starts_with(b"VN:Z:2")) => gfa2 = true,
+            Some(b"S") => {
+                let id = fields.next().filter(|x| !x.is_empty());
+                if gfa2 {
+                    fields.next();
+                }
+                let seq = fields.next().filter(|x| !x.is_empty());
+                match (id, seq) {
+                    (Some(_), Some(b"*")) => {}
+                    (Some(id), Some(seq)) => f(id, seq),
+                    _ => {
+                        return Err(format!(
+                            "{}: malformed segment record on line {}",
+                            file, line_number
+                        ))
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
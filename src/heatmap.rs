@@ -0,0 +1,295 @@
+use crate::svg::{self, Svg};
+use log::*;
+
+const MARGIN: f64 = 30.;
+const LABEL_SPACE: f64 = 150.;
+const LEGEND_SPACE: f64 = 60.;
+const MAX_LABELS: usize = 500;
+const NUM_COLOUR_BINS: usize = 64;
+const ANI_STOPS: [(u8, u8, u8); 3] = [(255, 255, 204), (253, 141, 60), (128, 0, 38)];
+const AF_STOPS: [(u8, u8, u8); 3] = [(247, 251, 255), (107, 174, 214), (8, 48, 107)];
+const MISSING_COLOUR: &str = "#ffffff";
+
+/// One computed pair from `skani triangle`; `af_i`/`af_j` are the aligned fractions of
+/// genome i and genome j respectively. Pairs that were not computed are missing.
+#[derive(Clone, Copy, Debug)]
+pub struct HeatmapPair {
+    pub i: usize,
+    pub j: usize,
+    pub ani: f32,
+    pub af_i: f32,
+    pub af_j: f32,
+}
+
+/// Condensed (upper triangular, no diagonal) symmetric matrix.
+struct Condensed {
+    n: usize,
+    vals: Vec<f32>,
+}
+
+impl Condensed {
+    fn new(n: usize, fill: f32) -> Condensed {
+        Condensed {
+            n,
+            vals: vec![fill; n * n.saturating_sub(1) / 2],
+        }
+    }
+    #[inline]
+    fn index(&self, i: usize, j: usize) -> usize {
+        let (a, b) = if i < j { (i, j) } else { (j, i) };
+        a * self.n - a * (a + 1) / 2 + (b - a - 1)
+    }
+    #[inline]
+    fn get(&self, i: usize, j: usize) -> f32 {
+        self.vals[self.index(i, j)]
+    }
+    #[inline]
+    fn set(&mut self, i: usize, j: usize, val: f32) {
+        let ind = self.index(i, j);
+        self.vals[ind] = val;
+    }
+}
+
+/// Average linkage (UPGMA) hierarchical clustering using the nearest-neighbour chain
+/// algorithm, which is O(n^2) time and uses a single condensed distance matrix.
+/// Returns the leaf order of the dendrogram.
+fn average_linkage_order(n: usize, mut dist: Condensed) -> Vec<usize> {
+    if n <= 2 {
+        return (0..n).collect();
+    }
+    let mut active = vec![true; n];
+    let mut size = vec![1usize; n];
+    //Dendrogram nodes: 0..n are leaves, n.. are merges.
+    let mut node_of_slot = (0..n).collect::<Vec<usize>>();
+    let mut children: Vec<(usize, usize)> = vec![];
+    let mut chain: Vec<usize> = vec![];
+    let mut remaining = n;
+
+    while remaining > 1 {
+        if chain.is_empty() {
+            chain.push(active.iter().position(|x| *x).unwrap());
+        }
+        let (a, b) = loop {
+            let a = *chain.last().unwrap();
+            let prev = if chain.len() >= 2 { Some(chain[chain.len() - 2]) } else { None };
+            //Prefer the previous chain element on ties so the chain always terminates.
+            let (mut best, mut best_d) = match prev {
+                Some(p) => (p, dist.get(a, p)),
+                None => (usize::MAX, f32::MAX),
+            };
+            for (k, &is_active) in active.iter().enumerate() {
+                if k == a || !is_active {
+                    continue;
+                }
+                let d = dist.get(a, k);
+                if d < best_d {
+                    best_d = d;
+                    best = k;
+                }
+            }
+            if Some(best) == prev {
+                chain.pop();
+                chain.pop();
+                break (a, best);
+            }
+            chain.push(best);
+        };
+
+        //Lance-Williams update for average linkage; merged cluster lives in slot a.
+        let (sa, sb) = (size[a] as f32, size[b] as f32);
+        for (k, &is_active) in active.iter().enumerate() {
+            if k == a || k == b || !is_active {
+                continue;
+            }
+            let d = (sa * dist.get(a, k) + sb * dist.get(b, k)) / (sa + sb);
+            dist.set(a, k, d);
+        }
+        active[b] = false;
+        size[a] += size[b];
+        children.push((node_of_slot[a], node_of_slot[b]));
+        node_of_slot[a] = n + children.len() - 1;
+        remaining -= 1;
+    }
+
+    let mut order = Vec::with_capacity(n);
+    let mut stack = vec![n + children.len() - 1];
+    while let Some(node) = stack.pop() {
+        if node < n {
+            order.push(node);
+        } else {
+            let (l, r) = children[node - n];
+            stack.push(r);
+            stack.push(l);
+        }
+    }
+    order
+}
+
+fn gradient(stops: &[(u8, u8, u8); 3], t: f64) -> (u8, u8, u8) {
+    let t = t.clamp(0., 1.);
+    if t < 0.5 {
+        svg::blend(stops[0], stops[1], t * 2.)
+    } else {
+        svg::blend(stops[1], stops[2], (t - 0.5) * 2.)
+    }
+}
+
+fn bin_colour(stops: &[(u8, u8, u8); 3], bin: usize) -> String {
+    let c = gradient(stops, bin as f64 / (NUM_COLOUR_BINS - 1) as f64);
+    svg::rgb(c.0, c.1, c.2)
+}
+
+/// Cells are aggregated onto a grid of at most `plot_size` x `plot_size` pixels and
+/// colours are quantized, so runs of equal colour along a row are drawn as a single
+/// rectangle. This keeps the SVG small for thousands of genomes.
+pub fn write_heatmap(
+    names: &[String],
+    pairs: &[HeatmapPair],
+    file_name: &str,
+    show_labels: bool,
+    af_overlay: bool,
+    plot_size: usize,
+) {
+    let n = names.len();
+    if n == 0 {
+        return;
+    }
+    let mut ani = Condensed::new(n, f32::NAN);
+    //AF of the genome in the row; indexed [row][col] through a map since it is asymmetric.
+    let mut af = fxhash::FxHashMap::default();
+    let mut min_ani = 1.0f32;
+    for pair in pairs {
+        if pair.i == pair.j || pair.ani <= 0. || pair.ani.is_nan() {
+            continue;
+        }
+        ani.set(pair.i, pair.j, pair.ani);
+        min_ani = f32::min(min_ani, pair.ani);
+        if af_overlay {
+            af.insert((pair.i, pair.j), pair.af_i);
+            af.insert((pair.j, pair.i), pair.af_j);
+        }
+    }
+    let min_ani = f32::min((min_ani * 100.).floor() / 100., 0.99) as f64;
+
+    let mut dist = Condensed::new(n, 1.);
+    for (d, a) in dist.vals.iter_mut().zip(ani.vals.iter()) {
+        if !a.is_nan() {
+            *d = 1. - a;
+        }
+    }
+    let order = average_linkage_order(n, dist);
+
+    let grid = usize::min(n, plot_size);
+    let cell = plot_size as f64 / grid as f64;
+    //Each grid cell is the mean of the (row, col) genome cells that fall into it.
+    let to_grid = |x: usize| x * grid / n;
+    let mut sums = vec![0f64; grid * grid];
+    let mut counts = vec![0u32; grid * grid];
+    for (r, &gr) in order.iter().enumerate() {
+        for (c, &gc) in order.iter().enumerate() {
+            let (row, col) = (to_grid(r), to_grid(c));
+            let upper = af_overlay && col > row;
+            let val = if gr == gc {
+                if upper { f32::NAN } else { 1. }
+            } else if upper {
+                *af.get(&(gr, gc)).unwrap_or(&f32::NAN)
+            } else {
+                ani.get(gr, gc)
+            };
+            if val.is_nan() {
+                continue;
+            }
+            let ind = row * grid + col;
+            sums[ind] += val as f64;
+            counts[ind] += 1;
+        }
+    }
+
+    let labels = show_labels && n <= MAX_LABELS;
+    if show_labels && !labels {
+        warn!("More than {} genomes; heatmap labels are not drawn.", MAX_LABELS);
+    }
+    let label_space = if labels { LABEL_SPACE } else { 0. };
+    let plot = plot_size as f64;
+    let mut svg = Svg::new(
+        MARGIN * 2. + plot + label_space,
+        MARGIN * 2. + plot + label_space + LEGEND_SPACE,
+    );
+
+    for gr in 0..grid {
+        let mut run_start = 0;
+        let mut run_colour: Option<String> = None;
+        for gc in 0..=grid {
+            let colour = if gc == grid {
+                None
+            } else {
+                let ind = gr * grid + gc;
+                let upper = af_overlay && gc > gr;
+                Some(if counts[ind] == 0 {
+                    MISSING_COLOUR.to_string()
+                } else {
+                    let mean = sums[ind] / counts[ind] as f64;
+                    if upper {
+                        let bin = (mean * (NUM_COLOUR_BINS - 1) as f64).round() as usize;
+                        bin_colour(&AF_STOPS, bin)
+                    } else {
+                        let t = (mean - min_ani) / (1. - min_ani);
+                        let bin = (t.clamp(0., 1.) * (NUM_COLOUR_BINS - 1) as f64).round() as usize;
+                        bin_colour(&ANI_STOPS, bin)
+                    }
+                })
+            };
+            if colour != run_colour {
+                if let Some(rc) = &run_colour {
+                    if rc != MISSING_COLOUR {
+                        svg.rect(
+                            MARGIN + run_start as f64 * cell,
+                            MARGIN + gr as f64 * cell,
+                            (gc - run_start) as f64 * cell,
+                            cell,
+                            rc,
+                        );
+                    }
+                }
+                run_start = gc;
+                run_colour = colour;
+            }
+        }
+    }
+    svg.rect_outline(MARGIN, MARGIN, plot, plot, "black");
+
+    if labels {
+        let font = f64::min(10., cell * 0.9);
+        for (r, &g) in order.iter().enumerate() {
+            let name = names[g].rsplit('/').next().unwrap();
+            let mid = MARGIN + (r as f64 + 0.5) * cell + font / 3.;
+            svg.text(MARGIN + plot + 4., mid, font, "start", 0., name);
+            let x = MARGIN + (r as f64 + 0.5) * cell;
+            svg.text(x, MARGIN + plot + 4., font, "start", 90., name);
+        }
+    }
+
+    //Colour bars
+    let legend_y = MARGIN + plot + label_space + 10.;
+    let bar_w = f64::min(200., plot / 2.5);
+    let mut draw_bar = |x: f64, stops: &[(u8, u8, u8); 3], lo: String, hi: String, title: &str| {
+        for b in 0..NUM_COLOUR_BINS {
+            svg.rect(
+                x + b as f64 * bar_w / NUM_COLOUR_BINS as f64,
+                legend_y,
+                bar_w / NUM_COLOUR_BINS as f64 + 0.5,
+                12.,
+                &bin_colour(stops, b),
+            );
+        }
+        svg.text(x, legend_y + 24., 10., "start", 0., &lo);
+        svg.text(x + bar_w, legend_y + 24., 10., "end", 0., &hi);
+        svg.text(x + bar_w / 2., legend_y + 24., 10., "middle", 0., title);
+    };
+    let ani_title = if af_overlay { "ANI (lower)" } else { "ANI" };
+    draw_bar(MARGIN, &ANI_STOPS, format!("{:.0}", min_ani * 100.), "100".to_string(), ani_title);
+    if af_overlay {
+        draw_bar(MARGIN + bar_w + 40., &AF_STOPS, "0".to_string(), "100".to_string(), "AF of row (upper)");
+    }
+    svg.write(file_name);
+}
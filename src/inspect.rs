@@ -0,0 +1,165 @@
+use crate::file_io;
+use crate::params::*;
+use crate::sketch_db::{is_consolidated_db, SketchDbReader};
+use crate::tetra::TetraCounts;
+use crate::types::*;
+use log::*;
+use rayon::prelude::*;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+struct InspectRow {
+    file_name: String,
+    name: String,
+    stats: GenomeStats,
+    /// Bases masked while sketching; sequence files are read unmasked.
+    masked_bases: usize,
+}
+
+impl InspectRow {
+    fn from_sketch(sketch: &Sketch) -> InspectRow {
+        InspectRow {
+            file_name: sketch.name().to_string(),
+            name: sketch.contigs.first().cloned().unwrap_or_default(),
+            stats: sketch.genome_stats,
+            masked_bases: sketch.masked_bases,
+        }
+    }
+}
+
+/// Statistics straight from a fasta/fastq, GenBank/EMBL, GFA or 2bit file without sketching. Uses the same contigs
+/// (>= MIN_LENGTH_CONTIG) as `skani sketch`.
+fn rows_from_fastx(file: &str, individual_contig: bool) -> Vec<InspectRow> {
+    let mut rows = vec![];
+    let mut contigs = vec![];
+    let mut contig_lengths = vec![];
+    let mut tetra_counts = TetraCounts::default();
+    let res = file_io::for_each_contig(file, |id, seq, _| {
+        if seq.len() < MIN_LENGTH_CONTIG {
+            return;
+        }
+        let contig = String::from_utf8(id.to_vec()).unwrap();
+        if individual_contig {
+            let mut counts = TetraCounts::default();
+            counts.add_seq(seq);
+            rows.push(InspectRow {
+                file_name: file.to_string(),
+                name: contig,
+                stats: GenomeStats::from_contigs(&[seq.len() as GnPosition], Some(counts.base_counts())),
+                masked_bases: 0,
+            });
+        } else {
+            tetra_counts.add_seq(seq);
+            contigs.push(contig);
+            contig_lengths.push(seq.len() as GnPosition);
+        }
+    });
+    if let Err(e) = res {
+        warn!("{}", e.message(file));
+        return vec![];
+    }
+    if !individual_contig && !contigs.is_empty() {
+        rows.push(InspectRow {
+            file_name: file.to_string(),
+            name: contigs.swap_remove(0),
+            stats: GenomeStats::from_contigs(&contig_lengths, Some(tetra_counts.base_counts())),
+            masked_bases: 0,
+        });
+    }
+    if rows.is_empty() {
+        warn!("File {} consists of only contigs < {} bp. Skipping this file.", file, MIN_LENGTH_CONTIG);
+    }
+    rows
+}
+
+/// Names come from markers.bin. Databases from skani <= v0.3 have no statistics in
+/// their markers, so lengths and N50 are taken from the full sketches instead.
+fn rows_from_database(folder: &str) -> Vec<InspectRow> {
+    let marker_file = Path::new(folder).join("markers.bin");
+    let (_, marker_sketches) = file_io::marker_sketches_from_marker_file(marker_file.to_str().unwrap());
+    if marker_sketches.iter().all(|sketch| sketch.genome_stats.num_contigs > 0) {
+        return marker_sketches.iter().map(InspectRow::from_sketch).collect();
+    }
+    warn!("{} was made by an older version of skani; reading full sketches. GC content is not available.", folder);
+    if is_consolidated_db(folder) {
+        let db_reader = SketchDbReader::new(folder).unwrap_or_else(|e| {
+            error!("Failed to load consolidated database {}: {}", folder, e);
+            std::process::exit(1)
+        });
+        (0..db_reader.len())
+            .map(|j| match db_reader.get_sketch(j) {
+                Ok((_params, sketch)) => InspectRow::from_sketch(&sketch),
+                Err(e) => {
+                    error!("Failed to load sketch {}: {}", marker_sketches[j].file_name, e);
+                    std::process::exit(1)
+                }
+            })
+            .collect()
+    } else {
+        let sketch_files = marker_sketches
+            .iter()
+            .map(|sketch| {
+                let file_name = Path::new(&format!("{}.sketch", sketch.file_name)).file_name().unwrap().to_owned();
+                Path::new(folder).join(file_name).to_str().unwrap().to_string()
+            })
+            .collect::<Vec<String>>();
+        let (_, sketches) = file_io::sketches_from_sketch(&sketch_files);
+        sketches.iter().map(InspectRow::from_sketch).collect()
+    }
+}
+
+fn rows_from_input(file: &str, individual_contig: bool) -> Vec<InspectRow> {
+    if Path::new(file).is_dir() {
+        rows_from_database(file)
+    } else if file.contains("markers.bin") {
+        let (_, sketches) = file_io::marker_sketches_from_marker_file(file);
+        sketches.iter().map(InspectRow::from_sketch).collect()
+    } else if file.contains(".sketch") {
+        let (_, sketches) = file_io::sketches_from_sketch(&vec![file.to_string()]);
+        sketches.iter().map(InspectRow::from_sketch).collect()
+    } else {
+        rows_from_fastx(file, individual_contig)
+    }
+}
+
+/// Per-genome length, contig count, N50, GC content, ambiguous and masked base counts as a TSV.
+pub fn inspect(command_params: CommandParams) {
+    let rows = command_params
+        .ref_files
+        .par_iter()
+        .map(|file| rows_from_input(file, command_params.individual_contig_r))
+        .collect::<Vec<Vec<InspectRow>>>();
+
+    let mut handle: Box<dyn Write> = if command_params.out_file_name.is_empty() {
+        Box::new(BufWriter::new(io::stdout().lock()))
+    } else {
+        Box::new(BufWriter::new(
+            File::create(&command_params.out_file_name).expect(&command_params.out_file_name),
+        ))
+    };
+    writeln!(handle, "File\tName\tNum_contigs\tLength\tN50\tGC\tAmbiguous_bases\tMasked_bases").unwrap();
+    for row in rows.iter().flatten() {
+        let gc = match row.stats.gc() {
+            Some(gc) => format!("{:.2}", gc * 100.),
+            None => "NA".to_string(),
+        };
+        let ambiguous = match row.stats.ambiguous_bases() {
+            Some(ambiguous) => ambiguous.to_string(),
+            None => "NA".to_string(),
+        };
+        writeln!(
+            handle,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            command_params.display_names.get(&row.file_name).unwrap_or(&row.file_name),
+            truncate_contig_name(&row.name, command_params.short_header),
+            row.stats.num_contigs,
+            row.stats.length,
+            row.stats.n50,
+            gc,
+            ambiguous,
+            row.masked_bases
+        )
+        .unwrap();
+    }
+}
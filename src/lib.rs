@@ -8,6 +8,17 @@ pub mod search;
 pub mod sketch;
 pub mod dist;
 pub mod triangle;
+pub mod profile;
+pub mod explain;
+pub mod dotplot;
+pub mod inspect;
+pub mod svg;
+pub mod heatmap;
+pub mod ddh;
+pub mod tetra;
+pub mod flat_file;
+pub mod gfa;
+pub mod twobit;
 pub mod cmd_line;
 pub mod model;
 pub mod regression;
@@ -2,7 +2,11 @@ use clap::Parser;
 use std::env;
 use skani::cli::{Cli, Commands};
 use skani::dist;
+use skani::dotplot;
+use skani::explain;
+use skani::inspect;
 use skani::parse;
+use skani::profile;
 use skani::search;
 use skani::sketch;
 use skani::triangle;
@@ -44,5 +48,17 @@ fn main() {
         Commands::Triangle(_) => {
             triangle::triangle(command_params, sketch_params);
         },
+        Commands::Profile(_) => {
+            profile::profile(command_params, sketch_params);
+        },
+        Commands::Explain(_) => {
+            explain::explain(command_params, sketch_params);
+        },
+        Commands::Dotplot(_) => {
+            dotplot::dotplot(command_params, sketch_params);
+        },
+        Commands::Inspect(_) => {
+            inspect::inspect(command_params);
+        },
     }
 }
@@ -33,10 +33,13 @@ pub const ORF_SIZE: usize = 30;
 pub const MARKER_C_DEFAULT: &str = "1000";
 pub const K_MARKER_AA: usize = 10;
 pub const K_MARKER_DNA: usize = 21;
+pub const MAX_MARKER_K_DNA: usize = 32;
 pub const SEARCH_STRING: &str = "search";
 pub const DIST_STRING: &str = "dist";
 pub const SKETCH_STRING: &str = "sketch";
 pub const TRIANGLE_STRING: &str = "triangle";
+/// Input path read from stdin, and `sketch -o` path written to stdout.
+pub const STDIO_PATH: &str = "-";
 pub const CHUNK_SIZE_DNA: usize = 20000;
 pub const CHUNK_SIZE_AA: usize = 20000;
 pub const MIN_LENGTH_CONTIG: usize = 500;
@@ -49,6 +52,8 @@ pub const SEARCH_ANI_CUTOFF_DEFAULT: f64 = 0.80;
 pub const SCREEN_MINIMUM_KMERS: usize = 20;
 pub const FULL_INDEX_THRESH: usize = 50;
 pub const REPET_KMER_THRESHOLD: usize = 8_000_000;
+/// Lowest threshold picked by adaptive repeat masking; seeds in two copies are never masked.
+pub const REPEAT_MASK_MIN_THRESHOLD: usize = 2;
 pub const OVERLAP_ORTHOLOGOUS_FRACTION: f32  = 0.50;
 pub const TOTAL_BASES_REGRESS_CUTOFF: usize = 150000;
 pub const LEARNED_INFO_HELP: &str = "Learned ANI mode detected. ANI may be adjusted according to a regression model trained on MAGs.";
@@ -56,7 +61,18 @@ pub const LEARNED_INFO_HELP: &str = "Learned ANI mode detected. ANI may be adjus
 pub const FAST_C: usize = 200;
 pub const SLOW_C: usize = 30;
 pub const MEDIUM_C: usize = 70;
+pub const GENUS_C: usize = 70;
+pub const GENUS_SEED_PATTERN: &str = "110110101011001111";
+pub const GENUS_SCREEN: f64 = 0.70;
 pub const SMALL_M: usize = 200;
+pub const DEFAULT_WINDOW_SIZE: &str = "10000";
+pub const DEFAULT_PLOT_SIZE: &str = "800";
+pub const DEFAULT_CI_REPLICATES: &str = "100";
+pub const MIN_CI_REPLICATES: usize = 20;
+pub const LEGACY_CI_SEED: u64 = 7;
+pub const CI_Z_90: f64 = 1.6449;
+pub const SYNTENY_MAX_GAP: i64 = 20000;
+pub const SYNTENY_OVERLAP_TOL: i64 = BP_CHAIN_BAND as i64;
 
 pub const ASCII_N: usize = 78;
 pub const ASCII_N_SMALL: usize = 110;
@@ -69,6 +85,10 @@ pub enum Mode {
     Dist,
     Triangle,
     Search,
+    Profile,
+    Explain,
+    Dotplot,
+    Inspect,
 }
 
 #[derive(Default)]
@@ -83,13 +103,117 @@ pub struct MapParams<'a> {
     pub length_cover_cutoff: usize,
     pub index_chain_band: usize,
     pub k: usize,
+    /// Bases covered by one seed: k, or the pattern length for spaced seeds.
+    pub seed_span: usize,
     pub amino_acid: bool,
     pub min_score: f64,
     pub robust: bool,
     pub median: bool,
     pub bp_chain_band: usize,
     pub min_length_cover: usize,
-    pub model: Option<&'a GBDT>
+    pub est_ci: bool,
+    pub ci_params: CiParams,
+    pub model: Option<&'a GBDT>,
+    /// Also chain with the query and reference roles swapped; see `chain::switch_qr`.
+    pub both_directions: bool,
+    /// Skip the `switch_qr` heuristic and use this orientation instead.
+    pub switch_override: Option<bool>,
+    /// Estimate ANI from shared marker k-mers only; see `screen::marker_ani`.
+    pub markers_only: bool,
+    /// Compute the --synteny columns.
+    pub synteny: bool,
+    /// Compute the --ddh columns.
+    pub ddh: bool,
+    /// Compute the --indels columns.
+    pub indels: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CiMethod {
+    Bootstrap,
+    Analytic,
+}
+
+/// How --ci intervals are computed. `seed: None` keeps the historical fixed seed, which
+/// gives the same random stream for every pair.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CiParams {
+    pub method: CiMethod,
+    pub replicates: usize,
+    pub seed: Option<u64>,
+}
+
+impl Default for CiParams {
+    fn default() -> Self {
+        CiParams {
+            method: CiMethod::Bootstrap,
+            replicates: DEFAULT_CI_REPLICATES.parse().unwrap(),
+            seed: None,
+        }
+    }
+}
+
+impl CiParams {
+    /// Suffix for the interval column names, recording how the interval was computed.
+    pub fn header_label(&self) -> String {
+        match (self.method, self.seed) {
+            (CiMethod::Analytic, _) => "[analytic]".to_string(),
+            (CiMethod::Bootstrap, None) => format!("[bootstrap,n={}]", self.replicates),
+            (CiMethod::Bootstrap, Some(seed)) => format!("[bootstrap,n={},seed={}]", self.replicates, seed),
+        }
+    }
+}
+
+/// Optional groups of columns written after the default (and --ci/--detailed) columns.
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct ExtraColumns {
+    pub synteny: bool,
+    pub ddh: bool,
+    pub indels: bool,
+    pub snps: bool,
+    pub tetra: bool,
+    pub genome_stats: bool,
+    pub both_directions: bool,
+    /// Not a column group: identity columns are marker-based estimates (--markers-only)
+    /// and are labelled as such in headers.
+    pub markers_only: bool,
+}
+
+impl ExtraColumns {
+    /// Column groups that need chains, so are unavailable with --markers-only.
+    pub fn needs_chaining(&self) -> bool {
+        self.synteny || self.ddh || self.indels || self.snps || self.both_directions
+    }
+}
+
+/// Genome name -> files whose sequences make up that genome, from a grouping TSV.
+/// Inputs that are not group names are read as a single file.
+pub type GenomeGroups = FxHashMap<String, Vec<String>>;
+
+/// Input path -> sample name, from the second column of `-l`/`--rl`/`--ql` lists.
+pub type DisplayNames = FxHashMap<String, String>;
+
+/// Contig name -> sorted, merged 0-based half-open regions from a mask BED file.
+pub type MaskRegions = FxHashMap<String, Vec<(usize, usize)>>;
+
+/// Contig filters for query fastas compared against `.sketch` references. Queries are otherwise
+/// sketched with the references' parameters, whose header filters and mask BED are for the references.
+#[derive(Default, Clone, PartialEq, Debug)]
+pub struct QueryContigFilters {
+    pub min_contig_length: Option<usize>,
+    pub contig_include: Option<String>,
+    pub contig_exclude: Option<String>,
+}
+
+impl QueryContigFilters {
+    /// The header filters given on the command line; contig lengths follow the references.
+    pub fn from_sketch_params(sketch_params: &SketchParams) -> QueryContigFilters {
+        QueryContigFilters {
+            min_contig_length: None,
+            contig_include: sketch_params.contig_include.clone(),
+            contig_exclude: sketch_params.contig_exclude.clone(),
+        }
+    }
 }
 
 #[derive(PartialEq)]
@@ -120,6 +244,18 @@ pub struct CommandParams{
     pub rescue_small: bool,
     pub separate_sketches: bool,
     pub short_header: bool,
+    pub window_size: usize,
+    pub window_step: usize,
+    pub sort_contigs: bool,
+    pub plot_size: usize,
+    pub heatmap_file: String,
+    pub heatmap_labels: bool,
+    pub heatmap_af: bool,
+    pub extra_columns: ExtraColumns,
+    pub ci_params: CiParams,
+    pub genome_groups: GenomeGroups,
+    pub display_names: DisplayNames,
+    pub query_contig_filters: QueryContigFilters,
 }
 
 pub fn fragment_length_formula(_n: usize, aa: bool) -> usize {
@@ -143,6 +279,165 @@ pub struct SketchParams {
     pub acgt_to_aa_encoding: Vec<MarkerBits>,
     pub acgt_to_aa_letters: Vec<u8>,
     pub orf_size: usize,
+    /// Spaced seed used instead of contiguous k-mers; `k` is then its weight.
+    pub spaced_seed: Option<SpacedSeed>,
+    /// Length of the marker k-mers, which also bounds the seed span.
+    pub marker_k: usize,
+    /// In AAI mode, translate annotated CDSs of GenBank/EMBL inputs instead of predicted ORFs.
+    pub annotated_cds: bool,
+    /// Contigs shorter than this are not sketched.
+    pub min_contig_length: usize,
+    /// Regex on contig headers; only matching contigs are sketched.
+    pub contig_include: Option<String>,
+    /// Regex on contig headers; matching contigs are not sketched.
+    pub contig_exclude: Option<String>,
+    /// GFA segments shorter than this are not sketched; `min_contig_length` unless set.
+    pub min_segment_length: usize,
+    /// Skip seeds overlapping soft-masked (lowercase) bases.
+    pub soft_mask: bool,
+    /// BED file of regions excluded from seeds and aligned fractions, kept as provenance.
+    pub mask_bed: Option<String>,
+    /// Repeat masking of seeds with many positions in the same genome.
+    pub repeat_mask: Option<RepeatMask>,
+}
+
+/// How seeds that occur many times within a genome are masked.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum RepeatMask {
+    /// Threshold chosen per genome from its seed multiplicities.
+    Adaptive,
+    /// Seeds with more positions than this are masked.
+    Threshold(usize),
+}
+
+/// On-disk layout of sketch parameters written by skani v0.3, before spaced seeds.
+/// Only used as a fallback when reading old .sketch files and databases.
+#[derive(Deserialize)]
+pub struct SketchParamsV03 {
+    pub c: usize,
+    pub k: usize,
+    pub marker_c: usize,
+    pub use_syncs: bool,
+    pub use_aa: bool,
+    pub acgt_to_aa_encoding: Vec<MarkerBits>,
+    pub acgt_to_aa_letters: Vec<u8>,
+    pub orf_size: usize,
+}
+
+impl From<SketchParamsV03> for SketchParams {
+    fn from(old: SketchParamsV03) -> SketchParams {
+        SketchParams {
+            c: old.c,
+            k: old.k,
+            marker_c: old.marker_c,
+            use_syncs: old.use_syncs,
+            use_aa: old.use_aa,
+            acgt_to_aa_encoding: old.acgt_to_aa_encoding,
+            acgt_to_aa_letters: old.acgt_to_aa_letters,
+            orf_size: old.orf_size,
+            spaced_seed: None,
+            marker_k: if old.use_aa { K_MARKER_AA } else { K_MARKER_DNA },
+            annotated_cds: false,
+            min_contig_length: MIN_LENGTH_CONTIG,
+            contig_include: None,
+            contig_exclude: None,
+            min_segment_length: MIN_LENGTH_CONTIG,
+            soft_mask: false,
+            mask_bed: None,
+            repeat_mask: None,
+        }
+    }
+}
+
+/// A spaced seed over the last `span` bases of each marker window, e.g. "1101101...".
+/// Only the '1' (care) positions make up the seed, so a substitution at a '0' position
+/// does not destroy the match. The number of care positions (the weight) plays the role
+/// of k. Forward and reverse windows are masked the same way, so canonical seeds are
+/// still strand-independent. Patterns should have about as many 1s at each position
+/// mod 3; otherwise seeds that skip third codon positions inflate ANI in coding regions.
+#[derive(Default, PartialEq, Eq, Serialize, Deserialize, Debug, Clone)]
+pub struct SpacedSeed {
+    pub pattern: Vec<bool>,
+}
+
+/// A contiguous run of care positions: shift right by `src_shift`, keep `mask`, then
+/// shift left by `dst_shift` into the packed seed.
+#[derive(Clone, Copy, Debug)]
+pub struct SeedRun {
+    pub src_shift: u32,
+    pub mask: MarkerBits,
+    pub dst_shift: u32,
+}
+
+impl SpacedSeed {
+    pub fn parse(pattern: &str) -> Result<SpacedSeed, String> {
+        if pattern.is_empty() || pattern.bytes().any(|b| b != b'0' && b != b'1') {
+            return Err(format!("Seed pattern {} must consist of only 0s and 1s.", pattern));
+        }
+        if !pattern.starts_with('1') || !pattern.ends_with('1') {
+            return Err(format!("Seed pattern {} must start and end with 1.", pattern));
+        }
+        let seed = SpacedSeed {
+            pattern: pattern.bytes().map(|b| b == b'1').collect(),
+        };
+        if seed.span() > MAX_MARKER_K_DNA {
+            return Err(format!("Seed pattern {} is longer than {} bases.", pattern, MAX_MARKER_K_DNA));
+        }
+        Ok(seed)
+    }
+
+    pub fn weight(&self) -> usize {
+        self.pattern.iter().filter(|x| **x).count()
+    }
+
+    pub fn span(&self) -> usize {
+        self.pattern.len()
+    }
+
+    /// Pattern position j (0 = oldest base) sits at bits 2 * (span - 1 - j) of a 2-bit
+    /// rolling k-mer.
+    pub fn runs(&self) -> Vec<SeedRun> {
+        let span = self.span();
+        let mut runs = vec![];
+        let mut packed = 0;
+        let mut j = span;
+        while j > 0 {
+            j -= 1;
+            if !self.pattern[j] {
+                continue;
+            }
+            let end = j;
+            while j > 0 && self.pattern[j - 1] {
+                j -= 1;
+            }
+            let len = end - j + 1;
+            runs.push(SeedRun {
+                src_shift: (2 * (span - 1 - end)) as u32,
+                mask: MarkerBits::MAX >> (std::mem::size_of::<MarkerBits>() * 8 - 2 * len),
+                dst_shift: (2 * packed) as u32,
+            });
+            packed += len;
+        }
+        runs
+    }
+
+    #[inline]
+    pub fn extract(runs: &[SeedRun], kmer: MarkerBits) -> MarkerBits {
+        let mut seed = 0;
+        for run in runs {
+            seed |= ((kmer >> run.src_shift) & run.mask) << run.dst_shift;
+        }
+        seed
+    }
+}
+
+impl std::fmt::Display for SpacedSeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for care in self.pattern.iter() {
+            write!(f, "{}", if *care { '1' } else { '0' })?;
+        }
+        Ok(())
+    }
 }
 
 impl SketchParams {
@@ -192,6 +487,73 @@ impl SketchParams {
             acgt_to_aa_encoding,
             acgt_to_aa_letters: DNA_TO_AA.to_vec(),
             orf_size,
+            spaced_seed: None,
+            marker_k: if use_aa { K_MARKER_AA } else { K_MARKER_DNA },
+            annotated_cds: false,
+            min_contig_length: MIN_LENGTH_CONTIG,
+            contig_include: None,
+            contig_exclude: None,
+            min_segment_length: MIN_LENGTH_CONTIG,
+            soft_mask: false,
+            mask_bed: None,
+            repeat_mask: None,
+        }
+    }
+
+    /// Use a spaced seed instead of contiguous k-mers. k becomes the seed's weight.
+    pub fn set_spaced_seed(&mut self, spaced_seed: SpacedSeed) {
+        self.k = spaced_seed.weight();
+        self.spaced_seed = Some(spaced_seed);
+    }
+
+    /// Number of bases covered by one seed.
+    pub fn seed_span(&self) -> usize {
+        self.spaced_seed.as_ref().map_or(self.k, |s| s.span())
+    }
+
+    /// Seeds are taken from the marker window, and markers must fit in `MarkerBits`.
+    pub fn check_seed_lengths(&self) -> Result<(), String> {
+        if self.use_aa && self.marker_k != K_MARKER_AA {
+            return Err(format!("Marker k can not be changed from {} in amino acid mode.", K_MARKER_AA));
+        }
+        if self.marker_k > MAX_MARKER_K_DNA {
+            return Err(format!("Marker k = {} is larger than the maximum of {}.", self.marker_k, MAX_MARKER_K_DNA));
+        }
+        if self.k == 0 {
+            return Err("k must be at least 1.".to_string());
+        }
+        if self.seed_span() > self.marker_k {
+            return Err(format!(
+                "Seeds span {} bases but marker k is {}; marker k must be at least the seed length.",
+                self.seed_span(),
+                self.marker_k
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sketches made with these parameters have comparable seeds and markers. Contig filters
+    /// and masking only change which bases are sketched.
+    pub fn same_seeds(&self, other: &SketchParams) -> bool {
+        self.c == other.c
+            && self.k == other.k
+            && self.marker_c == other.marker_c
+            && self.marker_k == other.marker_k
+            && self.spaced_seed == other.spaced_seed
+            && self.use_aa == other.use_aa
+    }
+
+    /// Parameters for sketching query fastas against references sketched with `self`: same
+    /// seeding, but no mask BED and the query's own contig filters.
+    pub fn for_queries(&self, filters: &QueryContigFilters) -> SketchParams {
+        let mut query_params = self.clone();
+        query_params.contig_include = filters.contig_include.clone();
+        query_params.contig_exclude = filters.contig_exclude.clone();
+        query_params.mask_bed = None;
+        if let Some(min_contig_length) = filters.min_contig_length {
+            query_params.min_contig_length = min_contig_length;
+            query_params.min_segment_length = min_contig_length;
         }
+        query_params
     }
 }
@@ -1,5 +1,6 @@
-use crate::cli::{Cli, Commands, DistArgs, SearchArgs, SketchArgs, TriangleArgs};
+use crate::cli::{Cli, Commands, DistArgs, DotplotArgs, ExplainArgs, InspectArgs, ProfileArgs, SearchArgs, SketchArgs, TriangleArgs};
 use crate::cmd_line::*;
+use crate::file_io;
 use crate::params::*;
 use crate::regression;
 use clap::parser::ArgMatches;
@@ -260,6 +261,7 @@ pub fn parse_params(matches: &ArgMatches) -> (SketchParams, CommandParams) {
     }
 
     let sketch_params = SketchParams::new(marker_c, c, k, use_syncs, amino_acid);
+    check_sketch_params(&sketch_params);
 
     let mut refs_are_sketch = !ref_files.is_empty();
     for ref_file in ref_files.iter() {
@@ -372,6 +374,18 @@ pub fn parse_params(matches: &ArgMatches) -> (SketchParams, CommandParams) {
         rescue_small,
         separate_sketches: false,
         short_header: false,
+        window_size: 0,
+        window_step: 0,
+        sort_contigs: false,
+        plot_size: 0,
+        heatmap_file: String::new(),
+        heatmap_labels: false,
+        heatmap_af: false,
+        extra_columns: ExtraColumns::default(),
+        ci_params: CiParams::default(),
+        genome_groups: GenomeGroups::default(),
+        display_names: DisplayNames::default(),
+        query_contig_filters: QueryContigFilters::default(),
     };
 
     (sketch_params, command_params)
@@ -487,6 +501,18 @@ pub fn parse_params_search(matches_subc: &ArgMatches) -> (SketchParams, CommandP
         rescue_small: false,
         separate_sketches: false,
         short_header: false,
+        window_size: 0,
+        window_step: 0,
+        sort_contigs: false,
+        plot_size: 0,
+        heatmap_file: String::new(),
+        heatmap_labels: false,
+        heatmap_af: false,
+        extra_columns: ExtraColumns::default(),
+        ci_params: CiParams::default(),
+        genome_groups: GenomeGroups::default(),
+        display_names: DisplayNames::default(),
+        query_contig_filters: QueryContigFilters::default(),
     };
 
     if command_params.ref_files.is_empty() {
@@ -505,6 +531,10 @@ pub fn parse_params_from_cli(cli: &Cli) -> (SketchParams, CommandParams) {
         Commands::Dist(args) => parse_dist_args(args),
         Commands::Triangle(args) => parse_triangle_args(args),
         Commands::Search(args) => parse_search_args(args),
+        Commands::Profile(args) => parse_profile_args(args),
+        Commands::Explain(args) => parse_explain_args(args),
+        Commands::Dotplot(args) => parse_dotplot_args(args),
+        Commands::Inspect(args) => parse_inspect_args(args),
     }
 }
 
@@ -532,67 +562,62 @@ fn parse_sketch_args(args: &SketchArgs) -> (SketchParams, CommandParams) {
         warn!("Amino acid mode (AAI) detected. This mode is not stable.");
     }
 
+    let mut genome_groups = GenomeGroups::default();
+    let mut display_names = DisplayNames::default();
     let ref_files;
     if !args.fasta_files.is_empty() {
         ref_files = args.fasta_files.clone();
     } else if let Some(list_file) = &args.fasta_list {
-        ref_files = read_file_list(list_file);
+        ref_files = read_file_list(list_file, &mut display_names);
+    } else if let Some(group_file) = &args.groups {
+        ref_files = read_genome_groups(group_file, &mut genome_groups);
     } else {
         error!("No reference inputs found.");
         std::process::exit(1);
     }
 
-    let def_k = if amino_acid { DEFAULT_K_AAI } else { DEFAULT_K };
-    let def_c = if amino_acid { DEFAULT_C_AAI } else { DEFAULT_C };
-    
-    let k = args.k.as_ref()
-        .map(|s| s.parse::<usize>().unwrap())
-        .unwrap_or(def_k.parse().unwrap());
-
-    let mut c = args.c.as_ref()
-        .map(|s| s.parse::<usize>().unwrap())
-        .unwrap_or(def_c.parse().unwrap());
-
-    let marker_c = args.marker_c.as_ref()
-        .map(|s| s.parse::<usize>().unwrap())
-        .unwrap_or(MARKER_C_DEFAULT.parse().unwrap());
-
-    // Handle presets
-    if args.fast && args.slow {
-        panic!("Both --slow and --fast were set. This is not allowed.");
-    }
-    if args.fast {
-        if args.c.is_some() {
-            warn!("-c value is set but --fast is also set. Using --fast mode instead (-c 200)");
-        }
-        c = FAST_C;
-    }
-    if args.slow {
-        if args.c.is_some() {
-            warn!("-c value is set but --slow is also set. Using --slow mode instead (-c 30)");
-        }
-        c = SLOW_C;
-    }
-    if args.medium {
-        if args.c.is_some() {
-            warn!("-c value is set but --medium is also set. Using --medium mode instead (-c 70)");
-        }
-        c = MEDIUM_C;
-    }
-
-    let sketch_params = SketchParams::new(marker_c, c, k, false, amino_acid);
+    let sketch_params = sketch_params_from_args(&SketchOptions {
+        amino_acid,
+        k: args.k.clone(),
+        c: args.c.clone(),
+        marker_c: args.marker_c.clone(),
+        marker_k: args.marker_k.clone(),
+        seed_pattern: args.seed_pattern.clone(),
+        slow: args.slow,
+        medium: args.medium,
+        fast: args.fast,
+        genus: args.genus,
+        annotated_cds: args.annotated_cds,
+        min_contig_length: args.min_contig_length,
+        min_segment_length: args.min_segment_length,
+        include_contigs: args.include_contigs.clone(),
+        exclude_contigs: args.exclude_contigs.clone(),
+        soft_mask: args.soft_mask,
+        mask_bed: args.mask_bed.clone(),
+        repeat_mask: args.repeat_mask.clone(),
+        ..Default::default()
+    });
 
     let mut refs_are_sketch = !ref_files.is_empty();
     for ref_file in ref_files.iter() {
-        if !ref_file.contains(".sketch")
-            && !ref_file.contains(".marker")
-            && !ref_file.contains("markers.bin")
+        if genome_groups.contains_key(ref_file)
+            || (!ref_file.contains(".sketch")
+                && !ref_file.contains(".marker")
+                && !ref_file.contains("markers.bin")
+                && !is_stdin_sketch(ref_file))
         {
             refs_are_sketch = false;
             break;
         }
     }
 
+    check_stdin_inputs(&[&ref_files]);
+    if file_io::is_stdin(&args.output) && (ref_files.len() != 1 || args.individual_contig) {
+        error!("-o {} writes the sketch of a single genome to stdout; give one input and do not use -i. Exiting.", STDIO_PATH);
+        std::process::exit(1);
+    }
+    check_genome_groups(&genome_groups, args.individual_contig);
+
     let command_params = CommandParams {
         screen: false,
         screen_val: 0.0,
@@ -620,6 +645,18 @@ fn parse_sketch_args(args: &SketchArgs) -> (SketchParams, CommandParams) {
         rescue_small: false,
         separate_sketches: args.separate_sketches,
         short_header: false,
+        window_size: 0,
+        window_step: 0,
+        sort_contigs: false,
+        plot_size: 0,
+        heatmap_file: String::new(),
+        heatmap_labels: false,
+        heatmap_af: false,
+        extra_columns: ExtraColumns::default(),
+        ci_params: CiParams::default(),
+        genome_groups,
+        display_names,
+        query_contig_filters: QueryContigFilters::default(),
     };
 
     (sketch_params, command_params)
@@ -636,13 +673,17 @@ fn parse_dist_args(args: &DistArgs) -> (SketchParams, CommandParams) {
     let rescue_small = !args.faster_small && !args.small_genomes;
 
     // Parse reference files
+    let mut genome_groups = GenomeGroups::default();
+    let mut display_names = DisplayNames::default();
     let ref_files;
     if !args.reference.is_empty() {
         ref_files = args.reference.clone();
     } else if !args.references.is_empty() {
         ref_files = args.references.clone();
     } else if let Some(list_file) = &args.reference_list {
-        ref_files = read_file_list(list_file);
+        ref_files = read_file_list(list_file, &mut display_names);
+    } else if let Some(group_file) = &args.reference_groups {
+        ref_files = read_genome_groups(group_file, &mut genome_groups);
     } else {
         error!("No reference inputs found.");
         std::process::exit(1);
@@ -657,58 +698,16 @@ fn parse_dist_args(args: &DistArgs) -> (SketchParams, CommandParams) {
         query_files.extend(args.queries.clone());
     }
     if let Some(list_file) = &args.query_list {
-        query_files.extend(read_file_list(list_file));
+        query_files.extend(read_file_list(list_file, &mut display_names));
+    }
+    if let Some(group_file) = &args.query_groups {
+        query_files.extend(read_genome_groups(group_file, &mut genome_groups));
     }
 
     let max_results = args.n.as_ref()
         .map(|s| s.parse::<usize>().unwrap())
         .unwrap_or(1000000000000);
 
-    let def_k = if amino_acid { DEFAULT_K_AAI } else { DEFAULT_K };
-    let def_c = if amino_acid { DEFAULT_C_AAI } else { DEFAULT_C };
-    
-    let k = args.k.as_ref()
-        .map(|s| s.parse::<usize>().unwrap())
-        .unwrap_or(def_k.parse().unwrap());
-
-    let mut c = args.c.as_ref()
-        .map(|s| s.parse::<usize>().unwrap())
-        .unwrap_or(def_c.parse().unwrap());
-
-    let mut marker_c = args.marker_c.as_ref()
-        .map(|s| s.parse::<usize>().unwrap())
-        .unwrap_or(MARKER_C_DEFAULT.parse().unwrap());
-
-    // Handle presets
-    if args.fast && args.slow {
-        panic!("Both --slow and --fast were set. This is not allowed.");
-    }
-    if args.fast {
-        if args.c.is_some() {
-            warn!("-c value is set but --fast is also set. Using --fast mode instead (-c 200)");
-        }
-        c = FAST_C;
-    }
-    if args.slow {
-        if args.c.is_some() {
-            warn!("-c value is set but --slow is also set. Using --slow mode instead (-c 30)");
-        }
-        c = SLOW_C;
-    }
-    if args.medium {
-        if args.c.is_some() {
-            warn!("-c value is set but --medium is also set. Using --medium mode instead (-c 70)");
-        }
-        c = MEDIUM_C;
-    }
-    if args.small_genomes {
-        if args.c.is_some() || args.marker_c.is_some() {
-            warn!("-c or -m value is set but --small-genomes is also set. Using -c 30 and -m 200 instead.");
-        }
-        c = SLOW_C;
-        marker_c = SMALL_M;
-    }
-
     let def_maf = if amino_acid {
         D_FRAC_COVER_CUTOFF_AA
     } else {
@@ -722,17 +721,40 @@ fn parse_dist_args(args: &DistArgs) -> (SketchParams, CommandParams) {
         .map(|s| s.parse::<f64>().unwrap())
         .unwrap_or(-1.0) / 100.0;
 
+    let default_screen = if args.genus { GENUS_SCREEN * 100. } else { 0.0 };
     let screen_val = args.s.as_ref()
         .map(|s| s.parse::<f64>().unwrap())
-        .unwrap_or(0.0) / 100.0;
-
-    let sketch_params = SketchParams::new(marker_c, c, k, false, amino_acid);
+        .unwrap_or(default_screen) / 100.0;
+
+    let sketch_params = sketch_params_from_args(&SketchOptions {
+        amino_acid,
+        k: args.k.clone(),
+        c: args.c.clone(),
+        marker_c: args.marker_c.clone(),
+        marker_k: args.marker_k.clone(),
+        seed_pattern: args.seed_pattern.clone(),
+        slow: args.slow,
+        medium: args.medium,
+        fast: args.fast,
+        genus: args.genus,
+        small_genomes: args.small_genomes,
+        annotated_cds: args.annotated_cds,
+        min_contig_length: args.min_contig_length,
+        min_segment_length: args.min_segment_length,
+        include_contigs: args.include_contigs.clone(),
+        exclude_contigs: args.exclude_contigs.clone(),
+        soft_mask: args.soft_mask,
+        mask_bed: args.mask_bed.clone(),
+        repeat_mask: args.repeat_mask.clone(),
+    });
 
     let mut refs_are_sketch = !ref_files.is_empty();
     for ref_file in ref_files.iter() {
-        if !ref_file.contains(".sketch")
-            && !ref_file.contains(".marker")
-            && !ref_file.contains("markers.bin")
+        if genome_groups.contains_key(ref_file)
+            || (!ref_file.contains(".sketch")
+                && !ref_file.contains(".marker")
+                && !ref_file.contains("markers.bin")
+                && !is_stdin_sketch(ref_file))
         {
             refs_are_sketch = false;
             break;
@@ -741,7 +763,9 @@ fn parse_dist_args(args: &DistArgs) -> (SketchParams, CommandParams) {
 
     let mut queries_are_sketch = !query_files.is_empty();
     for query_file in query_files.iter() {
-        if !query_file.contains(".sketch") && !query_file.contains("markers.bin") {
+        if genome_groups.contains_key(query_file)
+            || (!query_file.contains(".sketch") && !query_file.contains("markers.bin") && !is_stdin_sketch(query_file))
+        {
             queries_are_sketch = false;
             break;
         }
@@ -749,11 +773,26 @@ fn parse_dist_args(args: &DistArgs) -> (SketchParams, CommandParams) {
 
     let screen = (query_files.len() > FULL_INDEX_THRESH || args.qi) && !args.no_marker_index;
 
-    let learned_ani = if args.no_learned_ani {
+    let learned_ani = if args.no_learned_ani || args.genus {
         false
     } else {
-        regression::use_learned_ani(c, args.qi, args.ri, args.median)
+        regression::use_learned_ani(sketch_params.c, args.qi, args.ri, args.median)
+    };
+
+    let extra_columns = ExtraColumns {
+        synteny: args.synteny,
+        ddh: args.ddh,
+        indels: args.indels,
+        snps: args.snps,
+        tetra: args.tetra,
+        genome_stats: args.genome_stats,
+        both_directions: args.both_directions,
+        markers_only: args.markers_only,
     };
+    check_markers_only(&extra_columns);
+
+    check_stdin_inputs(&[&ref_files, &query_files]);
+    check_genome_groups(&genome_groups, args.qi || args.ri);
 
     let command_params = CommandParams {
         screen,
@@ -782,6 +821,18 @@ fn parse_dist_args(args: &DistArgs) -> (SketchParams, CommandParams) {
         rescue_small,
         separate_sketches: false,
         short_header: args.short_header,
+        window_size: 0,
+        window_step: 0,
+        sort_contigs: false,
+        plot_size: 0,
+        heatmap_file: String::new(),
+        heatmap_labels: false,
+        heatmap_af: false,
+        extra_columns,
+        ci_params: parse_ci_params(&args.ci_method, &args.ci_replicates, &args.ci_seed),
+        genome_groups,
+        display_names,
+        query_contig_filters: QueryContigFilters::default(),
     };
 
     (sketch_params, command_params)
@@ -797,61 +848,20 @@ fn parse_triangle_args(args: &TriangleArgs) -> (SketchParams, CommandParams) {
 
     let rescue_small = !args.faster_small && !args.small_genomes;
 
+    let mut genome_groups = GenomeGroups::default();
+    let mut display_names = DisplayNames::default();
     let ref_files;
     if !args.fasta_files.is_empty() {
         ref_files = args.fasta_files.clone();
     } else if let Some(list_file) = &args.fasta_list {
-        ref_files = read_file_list(list_file);
+        ref_files = read_file_list(list_file, &mut display_names);
+    } else if let Some(group_file) = &args.groups {
+        ref_files = read_genome_groups(group_file, &mut genome_groups);
     } else {
         error!("No reference inputs found.");
         std::process::exit(1);
     }
 
-    let def_k = if amino_acid { DEFAULT_K_AAI } else { DEFAULT_K };
-    let def_c = if amino_acid { DEFAULT_C_AAI } else { DEFAULT_C };
-    
-    let k = args.k.as_ref()
-        .map(|s| s.parse::<usize>().unwrap())
-        .unwrap_or(def_k.parse().unwrap());
-
-    let mut c = args.c.as_ref()
-        .map(|s| s.parse::<usize>().unwrap())
-        .unwrap_or(def_c.parse().unwrap());
-
-    let mut marker_c = args.marker_c.as_ref()
-        .map(|s| s.parse::<usize>().unwrap())
-        .unwrap_or(MARKER_C_DEFAULT.parse().unwrap());
-
-    // Handle presets
-    if args.fast && args.slow {
-        panic!("Both --slow and --fast were set. This is not allowed.");
-    }
-    if args.fast {
-        if args.c.is_some() {
-            warn!("-c value is set but --fast is also set. Using --fast mode instead (-c 200)");
-        }
-        c = FAST_C;
-    }
-    if args.slow {
-        if args.c.is_some() {
-            warn!("-c value is set but --slow is also set. Using --slow mode instead (-c 30)");
-        }
-        c = SLOW_C;
-    }
-    if args.medium {
-        if args.c.is_some() {
-            warn!("-c value is set but --medium is also set. Using --medium mode instead (-c 70)");
-        }
-        c = MEDIUM_C;
-    }
-    if args.small_genomes {
-        if args.c.is_some() || args.marker_c.is_some() {
-            warn!("-c or -m value is set but --small-genomes is also set. Using -c 30 and -m 200 instead.");
-        }
-        c = SLOW_C;
-        marker_c = SMALL_M;
-    }
-
     let def_maf = if amino_acid {
         D_FRAC_COVER_CUTOFF_AA
     } else {
@@ -865,28 +875,66 @@ fn parse_triangle_args(args: &TriangleArgs) -> (SketchParams, CommandParams) {
         .map(|s| s.parse::<f64>().unwrap())
         .unwrap_or(-1.0) / 100.0;
 
+    let default_screen = if args.genus { GENUS_SCREEN * 100. } else { 0.0 };
     let screen_val = args.s.as_ref()
         .map(|s| s.parse::<f64>().unwrap())
-        .unwrap_or(0.0) / 100.0;
-
-    let sketch_params = SketchParams::new(marker_c, c, k, false, amino_acid);
+        .unwrap_or(default_screen) / 100.0;
+
+    let sketch_params = sketch_params_from_args(&SketchOptions {
+        amino_acid,
+        k: args.k.clone(),
+        c: args.c.clone(),
+        marker_c: args.marker_c.clone(),
+        marker_k: args.marker_k.clone(),
+        seed_pattern: args.seed_pattern.clone(),
+        slow: args.slow,
+        medium: args.medium,
+        fast: args.fast,
+        genus: args.genus,
+        small_genomes: args.small_genomes,
+        annotated_cds: args.annotated_cds,
+        min_contig_length: args.min_contig_length,
+        min_segment_length: args.min_segment_length,
+        include_contigs: args.include_contigs.clone(),
+        exclude_contigs: args.exclude_contigs.clone(),
+        soft_mask: args.soft_mask,
+        mask_bed: args.mask_bed.clone(),
+        repeat_mask: args.repeat_mask.clone(),
+    });
 
     let mut refs_are_sketch = !ref_files.is_empty();
     for ref_file in ref_files.iter() {
-        if !ref_file.contains(".sketch")
-            && !ref_file.contains(".marker")
-            && !ref_file.contains("markers.bin")
+        if genome_groups.contains_key(ref_file)
+            || (!ref_file.contains(".sketch")
+                && !ref_file.contains(".marker")
+                && !ref_file.contains("markers.bin")
+                && !is_stdin_sketch(ref_file))
         {
             refs_are_sketch = false;
             break;
         }
     }
 
-    let learned_ani = if args.no_learned_ani {
+    let learned_ani = if args.no_learned_ani || args.genus {
         false
     } else {
-        regression::use_learned_ani(c, args.individual_contig, args.individual_contig, args.median)
+        regression::use_learned_ani(sketch_params.c, args.individual_contig, args.individual_contig, args.median)
+    };
+
+    let extra_columns = ExtraColumns {
+        synteny: args.synteny,
+        ddh: args.ddh,
+        indels: args.indels,
+        snps: args.snps,
+        tetra: args.tetra,
+        genome_stats: args.genome_stats,
+        both_directions: args.both_directions,
+        markers_only: args.markers_only,
     };
+    check_markers_only(&extra_columns);
+
+    check_stdin_inputs(&[&ref_files]);
+    check_genome_groups(&genome_groups, args.individual_contig);
 
     let command_params = CommandParams {
         screen: true,
@@ -915,6 +963,18 @@ fn parse_triangle_args(args: &TriangleArgs) -> (SketchParams, CommandParams) {
         rescue_small,
         separate_sketches: false,
         short_header: args.short_header,
+        window_size: 0,
+        window_step: 0,
+        sort_contigs: false,
+        plot_size: DEFAULT_PLOT_SIZE.parse().unwrap(),
+        heatmap_file: args.heatmap.clone().unwrap_or_default(),
+        heatmap_labels: args.heatmap_labels,
+        heatmap_af: args.heatmap_af,
+        extra_columns,
+        ci_params: parse_ci_params(&args.ci_method, &args.ci_replicates, &args.ci_seed),
+        genome_groups,
+        display_names,
+        query_contig_filters: QueryContigFilters::default(),
     };
 
     (sketch_params, command_params)
@@ -923,6 +983,8 @@ fn parse_triangle_args(args: &TriangleArgs) -> (SketchParams, CommandParams) {
 fn parse_search_args(args: &SearchArgs) -> (SketchParams, CommandParams) {
     setup_logging_and_threads(&args.threads, args.debug, args.trace);
 
+    let mut genome_groups = GenomeGroups::default();
+    let mut display_names = DisplayNames::default();
     let mut query_files = Vec::new();
     if !args.query.is_empty() {
         query_files.extend(args.query.clone());
@@ -931,7 +993,10 @@ fn parse_search_args(args: &SearchArgs) -> (SketchParams, CommandParams) {
         query_files.extend(args.queries.clone());
     }
     if let Some(list_file) = &args.query_list {
-        query_files.extend(read_file_list(list_file));
+        query_files.extend(read_file_list(list_file, &mut display_names));
+    }
+    if let Some(group_file) = &args.query_groups {
+        query_files.extend(read_genome_groups(group_file, &mut genome_groups));
     }
 
     let max_results = args.n.as_ref()
@@ -947,7 +1012,9 @@ fn parse_search_args(args: &SearchArgs) -> (SketchParams, CommandParams) {
 
     let mut queries_are_sketch = !query_files.is_empty();
     for query_file in query_files.iter() {
-        if !query_file.contains(".sketch") && !query_file.contains("markers.bin") {
+        if genome_groups.contains_key(query_file)
+            || (!query_file.contains(".sketch") && !query_file.contains("markers.bin") && !is_stdin_sketch(query_file))
+        {
             queries_are_sketch = false;
             break;
         }
@@ -969,6 +1036,28 @@ fn parse_search_args(args: &SearchArgs) -> (SketchParams, CommandParams) {
 
     let learned_ani = !args.no_learned_ani;
 
+    let extra_columns = ExtraColumns {
+        synteny: args.synteny,
+        ddh: args.ddh,
+        indels: args.indels,
+        snps: args.snps,
+        tetra: args.tetra,
+        genome_stats: args.genome_stats,
+        both_directions: args.both_directions,
+        markers_only: args.markers_only,
+    };
+    check_markers_only(&extra_columns);
+
+    check_stdin_inputs(&[&query_files]);
+    check_genome_groups(&genome_groups, args.qi);
+
+    let query_contig_filters = QueryContigFilters {
+        min_contig_length: args.min_contig_length,
+        contig_include: args.include_contigs.clone(),
+        contig_exclude: args.exclude_contigs.clone(),
+    };
+    check_contig_filters(&query_contig_filters.contig_include, &query_contig_filters.contig_exclude);
+
     let command_params = CommandParams {
         screen,
         screen_val,
@@ -996,6 +1085,18 @@ fn parse_search_args(args: &SearchArgs) -> (SketchParams, CommandParams) {
         rescue_small: false,
         separate_sketches: false,
         short_header: args.short_header,
+        window_size: 0,
+        window_step: 0,
+        sort_contigs: false,
+        plot_size: 0,
+        heatmap_file: String::new(),
+        heatmap_labels: false,
+        heatmap_af: false,
+        extra_columns,
+        ci_params: parse_ci_params(&args.ci_method, &args.ci_replicates, &args.ci_seed),
+        genome_groups,
+        display_names,
+        query_contig_filters,
     };
 
     if command_params.ref_files.is_empty() {
@@ -1006,11 +1107,600 @@ fn parse_search_args(args: &SearchArgs) -> (SketchParams, CommandParams) {
     (SketchParams::default(), command_params)
 }
 
-fn read_file_list(file_path: &str) -> Vec<String> {
+fn parse_profile_args(args: &ProfileArgs) -> (SketchParams, CommandParams) {
+    setup_logging_and_threads(&args.threads, args.debug, args.trace);
+
+    let mut display_names = DisplayNames::default();
+    let ref_files = vec![args.reference.clone()];
+    let mut query_files = Vec::new();
+    if !args.query.is_empty() {
+        query_files.extend(args.query.clone());
+    }
+    if let Some(list_file) = &args.query_list {
+        query_files.extend(read_file_list(list_file, &mut display_names));
+    }
+
+    let sketch_params = sketch_params_from_args(&SketchOptions {
+        k: args.k.clone(),
+        c: args.c.clone(),
+        marker_c: args.marker_c.clone(),
+        slow: args.slow,
+        medium: args.medium,
+        fast: args.fast,
+        ..Default::default()
+    });
+
+    let window_size = args.window.as_ref()
+        .map(|s| s.parse::<usize>().unwrap())
+        .unwrap_or(DEFAULT_WINDOW_SIZE.parse().unwrap());
+    let window_step = args.step.as_ref()
+        .map(|s| s.parse::<usize>().unwrap())
+        .unwrap_or(window_size);
+    if window_size == 0 || window_step == 0 {
+        error!("--window and --step must be > 0.");
+        std::process::exit(1);
+    }
+    if window_size < 10 * sketch_params.c {
+        warn!("Window size {} is small relative to -c {}; windowed ANI will be noisy. Consider a larger window or a smaller -c.", window_size, sketch_params.c);
+    }
+
+    let refs_are_sketch = ref_files[0].contains(".sketch");
+    let mut queries_are_sketch = !query_files.is_empty();
+    for query_file in query_files.iter() {
+        if !query_file.contains(".sketch") {
+            queries_are_sketch = false;
+            break;
+        }
+    }
+
+    let command_params = CommandParams {
+        screen: false,
+        screen_val: 0.0,
+        mode: Mode::Profile,
+        out_file_name: args.output.clone().unwrap_or_default(),
+        ref_files,
+        query_files,
+        refs_are_sketch,
+        queries_are_sketch,
+        robust: false,
+        median: false,
+        sparse: false,
+        full_matrix: false,
+        diagonal: false,
+        max_results: usize::MAX,
+        individual_contig_q: false,
+        individual_contig_r: false,
+        min_aligned_frac: 0.0,
+        both_min_aligned_frac: -0.01,
+        keep_refs: false,
+        est_ci: false,
+        learned_ani: false,
+        detailed_out: false,
+        distance: false,
+        rescue_small: false,
+        separate_sketches: false,
+        short_header: true,
+        window_size,
+        window_step,
+        sort_contigs: false,
+        plot_size: 0,
+        heatmap_file: String::new(),
+        heatmap_labels: false,
+        heatmap_af: false,
+        extra_columns: ExtraColumns::default(),
+        ci_params: CiParams::default(),
+        genome_groups: GenomeGroups::default(),
+        display_names,
+        query_contig_filters: QueryContigFilters::default(),
+    };
+
+    (sketch_params, command_params)
+}
+
+fn parse_explain_args(args: &ExplainArgs) -> (SketchParams, CommandParams) {
+    //A single pair; explain is not multithreaded.
+    setup_logging_and_threads("1", args.debug, args.trace);
+
+    let ref_files = vec![args.reference.clone()];
+    let query_files = vec![args.query.clone()];
+
+    let min_aligned_frac = args.min_af.as_ref()
+        .map(|s| s.parse::<f64>().unwrap())
+        .unwrap_or(D_FRAC_COVER_CUTOFF.parse().unwrap()) / 100.0;
+
+    let screen_val = args.s.as_ref()
+        .map(|s| s.parse::<f64>().unwrap())
+        .unwrap_or(0.0) / 100.0;
+
+    let sketch_params = sketch_params_from_args(&SketchOptions {
+        k: args.k.clone(),
+        c: args.c.clone(),
+        marker_c: args.marker_c.clone(),
+        slow: args.slow,
+        medium: args.medium,
+        fast: args.fast,
+        ..Default::default()
+    });
+
+    let refs_are_sketch = ref_files[0].contains(".sketch");
+    let queries_are_sketch = query_files[0].contains(".sketch");
+
+    let learned_ani = if args.no_learned_ani {
+        false
+    } else {
+        regression::use_learned_ani(sketch_params.c, false, false, args.median)
+    };
+
+    let command_params = CommandParams {
+        screen: false,
+        screen_val,
+        mode: Mode::Explain,
+        out_file_name: args.output.clone().unwrap_or_default(),
+        ref_files,
+        query_files,
+        refs_are_sketch,
+        queries_are_sketch,
+        robust: args.robust,
+        median: args.median,
+        sparse: false,
+        full_matrix: false,
+        diagonal: false,
+        max_results: usize::MAX,
+        individual_contig_q: false,
+        individual_contig_r: false,
+        min_aligned_frac,
+        both_min_aligned_frac: -0.01,
+        keep_refs: false,
+        est_ci: true,
+        learned_ani,
+        detailed_out: true,
+        distance: false,
+        rescue_small: true,
+        separate_sketches: false,
+        short_header: false,
+        window_size: 0,
+        window_step: 0,
+        sort_contigs: false,
+        plot_size: 0,
+        heatmap_file: String::new(),
+        heatmap_labels: false,
+        heatmap_af: false,
+        extra_columns: ExtraColumns::default(),
+        ci_params: CiParams::default(),
+        genome_groups: GenomeGroups::default(),
+        display_names: DisplayNames::default(),
+        query_contig_filters: QueryContigFilters::default(),
+    };
+
+    (sketch_params, command_params)
+}
+
+fn parse_dotplot_args(args: &DotplotArgs) -> (SketchParams, CommandParams) {
+    setup_logging_and_threads("1", args.debug, args.trace);
+
+    let ref_files = vec![args.reference.clone()];
+    let query_files = vec![args.query.clone()];
+
+    let plot_size = args.size.as_ref()
+        .map(|s| s.parse::<usize>().unwrap())
+        .unwrap_or(DEFAULT_PLOT_SIZE.parse().unwrap());
+
+    let sketch_params = sketch_params_from_args(&SketchOptions {
+        k: args.k.clone(),
+        c: args.c.clone(),
+        marker_c: args.marker_c.clone(),
+        slow: args.slow,
+        medium: args.medium,
+        fast: args.fast,
+        ..Default::default()
+    });
+
+    let refs_are_sketch = ref_files[0].contains(".sketch");
+    let queries_are_sketch = query_files[0].contains(".sketch");
+
+    let command_params = CommandParams {
+        screen: false,
+        screen_val: 0.0,
+        mode: Mode::Dotplot,
+        out_file_name: args.output.clone().unwrap_or_default(),
+        ref_files,
+        query_files,
+        refs_are_sketch,
+        queries_are_sketch,
+        robust: false,
+        median: false,
+        sparse: false,
+        full_matrix: false,
+        diagonal: false,
+        max_results: usize::MAX,
+        individual_contig_q: false,
+        individual_contig_r: false,
+        min_aligned_frac: 0.0,
+        both_min_aligned_frac: -0.01,
+        keep_refs: false,
+        est_ci: false,
+        learned_ani: false,
+        detailed_out: false,
+        distance: false,
+        rescue_small: true,
+        separate_sketches: false,
+        short_header: true,
+        window_size: 0,
+        window_step: 0,
+        sort_contigs: args.sort,
+        plot_size,
+        heatmap_file: String::new(),
+        heatmap_labels: false,
+        heatmap_af: false,
+        extra_columns: ExtraColumns::default(),
+        ci_params: CiParams::default(),
+        genome_groups: GenomeGroups::default(),
+        display_names: DisplayNames::default(),
+        query_contig_filters: QueryContigFilters::default(),
+    };
+
+    (sketch_params, command_params)
+}
+
+fn parse_inspect_args(args: &InspectArgs) -> (SketchParams, CommandParams) {
+    setup_logging_and_threads(&args.threads, args.debug, args.trace);
+
+    let mut display_names = DisplayNames::default();
+    let ref_files;
+    if !args.files.is_empty() {
+        ref_files = args.files.clone();
+    } else if let Some(list_file) = &args.file_list {
+        ref_files = read_file_list(list_file, &mut display_names);
+    } else {
+        error!("No inputs found.");
+        std::process::exit(1);
+    }
+
+    let command_params = CommandParams {
+        screen: false,
+        screen_val: 0.0,
+        mode: Mode::Inspect,
+        out_file_name: args.output.clone().unwrap_or_default(),
+        ref_files,
+        query_files: vec![],
+        refs_are_sketch: false,
+        queries_are_sketch: false,
+        robust: false,
+        median: false,
+        sparse: false,
+        full_matrix: false,
+        diagonal: false,
+        max_results: usize::MAX,
+        individual_contig_q: false,
+        individual_contig_r: args.individual_contig,
+        min_aligned_frac: 0.0,
+        both_min_aligned_frac: -0.01,
+        keep_refs: false,
+        est_ci: false,
+        learned_ani: false,
+        detailed_out: false,
+        distance: false,
+        rescue_small: true,
+        separate_sketches: false,
+        short_header: args.short_header,
+        window_size: 0,
+        window_step: 0,
+        sort_contigs: false,
+        plot_size: 0,
+        heatmap_file: String::new(),
+        heatmap_labels: false,
+        heatmap_af: false,
+        extra_columns: ExtraColumns::default(),
+        ci_params: CiParams::default(),
+        genome_groups: GenomeGroups::default(),
+        display_names,
+        query_contig_filters: QueryContigFilters::default(),
+    };
+
+    (SketchParams::default(), command_params)
+}
+
+fn check_markers_only(extra_columns: &ExtraColumns) {
+    if extra_columns.markers_only && extra_columns.needs_chaining() {
+        error!("--markers-only can not be used with --synteny, --ddh, --indels, --snps or --both-directions, which need chaining.");
+        std::process::exit(1);
+    }
+    if extra_columns.markers_only {
+        warn!("--markers-only: ANI is estimated from shared marker k-mers without chaining and is much less accurate.");
+    }
+}
+
+/// Sketch options shared by the subcommands that sketch fastas, copied from their args.
+/// Options a subcommand doesn't have keep their defaults.
+#[derive(Default)]
+struct SketchOptions {
+    amino_acid: bool,
+    k: Option<String>,
+    c: Option<String>,
+    marker_c: Option<String>,
+    marker_k: Option<String>,
+    seed_pattern: Option<String>,
+    slow: bool,
+    medium: bool,
+    fast: bool,
+    genus: bool,
+    small_genomes: bool,
+    annotated_cds: bool,
+    min_contig_length: Option<usize>,
+    min_segment_length: Option<usize>,
+    include_contigs: Option<String>,
+    exclude_contigs: Option<String>,
+    soft_mask: bool,
+    mask_bed: Option<String>,
+    repeat_mask: Option<String>,
+}
+
+/// Sketch parameters from -k/-c/-m, the --slow/--medium/--fast/--genus/--small-genomes
+/// presets and the seed, contig and masking options. Presets override -c and -m with a
+/// warning.
+fn sketch_params_from_args(opts: &SketchOptions) -> SketchParams {
+    let amino_acid = opts.amino_acid;
+    let def_k = if amino_acid { DEFAULT_K_AAI } else { DEFAULT_K };
+    let def_c = if amino_acid { DEFAULT_C_AAI } else { DEFAULT_C };
+
+    let k = opts.k.as_ref()
+        .map(|s| s.parse::<usize>().unwrap())
+        .unwrap_or(def_k.parse().unwrap());
+
+    let mut c = opts.c.as_ref()
+        .map(|s| s.parse::<usize>().unwrap())
+        .unwrap_or(def_c.parse().unwrap());
+
+    let mut marker_c = opts.marker_c.as_ref()
+        .map(|s| s.parse::<usize>().unwrap())
+        .unwrap_or(MARKER_C_DEFAULT.parse().unwrap());
+
+    // Handle presets
+    if opts.fast && opts.slow {
+        panic!("Both --slow and --fast were set. This is not allowed.");
+    }
+    if opts.fast {
+        if opts.c.is_some() {
+            warn!("-c value is set but --fast is also set. Using --fast mode instead (-c 200)");
+        }
+        c = FAST_C;
+    }
+    if opts.slow {
+        if opts.c.is_some() {
+            warn!("-c value is set but --slow is also set. Using --slow mode instead (-c 30)");
+        }
+        c = SLOW_C;
+    }
+    if opts.medium {
+        if opts.c.is_some() {
+            warn!("-c value is set but --medium is also set. Using --medium mode instead (-c 70)");
+        }
+        c = MEDIUM_C;
+    }
+    if opts.genus {
+        if opts.c.is_some() {
+            warn!("-c value is set but --genus is also set. Using --genus mode instead (-c 70)");
+        }
+        c = GENUS_C;
+    }
+    if opts.small_genomes {
+        if opts.c.is_some() || opts.marker_c.is_some() {
+            warn!("-c or -m value is set but --small-genomes is also set. Using -c 30 and -m 200 instead.");
+        }
+        c = SLOW_C;
+        marker_c = SMALL_M;
+    }
+
+    let mut sketch_params = SketchParams::new(marker_c, c, k, false, amino_acid);
+    if let Some(spaced_seed) = parse_seed_pattern(&opts.seed_pattern, opts.genus, opts.k.is_some(), amino_acid) {
+        sketch_params.set_spaced_seed(spaced_seed);
+    }
+    if let Some(marker_k) = &opts.marker_k {
+        sketch_params.marker_k = parse_marker_k(marker_k);
+    }
+    if opts.annotated_cds && !amino_acid {
+        warn!("--annotated-cds only has an effect with --aai.");
+    }
+    sketch_params.annotated_cds = opts.annotated_cds && amino_acid;
+    if let Some(min_contig_length) = opts.min_contig_length {
+        sketch_params.min_contig_length = min_contig_length;
+    }
+    sketch_params.min_segment_length = opts.min_segment_length.unwrap_or(sketch_params.min_contig_length);
+    sketch_params.contig_include = opts.include_contigs.clone();
+    sketch_params.contig_exclude = opts.exclude_contigs.clone();
+    check_contig_filters(&sketch_params.contig_include, &sketch_params.contig_exclude);
+    sketch_params.soft_mask = opts.soft_mask;
+    sketch_params.mask_bed = opts.mask_bed.clone();
+    check_mask_bed(&sketch_params);
+    sketch_params.repeat_mask = opts.repeat_mask.as_deref().map(parse_repeat_mask);
+    check_sketch_params(&sketch_params);
+    sketch_params
+}
+
+/// The spaced seed from --seed-pattern or --genus. Its weight replaces k, so a -k value is
+/// ignored.
+fn parse_seed_pattern(seed_pattern: &Option<String>, genus: bool, k_set: bool, amino_acid: bool) -> Option<SpacedSeed> {
+    let pattern = if genus {
+        if seed_pattern.is_some() {
+            warn!("--seed-pattern is set but --genus is also set. Using --genus mode instead (--seed-pattern {})", GENUS_SEED_PATTERN);
+        }
+        GENUS_SEED_PATTERN
+    } else {
+        seed_pattern.as_deref()?
+    };
+    if amino_acid {
+        error!("Spaced seeds (--seed-pattern, --genus) are not supported in amino acid mode.");
+        std::process::exit(1);
+    }
+    match SpacedSeed::parse(pattern) {
+        Ok(spaced_seed) => {
+            if k_set {
+                let option = if genus { "--genus" } else { "--seed-pattern" };
+                warn!("-k value is set but {} is also set. Using the seed pattern's weight instead (-k {})", option, spaced_seed.weight());
+            }
+            Some(spaced_seed)
+        }
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_marker_k(value: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(marker_k) if marker_k > 0 && marker_k <= MAX_MARKER_K_DNA => marker_k,
+        _ => {
+            error!("--marker-k must be an integer from 1 to {}, not '{}'. Exiting.", MAX_MARKER_K_DNA, value);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_repeat_mask(value: &str) -> RepeatMask {
+    if value == "auto" {
+        return RepeatMask::Adaptive;
+    }
+    match value.parse::<usize>() {
+        Ok(threshold) if threshold > 0 => RepeatMask::Threshold(threshold),
+        _ => {
+            error!("--repeat-mask must be 'auto' or a positive integer, not '{}'. Exiting.", value);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn check_mask_bed(sketch_params: &SketchParams) {
+    if let Some(mask_bed) = &sketch_params.mask_bed {
+        if let Err(e) = file_io::read_mask_bed(mask_bed) {
+            error!("Invalid mask BED file {}. Exiting.", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `-` reading a serialized sketch from stdin.
+fn is_stdin_sketch(file: &str) -> bool {
+    file_io::is_stdin(file) && file_io::stdin_is_sketch()
+}
+
+/// Stdin can only be read once.
+fn check_stdin_inputs(files: &[&Vec<String>]) {
+    if files.iter().flat_map(|x| x.iter()).filter(|x| file_io::is_stdin(x)).count() > 1 {
+        error!("Only one input can be read from stdin ('{}'). Exiting.", STDIO_PATH);
+        std::process::exit(1);
+    }
+}
+
+fn check_contig_filters(include: &Option<String>, exclude: &Option<String>) {
+    for pattern in [include, exclude].into_iter().flatten() {
+        if let Err(e) = file_io::contig_regex(pattern) {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn check_sketch_params(sketch_params: &SketchParams) {
+    if let Err(e) = sketch_params.check_seed_lengths() {
+        error!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Reads a grouping TSV (`genome_name<TAB>file1,file2,...`) into `genome_groups` and
+/// returns the genome names in file order, to be used in place of file names.
+fn read_genome_groups(file_path: &str, genome_groups: &mut GenomeGroups) -> Vec<String> {
+    let file = File::open(file_path)
+        .unwrap_or_else(|_| panic!("File {} could not be opened properly. Make sure this file exists. Exiting.", file_path));
+    let reader = BufReader::new(file);
+    let mut names = vec![];
+    for line in reader.lines() {
+        let line = line.unwrap();
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (name, files) = match line.split_once('\t') {
+            Some((name, files)) => (name.trim(), files),
+            None => {
+                error!("Line '{}' in {} is not of the form genome_name<TAB>file1,file2,... Exiting.", line, file_path);
+                std::process::exit(1);
+            }
+        };
+        let files = files
+            .split(',')
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<String>>();
+        if name.is_empty() || files.is_empty() {
+            error!("Line '{}' in {} needs both a genome name and at least one file. Exiting.", line, file_path);
+            std::process::exit(1);
+        }
+        if genome_groups.insert(name.to_string(), files).is_some() {
+            error!("Genome {} is listed more than once in the grouping files. Exiting.", name);
+            std::process::exit(1);
+        }
+        names.push(name.to_string());
+    }
+    names
+}
+
+/// Grouped genomes are sketched as a whole, so they can't be split into contigs.
+fn check_genome_groups(genome_groups: &GenomeGroups, individual_contig: bool) {
+    if !genome_groups.is_empty() && individual_contig {
+        error!("Grouping files cannot be used with individual contig mode (-i, --qi, --ri). Exiting.");
+        std::process::exit(1);
+    }
+}
+
+/// One input per line. A line may be `path<TAB>name`, in which case `name` is recorded
+/// in `display_names` and shown in output instead of the path.
+fn read_file_list(file_path: &str, display_names: &mut DisplayNames) -> Vec<String> {
     let file = File::open(file_path)
         .unwrap_or_else(|_| panic!("File {} could not be opened properly. Make sure this file exists. Exiting.", file_path));
     let reader = BufReader::new(file);
     reader.lines()
-        .map(|line| line.unwrap().trim().to_string())
+        .map(|line| {
+            let line = line.unwrap();
+            match line.trim().split_once('\t') {
+                Some((path, name)) => {
+                    let path = path.trim().to_string();
+                    let name = name.trim();
+                    if !name.is_empty() {
+                        display_names.insert(path.clone(), name.to_string());
+                    }
+                    path
+                }
+                None => line.trim().to_string(),
+            }
+        })
         .collect()
 }
+
+fn parse_ci_params(method: &Option<String>, replicates: &Option<String>, seed: &Option<String>) -> CiParams {
+    let method = match method.as_deref() {
+        None | Some("bootstrap") => CiMethod::Bootstrap,
+        Some("analytic") => CiMethod::Analytic,
+        Some(x) => {
+            error!("Unknown --ci-method {}; must be 'bootstrap' or 'analytic'.", x);
+            std::process::exit(1);
+        }
+    };
+    let replicates = replicates.as_ref()
+        .map(|s| s.parse::<usize>().unwrap())
+        .unwrap_or(DEFAULT_CI_REPLICATES.parse().unwrap());
+    if replicates < MIN_CI_REPLICATES {
+        error!("--ci-replicates must be at least {}.", MIN_CI_REPLICATES);
+        std::process::exit(1);
+    }
+    let seed = seed.as_ref().map(|s| s.parse::<u64>().unwrap());
+    if method == CiMethod::Analytic && seed.is_some() {
+        warn!("--ci-seed has no effect with --ci-method analytic.");
+    }
+    CiParams {
+        method,
+        replicates,
+        seed,
+    }
+}
@@ -0,0 +1,219 @@
+use crate::chain;
+use crate::file_io;
+use crate::params::*;
+use crate::types::*;
+use log::*;
+use rayon::prelude::*;
+use std::time::Instant;
+
+/// Merge (possibly overlapping) intervals into a sorted list of disjoint intervals.
+fn merge_intervals(mut intervals: Vec<(GnPosition, GnPosition)>) -> Vec<(GnPosition, GnPosition)> {
+    intervals.sort_unstable();
+    let mut merged: Vec<(GnPosition, GnPosition)> = vec![];
+    for int in intervals {
+        if let Some(last) = merged.last_mut() {
+            if int.0 <= last.1 {
+                last.1 = GnPosition::max(last.1, int.1);
+                continue;
+            }
+        }
+        merged.push(int);
+    }
+    merged
+}
+
+fn is_covered(merged: &[(GnPosition, GnPosition)], pos: GnPosition) -> bool {
+    let ind = merged.partition_point(|x| x.1 <= pos);
+    ind < merged.len() && merged[ind].0 <= pos
+}
+
+fn covered_bases(merged: &[(GnPosition, GnPosition)], start: GnPosition, end: GnPosition) -> GnPosition {
+    let mut bases = 0;
+    let first = merged.partition_point(|x| x.1 <= start);
+    for int in merged[first..].iter() {
+        if int.0 >= end {
+            break;
+        }
+        bases += GnPosition::min(int.1, end) - GnPosition::max(int.0, start);
+    }
+    bases
+}
+
+/// Seed positions for each reference contig, sorted by position. Repetitive seeds
+/// are skipped in the same way as during anchoring.
+fn ref_seeds_by_contig(ref_sketch: &Sketch, index_chain_band: usize) -> Vec<Vec<(GnPosition, SeedBits)>> {
+    let mut seeds = vec![vec![]; ref_sketch.contigs.len()];
+    if let Some(kmer_seeds) = &ref_sketch.kmer_seeds_k {
+        for seed in kmer_seeds.keys() {
+            let positions = ref_sketch.get_seed_positions(seed);
+            if positions.len() > index_chain_band || ref_sketch.repeat_seeds.contains(&seed) {
+                continue;
+            }
+            for pos in positions.iter() {
+                seeds[pos.contig_index() as usize].push((pos.pos, seed));
+            }
+        }
+    }
+    for contig_seeds in seeds.iter_mut() {
+        contig_seeds.sort_unstable();
+    }
+    seeds
+}
+
+/// Windowed ANI and coverage of a single query along the reference. Windows only use
+/// reference seeds lying inside orthologous chains; ANI is estimated from the fraction
+/// of those seeds shared with the query, as for the fragment ANIs in `chain::calculate_ani`.
+fn profile_query(
+    ref_sketch: &Sketch,
+    query_sketch: &Sketch,
+    ref_seeds: &[Vec<(GnPosition, SeedBits)>],
+    command_params: &CommandParams,
+) -> Vec<ProfileWindow> {
+    let model_opt = None;
+    let map_params = chain::map_params_from_sketch(ref_sketch, false, command_params, &model_opt);
+    let k = map_params.k as GnPosition;
+    let seed_span = map_params.seed_span as GnPosition;
+    let (int_chunks, _, switched) = chain::chain_intervals(ref_sketch, query_sketch, &map_params);
+
+    let mut intervals_by_contig = vec![vec![]; ref_sketch.contigs.len()];
+    for int in int_chunks.iter().flatten() {
+        let (contig, (start, end)) = int.ref_coords(switched);
+        intervals_by_contig[contig].push((start, end + seed_span));
+    }
+    let merged_by_contig = intervals_by_contig
+        .into_iter()
+        .map(merge_intervals)
+        .collect::<Vec<Vec<(GnPosition, GnPosition)>>>();
+
+    let query_seeds = query_sketch.kmer_seeds_k.as_ref().unwrap();
+    let window = command_params.window_size as GnPosition;
+    let step = command_params.window_step as GnPosition;
+    let mut windows = vec![];
+    for (contig, &contig_len) in ref_sketch.contig_lengths.iter().enumerate() {
+        let merged = &merged_by_contig[contig];
+        let seeds = &ref_seeds[contig];
+        let mut start = 0;
+        while start < contig_len {
+            let end = GnPosition::min(start + window, contig_len);
+            let first = seeds.partition_point(|x| x.0 < start);
+            let mut num_seeds = 0;
+            let mut num_shared = 0;
+            for (pos, seed) in seeds[first..].iter() {
+                if *pos >= end {
+                    break;
+                }
+                if is_covered(merged, *pos) {
+                    num_seeds += 1;
+                    if query_seeds.contains_key(*seed) {
+                        num_shared += 1;
+                    }
+                }
+            }
+            let ani = if num_seeds == 0 {
+                f32::NAN
+            } else {
+                f64::powf(num_shared as f64 / num_seeds as f64, 1. / k as f64) as f32
+            };
+            windows.push(ProfileWindow {
+                ref_contig: contig,
+                start,
+                end,
+                ani,
+                coverage: covered_bases(merged, start, end) as f32 / (end - start) as f32,
+                num_seeds,
+            });
+            if end == contig_len {
+                break;
+            }
+            start += step;
+        }
+    }
+    windows
+}
+
+pub fn profile(command_params: CommandParams, mut sketch_params: SketchParams) {
+    let now = Instant::now();
+    let mut ref_sketches;
+    let mut query_sketches;
+    let query_filters = QueryContigFilters::from_sketch_params(&sketch_params);
+    if command_params.refs_are_sketch {
+        let new_sketch_params;
+        (new_sketch_params, ref_sketches) = file_io::sketches_from_sketch(&command_params.ref_files);
+        if !new_sketch_params.same_seeds(&sketch_params) {
+            warn!("Parameters from .sketch files not equal to the input parameters. Using parameters from .sketch files.")
+        }
+        sketch_params = new_sketch_params;
+    } else {
+        ref_sketches = file_io::fastx_to_sketches(&command_params.ref_files, &sketch_params, true);
+    }
+    // Fasta queries against .sketch references keep their seeding but not their mask or header filters.
+    let query_sketch_params = if command_params.refs_are_sketch {
+        sketch_params.for_queries(&query_filters)
+    } else {
+        sketch_params.clone()
+    };
+    if command_params.queries_are_sketch {
+        let query_params;
+        (query_params, query_sketches) = file_io::sketches_from_sketch(&command_params.query_files);
+        if !sketch_params.same_seeds(&query_params) {
+            error!("Query sketch parameters were not equal to reference sketch parameters. Exiting.");
+            std::process::exit(1)
+        }
+        if command_params.refs_are_sketch {
+            if let Err(e) = file_io::check_masks(&sketch_params, &query_params) {
+                warn!("{}", e);
+            }
+        }
+    } else {
+        query_sketches = file_io::fastx_to_sketches(&command_params.query_files, &query_sketch_params, true);
+    }
+    if query_sketches.is_empty() || ref_sketches.is_empty() {
+        error!("No reference sketches/genomes or query sketches/genomes found.");
+        std::process::exit(1)
+    }
+    if let Err(e) = file_io::check_seed_layouts(&ref_sketches, &query_sketches) {
+        error!("{} Exiting.", e);
+        std::process::exit(1)
+    }
+    if let Err(e) = file_io::check_min_contig_lengths(&ref_sketches, &query_sketches) {
+        warn!("{}", e);
+    }
+    file_io::set_display_names(&mut ref_sketches, &command_params.display_names);
+    file_io::set_display_names(&mut query_sketches, &command_params.display_names);
+    if ref_sketches.len() > 1 {
+        warn!("More than one reference found; only profiling against {}.", ref_sketches[0].file_name);
+    }
+    if sketch_params.use_aa {
+        error!("skani profile does not support amino acid sketches. Exiting.");
+        std::process::exit(1)
+    }
+    info!("Generating sketch time: {}", now.elapsed().as_secs_f32());
+
+    let now = Instant::now();
+    let ref_sketch = &ref_sketches[0];
+    let index_chain_band = BP_CHAIN_BAND / ref_sketch.c;
+    let ref_seeds = ref_seeds_by_contig(ref_sketch, index_chain_band);
+
+    //Keep the query order given on the command line so tracks are stacked predictably.
+    let mut query_order = (0..query_sketches.len()).collect::<Vec<usize>>();
+    query_order.sort_by_key(|&j| {
+        command_params
+            .query_files
+            .iter()
+            .position(|x| *x == query_sketches[j].file_name)
+            .unwrap_or(usize::MAX)
+    });
+    let profiles = query_order
+        .into_par_iter()
+        .map(|j| {
+            let query_sketch = &query_sketches[j];
+            (
+                query_sketch.name(),
+                profile_query(ref_sketch, query_sketch, &ref_seeds, &command_params),
+            )
+        })
+        .collect::<Vec<(&str, Vec<ProfileWindow>)>>();
+
+    file_io::write_profile(&profiles, ref_sketch, &command_params.out_file_name);
+    info!("Profiling time: {}", now.elapsed().as_secs_f32());
+}
@@ -52,13 +52,7 @@ pub fn screen_refs_indices(
             }
         }
     }
-    //Use fixed K value for AA markers, but flexible ones for DNA because saturation less of an
-    //issue.
-    let k = if sketch_params.use_aa {
-        K_MARKER_AA
-    } else {
-        K_MARKER_DNA
-    };
+    let k = sketch_params.marker_k;
     let cutoff = identity.powi(k as i32);
     trace!("cutoff screening val {}",cutoff);
     let ret = count_hash_map
@@ -119,7 +113,7 @@ pub fn check_markers_quickly(
     }
 
     assert!(ref_sketch.amino_acid == query_sketch.amino_acid);
-    let k = if ref_sketch.amino_acid{K_MARKER_AA} else {K_MARKER_DNA};
+    let k = ref_sketch.marker_k;
 
     let ratio = screen_val.powi(k.try_into().unwrap()) * min_card as f64;
     let mut ratio = ratio as usize;
@@ -141,6 +135,115 @@ pub fn check_markers_quickly(
     false
 }
 
+///Full marker intersection statistics for a pair. Unlike `check_markers_quickly`,
+///this does not stop early, so it is only used for diagnostics.
+pub fn marker_screen_stats(
+    ref_sketch: &Sketch,
+    query_sketch: &Sketch,
+    screen_val: f64,
+    rescue_small: bool,
+) -> MarkerScreenStats{
+    let (seeds1, seeds2) = if query_sketch.marker_seeds.len() > ref_sketch.marker_seeds.len(){
+        (&ref_sketch.marker_seeds, &query_sketch.marker_seeds)
+    }
+    else{
+        (&query_sketch.marker_seeds, &ref_sketch.marker_seeds)
+    };
+    let min_card = seeds1.len();
+    let shared = seeds1.iter().filter(|x| seeds2.contains(x)).count();
+    let k = ref_sketch.marker_k;
+    let required = usize::max((screen_val.powi(k as i32) * min_card as f64) as usize, 1);
+    let marker_identity = if min_card == 0{
+        0.
+    }
+    else{
+        f64::powf(shared as f64 / min_card as f64, 1. / k as f64)
+    };
+    MarkerScreenStats{
+        ref_markers: ref_sketch.marker_seeds.len(),
+        query_markers: query_sketch.marker_seeds.len(),
+        shared_markers: shared,
+        min_markers: min_card,
+        screen_val,
+        required_shared: required,
+        marker_identity,
+        passed: check_markers_quickly(ref_sketch, query_sketch, screen_val, rescue_small),
+    }
+}
+
+///Wilson score interval for a binomial proportion at the same confidence level as --ci.
+fn wilson_interval(successes: usize, trials: usize) -> (f64, f64) {
+    if trials == 0 {
+        return (0., 1.);
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = CI_Z_90 * CI_Z_90;
+    let denom = 1. + z2 / n;
+    let center = (p + z2 / (2. * n)) / denom;
+    let half = CI_Z_90 * (p * (1. - p) / n + z2 / (4. * n * n)).sqrt() / denom;
+    (f64::max(center - half, 0.), f64::min(center + half, 1.))
+}
+
+///ANI for `--markers-only`: no chaining, only the shared marker k-mers. With max-containment
+///C = shared / min(#markers), the ANI is C^(1/k) for the marker k. Aligned fractions assume
+///the genome with fewer markers is fully aligned, i.e. AF = min(1, containment / C).
+///The interval treats the shared count as binomial (Wilson interval) and transforms it
+///the same way.
+pub fn marker_ani(ref_sketch: &Sketch, query_sketch: &Sketch, map_params: &MapParams) -> AniEstResult {
+    let (seeds1, seeds2) = if query_sketch.marker_seeds.len() > ref_sketch.marker_seeds.len(){
+        (&ref_sketch.marker_seeds, &query_sketch.marker_seeds)
+    }
+    else{
+        (&query_sketch.marker_seeds, &ref_sketch.marker_seeds)
+    };
+    let min_card = seeds1.len();
+    let shared = seeds1.iter().filter(|x| seeds2.contains(x)).count();
+    let k = ref_sketch.marker_k;
+    let mut ret = AniEstResult{
+        ani: f32::NAN,
+        ref_file: ref_sketch.name().to_string(),
+        query_file: query_sketch.name().to_string(),
+        ref_path: ref_sketch.file_name.clone(),
+        query_path: query_sketch.file_name.clone(),
+        query_contig: query_sketch.contigs[0].clone(),
+        ref_contig: ref_sketch.contigs[0].clone(),
+        num_contigs_r: ref_sketch.contigs.len() as u32,
+        num_contigs_q: query_sketch.contigs.len() as u32,
+        aai: map_params.amino_acid,
+        tetra: crate::tetra::tetra_correlation(&ref_sketch.tetra_zscores, &query_sketch.tetra_zscores),
+        ref_stats: ref_sketch.genome_stats,
+        query_stats: query_sketch.genome_stats,
+        ..Default::default()
+    };
+    if shared == 0{
+        return ret;
+    }
+    let containment = shared as f64 / min_card as f64;
+    let to_ani = |c: f64| c.powf(1. / k as f64) as f32;
+    let af = |markers: usize| f64::min(1., shared as f64 / markers as f64 / containment) as f32;
+    let (lower, upper) = wilson_interval(shared, min_card);
+    ret.ani = to_ani(containment);
+    ret.ci_lower = to_ani(lower);
+    ret.ci_upper = to_ani(upper);
+    ret.align_fraction_ref = af(ref_sketch.marker_seeds.len());
+    ret.align_fraction_query = af(query_sketch.marker_seeds.len());
+
+    //Same aligned fraction cutoffs as `chain::calculate_ani`.
+    let (af_r, af_q) = (ret.align_fraction_ref as f64, ret.align_fraction_query as f64);
+    let fails_af = if map_params.both_frac_cover_cutoff > 0.0 {
+        af_q < map_params.both_frac_cover_cutoff || af_r < map_params.both_frac_cover_cutoff
+    } else if map_params.amino_acid {
+        af_q < map_params.frac_cover_cutoff || af_r < map_params.frac_cover_cutoff
+    } else {
+        af_q < map_params.frac_cover_cutoff && af_r < map_params.frac_cover_cutoff
+    };
+    if fails_af{
+        ret.ani = -1.;
+    }
+    ret
+}
+
 ///Screen used in triangle, dist, but not search.
 ///Returns the indices of sketches in ref_sketch that
 ///pass the filter, using an inverted-index. If `rescue_small` is true,
@@ -166,13 +269,7 @@ pub fn screen_refs(
             }
         }
     }
-    //Use fixed K value for AA markers, but flexible ones for DNA because saturation less of an
-    //issue.
-    let k = if sketch_params.use_aa {
-        K_MARKER_AA
-    } else {
-        K_MARKER_DNA
-    };
+    let k = sketch_params.marker_k;
     let cutoff = identity.powi(k as i32);
     let ret = count_hash_map
         .iter()
@@ -13,6 +13,8 @@ use std::sync::Mutex;
 use std::sync::RwLock;
 use std::time::Instant;
 
+/// Query fastas are sketched with the database's parameters, apart from the contig filters,
+/// which come from `command_params.query_contig_filters`.
 pub fn search(command_params: CommandParams) {
     let now = Instant::now();
     info!("Searching...");
@@ -37,6 +39,16 @@ pub fn search(command_params: CommandParams) {
     let ref_sketches;
     let sketch_params;
     (sketch_params, ref_sketches) = file_io::marker_sketches_from_marker_file(ref_marker_file);
+    let query_filters = &command_params.query_contig_filters;
+    let query_sketch_params = sketch_params.for_queries(query_filters);
+    if let Some(min_contig_length) = query_filters.min_contig_length {
+        if min_contig_length != sketch_params.min_contig_length {
+            warn!(
+                "Query contigs are filtered at {} bp but the database was sketched with a minimum contig length of {} bp; aligned fractions of queries and references are not comparable.",
+                min_contig_length, sketch_params.min_contig_length
+            );
+        }
+    }
     let screen_val;
     if command_params.screen_val == 0. {
         if sketch_params.use_aa {
@@ -96,25 +108,34 @@ pub fn search(command_params: CommandParams) {
     
     for query_file in command_params.query_files.iter() {
         let query_params;
-        let query_sketches;
+        let mut query_sketches;
         if command_params.queries_are_sketch {
             (query_params, query_sketches) =
                 file_io::sketches_from_sketch(&vec![query_file.clone()]);
-            if query_params != sketch_params && !query_file.contains("markers.bin") {
+            if !query_params.same_seeds(&sketch_params) && !query_file.contains("markers.bin") {
                 warn!("Query sketch parameters for {} not equal to reference sketch parameters; no ANI calculated", query_file);
             }
         } else if command_params.individual_contig_q {
             query_sketches = file_io::fastx_to_multiple_sketch_rewrite(
                 &vec![query_file.clone()],
-                &sketch_params,
+                &query_sketch_params,
                 true,
             );
         } else {
-            query_sketches =
-                file_io::fastx_to_sketches(&vec![query_file.clone()], &sketch_params, true);
+            query_sketches = file_io::fastx_groups_to_sketches(
+                &vec![query_file.clone()],
+                &command_params.genome_groups,
+                &query_sketch_params,
+                true,
+            );
         }
 
+        file_io::set_display_names(&mut query_sketches, &command_params.display_names);
         if !query_sketches.is_empty() {
+            if let Err(e) = file_io::check_seed_layouts(&ref_sketches, &query_sketches) {
+                warn!("{} Skipping {}.", e, query_file);
+                continue;
+            }
             let is = 0..query_sketches.len();
             is.into_par_iter().for_each(|i| {
                 let query_sketch = &query_sketches[i];
@@ -140,6 +161,21 @@ pub fn search(command_params: CommandParams) {
                     );
                 }
                 ref_indices_to_try.into_par_iter().for_each(|j| {
+                    if command_params.extra_columns.markers_only {
+                        //Marker sketches are enough; full reference sketches are never loaded.
+                        let map_params = chain::map_params_from_sketch(
+                            &ref_sketches[j],
+                            sketch_params.use_aa,
+                            &command_params,
+                            &model_opt
+                        );
+                        let ani_res = chain::chain_seeds(&ref_sketches[j], query_sketch, map_params);
+                        if ani_res.ani > 0.5 {
+                            let mut locked = anis.lock().unwrap();
+                            locked.push(ani_res);
+                        }
+                        return;
+                    }
                     let original_file = &ref_sketches[j].file_name;
                     let ref_sketch;
                     if !command_params.keep_refs {
@@ -271,6 +307,8 @@ pub fn search(command_params: CommandParams) {
                             command_params.detailed_out,
                             !*fw,
                             command_params.short_header,
+                            &command_params.extra_columns,
+                            &command_params.ci_params,
                         );
                         if *fw == true{
                             *fw = false;
@@ -295,6 +333,8 @@ pub fn search(command_params: CommandParams) {
         command_params.detailed_out,
         !*first_write.lock().unwrap(),
         command_params.short_header,
+        &command_params.extra_columns,
+        &command_params.ci_params,
     );
     info!("Searching time: {}", now.elapsed().as_secs_f32());
 }
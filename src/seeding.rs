@@ -1,5 +1,6 @@
 use crate::params::*;
 use crate::types::*;
+use log::*;
 use rust_lapper::{Interval, Lapper};
 
 #[inline]
@@ -121,7 +122,7 @@ pub fn fmh_seeds_aa_with_orf(
 ) {
     let marker_k = K_MARKER_AA;
     if seed && new_sketch.kmer_seeds_k.is_none() {
-        new_sketch.kmer_seeds_k = Some(KmerSeeds::default());
+        new_sketch.kmer_seeds_k = Some(KmerSeeds::with_seed_bits(5 * sketch_params.k));
     }
     let _kmer_seeds_k = &mut new_sketch.kmer_seeds_k;
     let k = sketch_params.k;
@@ -230,13 +231,13 @@ pub fn fmh_seeds(
     seed: bool,
 ) {
     if seed && new_sketch.kmer_seeds_k.is_none() {
-        new_sketch.kmer_seeds_k = Some(KmerSeeds::default());
+        new_sketch.kmer_seeds_k = Some(KmerSeeds::with_seed_bits(2 * sketch_params.k));
     }
-    let marker_k = K_MARKER_DNA;
+    let marker_k = sketch_params.marker_k;
     let _kmer_seeds_k = &mut new_sketch.kmer_seeds_k;
     let k = sketch_params.k;
     let c = sketch_params.c;
-    if k > 16 {
+    if sketch_params.seed_span() > marker_k {
         panic!("Value of k > {} for DNA; not allowed.", marker_k);
     }
     if string.len() < 2 * marker_k {
@@ -247,6 +248,8 @@ pub fn fmh_seeds(
     let mut rolling_kmer_r_marker: MarkerBits = 0;
     let mut rolling_kmer_r_seed: MarkerBits;
     let seed_mask = MarkerBits::MAX >> (std::mem::size_of::<MarkerBits>() * 8 - 2 * k);
+    let spaced_runs = sketch_params.spaced_seed.as_ref().map(|s| s.runs());
+    let seed_span = sketch_params.seed_span();
 
     let marker_reverse_shift_dist = 2 * (marker_k - 1);
     let marker_mask = MarkerBits::MAX >> (std::mem::size_of::<MarkerBits>() * 8 - 2 * marker_k);
@@ -257,7 +260,13 @@ pub fn fmh_seeds(
 
     let threshold = u64::MAX / (c as u64);
     let threshold_marker = u64::MAX / (sketch_params.marker_c as u64);
+    let mut resume_ind = 0;
+    let mut marker_resume_ind = 0;
     for i in 0..marker_k - 1 {
+        if !BYTE_IS_NUC[string[i] as usize] {
+            resume_ind = i + seed_span;
+            marker_resume_ind = i + marker_k;
+        }
         let nuc_f = BYTE_TO_SEQ[string[i] as usize];
         //        let nuc_f = KmerEnc::encode(string[i]
         let nuc_r = 3 - nuc_f;
@@ -267,11 +276,11 @@ pub fn fmh_seeds(
         rolling_kmer_r_marker >>= 2;
         rolling_kmer_r_marker |= nuc_r << marker_reverse_shift_dist;
     }
-    let mut resume_ind = 0;
     for i in marker_k-1..len {
         let nuc_byte = string[i] as usize;
-        if nuc_byte == ASCII_N || nuc_byte == ASCII_N_SMALL{
-            resume_ind = i + k;
+        if !BYTE_IS_NUC[nuc_byte] {
+            resume_ind = i + seed_span;
+            marker_resume_ind = i + marker_k;
         }
         let nuc_f = BYTE_TO_SEQ[nuc_byte];
         let nuc_r = 3 - nuc_f;
@@ -285,8 +294,13 @@ pub fn fmh_seeds(
         //        KmerEnc::print_string(rolling_kmer_f, k);
         //        KmerEnc::print_string(rolling_kmer_r, k);
         //
-        rolling_kmer_f_seed = rolling_kmer_f_marker & seed_mask;
-        rolling_kmer_r_seed = rolling_kmer_r_marker & seed_mask;
+        if let Some(runs) = &spaced_runs {
+            rolling_kmer_f_seed = SpacedSeed::extract(runs, rolling_kmer_f_marker);
+            rolling_kmer_r_seed = SpacedSeed::extract(runs, rolling_kmer_r_marker);
+        } else {
+            rolling_kmer_f_seed = rolling_kmer_f_marker & seed_mask;
+            rolling_kmer_r_seed = rolling_kmer_r_marker & seed_mask;
+        }
         let canonical_seed = rolling_kmer_f_seed < rolling_kmer_r_seed;
 
         let canonical_kmer_seed = if canonical_seed {
@@ -315,35 +329,58 @@ pub fn fmh_seeds(
                 rolling_kmer_r_marker
             };
 
-            if hash_seed < threshold_marker {
+            if hash_seed < threshold_marker && marker_resume_ind <= i {
                 new_sketch.marker_seeds.insert(canonical_kmer_marker);
             }
         }
     }
 }
 
-//This function is unused right now. Originally used for
-//getting a repetitive k-mer masking threshold. We may
-//modify the masking procedure in the future, so leaving for now.
-pub fn get_repetitive_kmers(kmer_seeds: &Option<KmerSeeds>, sketch: &Sketch, c: usize) -> usize {
-    if kmer_seeds.is_none() {
-        usize::MAX
-    } else {
-        let kmer_seeds = kmer_seeds.as_ref().unwrap();
-        let mut count_vec = vec![];
-        for (seed, _tagged_index) in kmer_seeds {
-            // Use the sketch's get_seed_positions to get the actual count
-            let positions_iter = sketch.get_seed_positions(*seed);
-            count_vec.push(positions_iter.len());
-        }
-        count_vec.sort();
-        let mut max_repet_cutoff = count_vec[count_vec.len() - count_vec.len() / 5000 - 1];
-        let default_repet_cutoff = BP_CHAIN_BAND / c;
-        if max_repet_cutoff < 20 || max_repet_cutoff > default_repet_cutoff{
-            max_repet_cutoff = default_repet_cutoff;
-        }
-        max_repet_cutoff
+/// Seed multiplicities more than three standard deviations above the mean are repeats.
+/// The threshold is at least REPEAT_MASK_MIN_THRESHOLD and at most the chaining band
+/// (BP_CHAIN_BAND / c), above which chaining skips seeds anyway.
+pub fn adaptive_repeat_threshold(counts: &[usize], c: usize) -> usize {
+    if counts.is_empty() {
+        return usize::MAX;
     }
+    let n = counts.len() as f64;
+    let mean = counts.iter().sum::<usize>() as f64 / n;
+    let var = counts.iter().map(|x| (*x as f64 - mean).powi(2)).sum::<f64>() / n;
+    let threshold = (mean + 3. * var.sqrt()).ceil() as usize;
+    usize::min(
+        usize::max(threshold, REPEAT_MASK_MIN_THRESHOLD),
+        usize::max(BP_CHAIN_BAND / c, REPEAT_MASK_MIN_THRESHOLD),
+    )
+}
+
+/// Records the seeds of `sketch` with more positions than the repeat threshold in
+/// `repeat_seeds`, and the threshold in `repetitive_kmers`.
+pub fn mask_repeats(sketch: &mut Sketch, repeat_mask: RepeatMask) {
+    let counts = match &sketch.kmer_seeds_k {
+        Some(kmer_seeds) => kmer_seeds
+            .keys()
+            .map(|seed| (seed, sketch.get_seed_positions(seed).len()))
+            .collect::<Vec<(SeedBits, usize)>>(),
+        None => return,
+    };
+    let threshold = match repeat_mask {
+        RepeatMask::Adaptive => {
+            adaptive_repeat_threshold(&counts.iter().map(|x| x.1).collect::<Vec<usize>>(), sketch.c)
+        }
+        RepeatMask::Threshold(threshold) => threshold,
+    };
+    sketch.repeat_seeds = counts
+        .into_iter()
+        .filter(|x| x.1 > threshold)
+        .map(|x| x.0)
+        .collect();
+    sketch.repetitive_kmers = threshold;
+    debug!(
+        "{}: {} seeds with > {} positions repeat-masked",
+        sketch.file_name,
+        sketch.repeat_seeds.len(),
+        threshold
+    );
 }
 
 
@@ -5,7 +5,7 @@ use crate::types::*;
 use log::*;
 use rayon::prelude::*;
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{self, BufWriter, Write};
 use std::sync::mpsc;
 
 use std::path::Path;
@@ -16,6 +16,11 @@ pub fn sketch(command_params: CommandParams, sketch_params: SketchParams) {
     let now = Instant::now();
     info!("Sketching files...");
     let p = command_params.out_file_name.to_string();
+    if p == STDIO_PATH {
+        sketch_to_stdout(command_params, sketch_params);
+        info!("Sketching time: {}", now.elapsed().as_secs_f32());
+        return;
+    }
     if Path::new(&p).exists() {
         error!("Output directory exists; output directory must not be an existing directory. Exiting.");
         std::process::exit(1);
@@ -34,6 +39,33 @@ pub fn sketch(command_params: CommandParams, sketch_params: SketchParams) {
     info!("Sketching time: {}", now.elapsed().as_secs_f32());
 }
 
+/// Write the sketch of a single genome to stdout, in the format of a --separate-sketches
+/// .sketch file.
+fn sketch_to_stdout(command_params: CommandParams, sketch_params: SketchParams) {
+    let mut ref_sketches = file_io::fastx_groups_to_sketches(
+        &command_params.ref_files,
+        &command_params.genome_groups,
+        &sketch_params,
+        true,
+    );
+    file_io::set_display_names(&mut ref_sketches, &command_params.display_names);
+    let sketch = match ref_sketches.first() {
+        Some(sketch) => sketch,
+        None => {
+            error!("No genome was sketched; nothing written to stdout. Exiting.");
+            std::process::exit(1);
+        }
+    };
+    let mut out = BufWriter::new(io::stdout().lock());
+    let res = bincode::serialize_into(&mut out, &(&sketch_params, sketch))
+        .map_err(|e| e.to_string())
+        .and_then(|_| out.flush().map_err(|e| e.to_string()));
+    if let Err(e) = res {
+        error!("Could not write the sketch to stdout: {}. Exiting.", e);
+        std::process::exit(1);
+    }
+}
+
 /// Create separate .sketch files (legacy format)
 fn sketch_separate_files(command_params: CommandParams, sketch_params: SketchParams) {
     let num_iters = command_params.ref_files.len();
@@ -41,7 +73,7 @@ fn sketch_separate_files(command_params: CommandParams, sketch_params: SketchPar
     let marker_sketches: Mutex<Vec<Sketch>> = Mutex::new(vec![]);
     
     (0..num_iters).into_par_iter().for_each(|i| {
-        let ref_sketches;
+        let mut ref_sketches;
         if command_params.individual_contig_r {
             ref_sketches = file_io::fastx_to_multiple_sketch_rewrite(
                 &vec![command_params.ref_files[i].clone()],
@@ -49,12 +81,14 @@ fn sketch_separate_files(command_params: CommandParams, sketch_params: SketchPar
                 true,
             );
         } else {
-            ref_sketches = file_io::fastx_to_sketches(
+            ref_sketches = file_io::fastx_groups_to_sketches(
                 &vec![command_params.ref_files[i].clone()],
+                &command_params.genome_groups,
                 &sketch_params,
                 true,
             );
         }
+        file_io::set_display_names(&mut ref_sketches, &command_params.display_names);
         let marker_ref_sketches = ref_sketches
             .iter()
             .map(Sketch::get_markers_only)
@@ -158,7 +192,7 @@ fn sketch_consolidated_db(command_params: CommandParams, sketch_params: SketchPa
     // Multi-producer: parallel sketch generation
     (0..num_iters).into_par_iter().for_each(|i| {
         let sender = sender.clone();
-        let ref_sketches;
+        let mut ref_sketches;
         
         if command_params.individual_contig_r {
             ref_sketches = file_io::fastx_to_multiple_sketch_rewrite(
@@ -167,12 +201,14 @@ fn sketch_consolidated_db(command_params: CommandParams, sketch_params: SketchPa
                 true,
             );
         } else {
-            ref_sketches = file_io::fastx_to_sketches(
+            ref_sketches = file_io::fastx_groups_to_sketches(
                 &vec![command_params.ref_files[i].clone()],
+                &command_params.genome_groups,
                 &sketch_params,
                 true,
             );
         }
+        file_io::set_display_names(&mut ref_sketches, &command_params.display_names);
 
         for sketch in ref_sketches {
             let marker_sketch = Sketch::get_markers_only(&sketch);
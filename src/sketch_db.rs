@@ -12,6 +12,17 @@ pub struct IndexEntry {
     pub file_name: String,
     pub offset: u64,
     pub length: u64,
+    pub stats: GenomeStats,
+    /// Sample name given when sketching, if any.
+    pub display_name: Option<String>,
+}
+
+/// Index entry layout of skani v0.3 databases, before genome statistics were stored.
+#[derive(Deserialize)]
+struct IndexEntryV03 {
+    file_name: String,
+    offset: u64,
+    length: u64,
 }
 
 /// Writer for creating consolidated sketch databases
@@ -25,6 +36,7 @@ pub struct SketchDbWriter {
 pub struct SketchDbReader {
     mmap: memmap2::Mmap,
     index: Vec<(u64, u64)>, // Vector of (offset, length) pairs, indexed by sketch index
+    entries: Vec<IndexEntry>,
 }
 
 impl SketchDbWriter {
@@ -52,6 +64,8 @@ impl SketchDbWriter {
             file_name: sketch.file_name.clone(),
             offset: self.current_offset,
             length,
+            stats: sketch.genome_stats,
+            display_name: sketch.display_name.clone(),
         };
         self.index.push(entry);
 
@@ -86,9 +100,24 @@ impl SketchDbReader {
     pub fn new(database_dir: &str) -> Result<Self, Box<dyn std::error::Error>> {
         // Load index.db
         let index_path = format!("{}/index.db", database_dir);
-        let index_file = File::open(index_path)?;
-        let index_reader = BufReader::new(index_file);
-        let index_vec: Vec<IndexEntry> = bincode::deserialize_from(index_reader)?;
+        let index_reader = BufReader::new(File::open(&index_path)?);
+        let index_vec: Vec<IndexEntry> = match bincode::deserialize_from(index_reader) {
+            Ok(index_vec) => index_vec,
+            Err(_) => {
+                //Re-read the index only to try the v0.3 layout.
+                let index_reader = BufReader::new(File::open(&index_path)?);
+                let old: Vec<IndexEntryV03> = bincode::deserialize_from(index_reader)?;
+                old.into_iter()
+                    .map(|entry| IndexEntry {
+                        file_name: entry.file_name,
+                        offset: entry.offset,
+                        length: entry.length,
+                        stats: GenomeStats::default(),
+                        display_name: None,
+                    })
+                    .collect()
+            }
+        };
 
         // Convert to vector for index-based lookups
         let index: Vec<(u64, u64)> = index_vec.iter()
@@ -101,7 +130,7 @@ impl SketchDbReader {
         let mmap = unsafe { memmap2::Mmap::map(&concat_file)? };
 
         info!("Loaded consolidated sketch database with {} sketches", index.len());
-        Ok(SketchDbReader { mmap, index })
+        Ok(SketchDbReader { mmap, index, entries: index_vec })
     }
 
     /// Get a sketch by index
@@ -114,7 +143,7 @@ impl SketchDbReader {
             let bytes = &self.mmap[start..end];
             
             // Time the deserialization (memory copy + parsing)
-            let (params, sketch) = bincode::deserialize(bytes)?;
+            let (params, sketch) = crate::file_io::deserialize_sketch(bytes)?;
                         
             Ok((params, sketch))
         } else {
@@ -122,6 +151,11 @@ impl SketchDbReader {
         }
     }
 
+    /// Index entries, in sketch order. Genome statistics are empty for v0.3 databases.
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
     /// Get the number of sketches in the database
     pub fn sketch_count(&self) -> usize {
         self.index.len()
@@ -0,0 +1,93 @@
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// Minimal SVG builder used by the plotting commands. Elements are appended
+/// in drawing order; nothing is buffered besides the output string.
+pub struct Svg {
+    width: f64,
+    height: f64,
+    body: String,
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn rgb(r: u8, g: u8, b: u8) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Linear interpolation between two colours; `t` is clamped to [0,1].
+pub fn blend(from: (u8, u8, u8), to: (u8, u8, u8), t: f64) -> (u8, u8, u8) {
+    let t = t.clamp(0., 1.);
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    (mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
+
+impl Svg {
+    pub fn new(width: f64, height: f64) -> Svg {
+        Svg {
+            width,
+            height,
+            body: String::new(),
+        }
+    }
+
+    pub fn rect(&mut self, x: f64, y: f64, w: f64, h: f64, fill: &str) {
+        writeln!(
+            self.body,
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"/>",
+            x, y, w, h, fill
+        )
+        .unwrap();
+    }
+
+    pub fn rect_outline(&mut self, x: f64, y: f64, w: f64, h: f64, stroke: &str) {
+        writeln!(
+            self.body,
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"none\" stroke=\"{}\"/>",
+            x, y, w, h, stroke
+        )
+        .unwrap();
+    }
+
+    pub fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, stroke: &str, stroke_width: f64) {
+        writeln!(
+            self.body,
+            "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" stroke-width=\"{}\"/>",
+            x1, y1, x2, y2, stroke, stroke_width
+        )
+        .unwrap();
+    }
+
+    /// Text anchored at (x,y). `rotate` is in degrees around the anchor.
+    pub fn text(&mut self, x: f64, y: f64, size: f64, anchor: &str, rotate: f64, text: &str) {
+        writeln!(
+            self.body,
+            "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{}\" font-family=\"sans-serif\" text-anchor=\"{}\" transform=\"rotate({} {:.2} {:.2})\">{}</text>",
+            x, y, size, anchor, rotate, x, y, escape(text)
+        )
+        .unwrap();
+    }
+
+    pub fn to_svg_string(&self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.0} {:.0}\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n{}</svg>\n",
+            self.width, self.height, self.width, self.height, self.body
+        )
+    }
+
+    /// Write to `file_name`, or stdout if `file_name` is empty.
+    pub fn write(&self, file_name: &str) {
+        let mut handle: Box<dyn Write> = if file_name.is_empty() {
+            Box::new(BufWriter::new(io::stdout().lock()))
+        } else {
+            Box::new(BufWriter::new(File::create(file_name).expect(file_name)))
+        };
+        handle.write_all(self.to_svg_string().as_bytes()).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+//! Tetranucleotide frequency Z-scores and their correlation (TETRA; Teeling et al. 2004).
+//!
+//! Counts are taken on both strands. The expected count of a tetranucleotide n1n2n3n4 comes
+//! from a maximal-order Markov model, E = N(n1n2n3) * N(n2n3n4) / N(n2n3). The Z-score is the
+//! deviation from E divided by its approximate standard deviation.
+
+use serde::{Deserialize, Serialize};
+
+pub const NUM_TETRA: usize = 256;
+
+/// Per-genome Z-score profile. Compared bitwise so that `Sketch` can stay `Eq`.
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct TetraProfile(pub Vec<f32>);
+
+impl PartialEq for TetraProfile {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().zip(other.0.iter()).all(|(a, b)| a.to_bits() == b.to_bits())
+    }
+}
+impl Eq for TetraProfile {}
+
+/// Tetranucleotide counts of the forward strand. Reverse-complement counts are added when
+/// computing Z-scores.
+#[derive(Clone)]
+pub struct TetraCounts {
+    tetra: [u64; NUM_TETRA],
+    //Words of length 1-3 at the start of each run of ACGT; needed so that tri- and
+    //dinucleotide counts can be derived exactly from the tetranucleotide counts.
+    prefix_tri: [u64; 64],
+    prefix_di: [u64; 16],
+    prefix_mono: [u64; 4],
+}
+
+impl Default for TetraCounts {
+    fn default() -> Self {
+        TetraCounts {
+            tetra: [0; NUM_TETRA],
+            prefix_tri: [0; 64],
+            prefix_di: [0; 16],
+            prefix_mono: [0; 4],
+        }
+    }
+}
+
+const INVALID: u8 = 4;
+const NUC_TO_BITS: [u8; 256] = {
+    let mut table = [INVALID; 256];
+    table[b'A' as usize] = 0;
+    table[b'a' as usize] = 0;
+    table[b'C' as usize] = 1;
+    table[b'c' as usize] = 1;
+    table[b'G' as usize] = 2;
+    table[b'g' as usize] = 2;
+    table[b'T' as usize] = 3;
+    table[b't' as usize] = 3;
+    table
+};
+
+/// Reverse complement of a 2-bit encoded word of `len` nucleotides.
+#[inline]
+fn rc_code(code: usize, len: usize) -> usize {
+    let mut rc = 0;
+    for i in 0..len {
+        rc = (rc << 2) | (3 - ((code >> (2 * i)) & 3));
+    }
+    rc
+}
+
+impl TetraCounts {
+    /// Count tetranucleotides; words spanning non-ACGT characters are skipped.
+    pub fn add_seq(&mut self, seq: &[u8]) {
+        let mut code = 0;
+        let mut valid = 0;
+        for &c in seq {
+            let b = NUC_TO_BITS[c as usize];
+            if b == INVALID {
+                valid = 0;
+                continue;
+            }
+            code = ((code << 2) | b as usize) & 0xff;
+            if valid >= 3 {
+                self.tetra[code] += 1;
+            } else {
+                valid += 1;
+                if valid == 1 {
+                    self.prefix_mono[code & 0x3] += 1;
+                } else if valid == 2 {
+                    self.prefix_di[code & 0xf] += 1;
+                } else if valid == 3 {
+                    self.prefix_tri[code & 0x3f] += 1;
+                }
+            }
+        }
+    }
+
+    /// Trinucleotide counts: every trinucleotide is either the suffix of a counted
+    /// tetranucleotide or the first trinucleotide of a run. Likewise for dinucleotides.
+    fn tri_di_counts(&self) -> ([u64; 64], [u64; 16]) {
+        let mut tri = self.prefix_tri;
+        for (w, count) in self.tetra.iter().enumerate() {
+            tri[w & 0x3f] += count;
+        }
+        let mut di = self.prefix_di;
+        for (w, count) in tri.iter().enumerate() {
+            di[w & 0xf] += count;
+        }
+        (tri, di)
+    }
+
+    /// Counts of A, C, G and T, derived the same way as the tri- and dinucleotide counts.
+    pub fn base_counts(&self) -> [u64; 4] {
+        let (_, di) = self.tri_di_counts();
+        let mut mono = self.prefix_mono;
+        for (w, count) in di.iter().enumerate() {
+            mono[w & 0x3] += count;
+        }
+        mono
+    }
+
+    /// Z-scores of all 256 tetranucleotides, indexed by their 2-bit code (A=0,C=1,G=2,T=3).
+    /// Returns an empty vector if there is too little sequence.
+    pub fn zscores(&self) -> TetraProfile {
+        let both = |counts: &[u64], i: usize, len: usize| (counts[i] + counts[rc_code(i, len)]) as f64;
+        if self.tetra.iter().sum::<u64>() == 0 {
+            return TetraProfile::default();
+        }
+        let (tri, di) = self.tri_di_counts();
+        let mut z = vec![0.; NUM_TETRA];
+        for (w, z_w) in z.iter_mut().enumerate() {
+            let n1n2n3 = both(&tri, w >> 2, 3);
+            let n2n3n4 = both(&tri, w & 0x3f, 3);
+            let n2n3 = both(&di, (w >> 2) & 0xf, 2);
+            if n2n3 == 0. {
+                continue;
+            }
+            let expected = n1n2n3 * n2n3n4 / n2n3;
+            let var = expected * ((n2n3 - n1n2n3) * (n2n3 - n2n3n4)) / (n2n3 * n2n3);
+            if var > 0. {
+                *z_w = ((both(&self.tetra, w, 4) - expected) / var.sqrt()) as f32;
+            }
+        }
+        TetraProfile(z)
+    }
+}
+
+/// Pearson correlation of two Z-score profiles. None if either genome has no profile
+/// (e.g. sketches made before profiles were stored).
+pub fn tetra_correlation(a: &TetraProfile, b: &TetraProfile) -> Option<f32> {
+    let (a, b) = (&a.0, &b.0);
+    if a.len() != NUM_TETRA || b.len() != NUM_TETRA {
+        return None;
+    }
+    let n = NUM_TETRA as f64;
+    let mean_a = a.iter().map(|x| *x as f64).sum::<f64>() / n;
+    let mean_b = b.iter().map(|x| *x as f64).sum::<f64>() / n;
+    let mut cov = 0.;
+    let mut var_a = 0.;
+    let mut var_b = 0.;
+    for (x, y) in a.iter().zip(b.iter()) {
+        let dx = *x as f64 - mean_a;
+        let dy = *y as f64 - mean_b;
+        cov += dx * dy;
+        var_a += dx * dx;
+        var_b += dy * dy;
+    }
+    if var_a == 0. || var_b == 0. {
+        return None;
+    }
+    Some((cov / (var_a * var_b).sqrt()) as f32)
+}
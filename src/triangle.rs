@@ -1,5 +1,6 @@
 use crate::chain;
 use crate::file_io;
+use crate::heatmap;
 use crate::params::*;
 use crate::regression;
 use crate::screen;
@@ -11,7 +12,7 @@ use std::sync::Mutex;
 use std::time::Instant;
 
 pub fn triangle(command_params: CommandParams, mut sketch_params: SketchParams) {
-    let ref_sketches;
+    let mut ref_sketches;
     let now = Instant::now();
     if command_params.refs_are_sketch {
         info!("Sketches detected.");
@@ -28,8 +29,9 @@ pub fn triangle(command_params: CommandParams, mut sketch_params: SketchParams)
             true,
         );
     } else {
-        ref_sketches = file_io::fastx_to_sketches(&command_params.ref_files, &sketch_params, true);
+        ref_sketches = file_io::fastx_groups_to_sketches(&command_params.ref_files, &command_params.genome_groups, &sketch_params, true);
     }
+    file_io::set_display_names(&mut ref_sketches, &command_params.display_names);
     let screen_val;
     if command_params.screen_val == 0. {
         if sketch_params.use_aa {
@@ -47,6 +49,13 @@ pub fn triangle(command_params: CommandParams, mut sketch_params: SketchParams)
         error!("No genomes/sketches found.");
         std::process::exit(1)
     }
+    if let Err(e) = file_io::check_seed_layouts(&ref_sketches, &[]) {
+        error!("{} Exiting.", e);
+        std::process::exit(1)
+    }
+    if let Err(e) = file_io::check_min_contig_lengths(&ref_sketches, &[]) {
+        warn!("{}", e);
+    }
 
     if ref_sketches.len() > 500 && !command_params.sparse {
         warn!("> 500 genomes detected. The output matrix will be large. Consider using -E or --sparse for a tsv output instead.");
@@ -55,6 +64,8 @@ pub fn triangle(command_params: CommandParams, mut sketch_params: SketchParams)
     let kmer_to_sketch = screen::kmer_to_sketch_from_refs(&ref_sketches);
     let counter: Mutex<usize> = Mutex::new(0);
     let first: Mutex<bool> = Mutex::new(true);
+    //Sparse output is written in batches, so keep a compact copy of the matrix for the heatmap.
+    let heatmap_pairs: Mutex<Vec<heatmap::HeatmapPair>> = Mutex::new(vec![]);
 
     let model_opt = regression::get_model(sketch_params.c, command_params.learned_ani);
     if model_opt.is_some() {
@@ -68,6 +79,15 @@ pub fn triangle(command_params: CommandParams, mut sketch_params: SketchParams)
         }
     }
 
+    //TETRA-only rows are only written in the sparse format; the matrix output needs an ANI.
+    let tetra_rows = command_params.extra_columns.tetra && command_params.sparse;
+    let add_tetra_only = |i: usize, j: usize| {
+        if let Some(tetra_res) = chain::tetra_only_result(&ref_sketches[i], &ref_sketches[j]) {
+            let mut locked = anis.lock().unwrap();
+            locked.entry(i).or_default().insert(j, tetra_res);
+        }
+    };
+
     (0..ref_sketches.len() - 1)
         .collect::<Vec<usize>>()
         .into_par_iter()
@@ -86,7 +106,7 @@ pub fn triangle(command_params: CommandParams, mut sketch_params: SketchParams)
                 ref_sketch_i.file_name,
                 screened_refs.len()
             );
-            screened_refs.into_par_iter().for_each(|j| {
+            screened_refs.par_iter().for_each(|&j| {
                 if j > i {
                     let map_params = chain::map_params_from_sketch(
                         ref_sketch_i,
@@ -97,12 +117,28 @@ pub fn triangle(command_params: CommandParams, mut sketch_params: SketchParams)
                     let ref_sketch_j = &ref_sketches[j];
                     let ani_res = chain::chain_seeds(ref_sketch_i, ref_sketch_j, map_params);
                     if ani_res.ani > 0.1 {
+                        if !command_params.heatmap_file.is_empty() {
+                            heatmap_pairs.lock().unwrap().push(heatmap::HeatmapPair {
+                                i,
+                                j,
+                                ani: ani_res.ani,
+                                af_i: ani_res.align_fraction_ref,
+                                af_j: ani_res.align_fraction_query,
+                            });
+                        }
                         let mut locked = anis.lock().unwrap();
                         let mapi = locked.entry(i).or_insert(FxHashMap::default());
                         mapi.insert(j, ani_res);
+                    } else if tetra_rows {
+                        add_tetra_only(i, j);
                     }
                 }
             });
+            if tetra_rows {
+                (i + 1..ref_sketches.len())
+                    .filter(|j| !screened_refs.contains(j))
+                    .for_each(|j| add_tetra_only(i, j));
+            }
 
             let c;
             {
@@ -129,6 +165,8 @@ pub fn triangle(command_params: CommandParams, mut sketch_params: SketchParams)
                             command_params.diagonal,
                             !*locked,
                             command_params.short_header,
+                            &command_params.extra_columns,
+                            &command_params.ci_params,
                         );
                         if *locked == true {
                             *locked = false;
@@ -152,6 +190,8 @@ pub fn triangle(command_params: CommandParams, mut sketch_params: SketchParams)
             command_params.diagonal,
             !*first.lock().unwrap(),
             command_params.short_header,
+            &command_params.extra_columns,
+            &command_params.ci_params,
         );
     } else {
         file_io::write_phyllip_matrix(
@@ -165,5 +205,25 @@ pub fn triangle(command_params: CommandParams, mut sketch_params: SketchParams)
             command_params.distance,
         );
     }
+    if !command_params.heatmap_file.is_empty() {
+        let names = ref_sketches
+            .iter()
+            .map(|x| {
+                if command_params.individual_contig_r {
+                    truncate_contig_name(&x.contigs[0], true)
+                } else {
+                    x.name().to_string()
+                }
+            })
+            .collect::<Vec<String>>();
+        heatmap::write_heatmap(
+            &names,
+            &heatmap_pairs.into_inner().unwrap(),
+            &command_params.heatmap_file,
+            command_params.heatmap_labels,
+            command_params.heatmap_af,
+            command_params.plot_size,
+        );
+    }
     info!("ANI triangle time: {}", now.elapsed().as_secs_f32());
 }
@@ -0,0 +1,131 @@
+//! UCSC 2bit input. The file is memory mapped and sequences are decoded one at a time.
+//! N blocks are decoded as `N`, which resets seeding like an N in fasta input. Soft-mask
+//! blocks are decoded as lowercase, as `twoBitToFa` does.
+
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::Read;
+
+const SIGNATURE: u32 = 0x1A41_2743;
+const BASES: [u8; 4] = [b'T', b'C', b'A', b'G'];
+const TRUNCATED: &str = "truncated 2bit file";
+
+/// Detects a 2bit file from its signature, in either byte order.
+pub fn is_twobit(file: &str) -> bool {
+    let mut signature = [0; 4];
+    match File::open(file).and_then(|mut f| f.read_exact(&mut signature)) {
+        Ok(()) => {
+            u32::from_le_bytes(signature) == SIGNATURE || u32::from_be_bytes(signature) == SIGNATURE
+        }
+        Err(_) => false,
+    }
+}
+
+/// Calls `f(name, seq)` for every sequence. Sequences before a corrupt record have already
+/// been passed to `f` when an error is returned.
+pub fn for_each_sequence<F>(file: &str, mut f: F) -> Result<(), String>
+where
+    F: FnMut(&[u8], &[u8]),
+{
+    let handle = File::open(file).map_err(|e| format!("{}: {}", file, e))?;
+    let mmap = unsafe { memmap2::Mmap::map(&handle) }.map_err(|e| format!("{}: {}", file, e))?;
+    read_sequences(&mmap, &mut f).map_err(|e| format!("{}: {}", file, e))
+}
+
+struct TwoBit<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> TwoBit<'a> {
+    fn bytes(&self, pos: usize, len: usize) -> Result<&'a [u8], String> {
+        pos.checked_add(len)
+            .and_then(|end| self.data.get(pos..end))
+            .ok_or_else(|| TRUNCATED.to_string())
+    }
+
+    fn u32_at(&self, pos: usize) -> Result<u32, String> {
+        let bytes = self.bytes(pos, 4)?.try_into().unwrap();
+        Ok(if self.big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    }
+
+    fn u64_at(&self, pos: usize) -> Result<u64, String> {
+        let bytes = self.bytes(pos, 8)?.try_into().unwrap();
+        Ok(if self.big_endian { u64::from_be_bytes(bytes) } else { u64::from_le_bytes(bytes) })
+    }
+
+    /// Reads a block list (count, starts, sizes) as (start, end) intervals.
+    fn blocks(&self, pos: &mut usize) -> Result<Vec<(usize, usize)>, String> {
+        let count = self.u32_at(*pos)? as usize;
+        self.bytes(*pos + 4, 8 * count)?;
+        let starts = *pos + 4;
+        let sizes = starts + 4 * count;
+        *pos = sizes + 4 * count;
+        (0..count)
+            .map(|i| {
+                let start = self.u32_at(starts + 4 * i)? as usize;
+                Ok((start, start + self.u32_at(sizes + 4 * i)? as usize))
+            })
+            .collect()
+    }
+
+    fn decode(&self, mut pos: usize, seq: &mut Vec<u8>) -> Result<(), String> {
+        let dna_size = self.u32_at(pos)? as usize;
+        pos += 4;
+        let n_blocks = self.blocks(&mut pos)?;
+        let mask_blocks = self.blocks(&mut pos)?;
+        //Reserved word.
+        pos += 4;
+        let packed = self.bytes(pos, dna_size.div_ceil(4))?;
+        seq.clear();
+        seq.extend((0..dna_size).map(|i| BASES[(packed[i / 4] >> (6 - 2 * (i % 4))) as usize & 3]));
+        if n_blocks.iter().chain(mask_blocks.iter()).any(|x| x.1 > dna_size) {
+            return Err("N or mask block past the end of a sequence".to_string());
+        }
+        for (start, end) in mask_blocks {
+            seq[start..end].make_ascii_lowercase();
+        }
+        for (start, end) in n_blocks {
+            seq[start..end].fill(b'N');
+        }
+        Ok(())
+    }
+}
+
+fn read_sequences<F>(data: &[u8], f: &mut F) -> Result<(), String>
+where
+    F: FnMut(&[u8], &[u8]),
+{
+    let signature = data.get(..4).ok_or_else(|| TRUNCATED.to_string())?.try_into().unwrap();
+    let big_endian = if u32::from_le_bytes(signature) == SIGNATURE {
+        false
+    } else if u32::from_be_bytes(signature) == SIGNATURE {
+        true
+    } else {
+        return Err("not a 2bit file".to_string());
+    };
+    let twobit = TwoBit { data, big_endian };
+    //Version 1 has 64-bit offsets for files over 4 GB.
+    let version = twobit.u32_at(4)?;
+    if version > 1 {
+        return Err(format!("unsupported 2bit version {}", version));
+    }
+    let count = twobit.u32_at(8)?;
+    let mut pos = 16;
+    let mut seq = vec![];
+    for _ in 0..count {
+        let name_len = twobit.bytes(pos, 1)?[0] as usize;
+        let name = twobit.bytes(pos + 1, name_len)?;
+        pos += 1 + name_len;
+        let offset = if version == 1 {
+            pos += 8;
+            twobit.u64_at(pos - 8)? as usize
+        } else {
+            pos += 4;
+            twobit.u32_at(pos - 4)? as usize
+        };
+        twobit.decode(offset, &mut seq)?;
+        f(name, &seq);
+    }
+    Ok(())
+}
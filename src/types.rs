@@ -33,6 +33,7 @@ use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use smallvec::smallvec;
 use crate::params::*;
+use crate::tetra::TetraProfile;
 use partitions::*;
 use std::collections::{HashMap, HashSet};
 use std::hash::{BuildHasherDefault, Hash, Hasher};
@@ -48,15 +49,36 @@ pub const BYTE_TO_SEQ: [MarkerBits; 256] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
+/// Bytes with a base in BYTE_TO_SEQ (A, C, G, T and U in either case). Any other byte, such
+/// as N or another IUPAC code, breaks the k-mers around it.
+pub const BYTE_IS_NUC: [bool; 256] = {
+    let mut table = [false; 256];
+    let nucs = b"ACGTUacgtu";
+    let mut i = 0;
+    while i < nucs.len() {
+        table[nucs[i] as usize] = true;
+        i += 1;
+    }
+    table
+};
+
+/// Bases that are neither A, C, G, T/U nor N: the other IUPAC ambiguity codes, or junk.
+pub fn count_iupac_bases(seq: &[u8]) -> usize {
+    seq.iter()
+        .filter(|x| !BYTE_IS_NUC[**x as usize] && **x != b'N' && **x != b'n')
+        .count()
+}
 
 pub type GnPosition = u32;
 pub type ContigIndex = u32;
 //pub type KmerBits = u128;
 pub type MarkerBits = u64;
-pub type SeedBits = u32;
+pub type SeedBits = u64;
+pub type NarrowSeedBits = u32;
 pub type KmerToSketch = MMHashMap<MarkerBits, SmallVec<[u32; KMER_SK_SMALL_VEC_SIZE]>>;
 //pub type KmerToSketch = MMHashMap<MarkerBits, Vec<usize>>;
-pub type KmerSeeds = MMHashMap32<SeedBits, u64>; // Tagged index: bit 0 = single/multiple, bits 1-63 = data
+pub type NarrowKmerSeeds = MMHashMap32<NarrowSeedBits, u64>; // Tagged index: bit 0 = single/multiple, bits 1-63 = data
+pub type WideKmerSeeds = MMHashMap<SeedBits, u64>;
 pub type MultiPositionStorage = Vec<SmallVec<[SeedPosition; 3]>>; // For cases with multiple SeedPositions
 //pub type KmerSeeds = MMHashMap<SeedBits, SmallVec<[SeedPosition;SMALL_VEC_SIZE]>>;
 
@@ -121,6 +143,106 @@ pub fn mm_hash(bytes: &[u8]) -> usize {
     key
 }
 
+/// Seed -> tagged index table. Seeds of up to 32 bits (k <= 16 for DNA) are stored with
+/// u32 keys to save memory; longer seeds need u64 keys. Seeds are passed around as
+/// `SeedBits` either way.
+#[derive(Eq, PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub enum KmerSeeds {
+    Narrow(NarrowKmerSeeds),
+    Wide(WideKmerSeeds),
+}
+
+impl Default for KmerSeeds {
+    fn default() -> Self {
+        KmerSeeds::Narrow(NarrowKmerSeeds::default())
+    }
+}
+
+impl KmerSeeds {
+    /// Table for seeds that are `seed_bits` bits wide.
+    pub fn with_seed_bits(seed_bits: usize) -> KmerSeeds {
+        if seed_bits <= NarrowSeedBits::BITS as usize {
+            KmerSeeds::default()
+        } else {
+            KmerSeeds::Wide(WideKmerSeeds::default())
+        }
+    }
+
+    pub fn is_wide(&self) -> bool {
+        matches!(self, KmerSeeds::Wide(_))
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            KmerSeeds::Narrow(seeds) => seeds.len(),
+            KmerSeeds::Wide(seeds) => seeds.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn get(&self, seed: SeedBits) -> Option<u64> {
+        match self {
+            KmerSeeds::Narrow(seeds) => seeds.get(&(seed as NarrowSeedBits)).copied(),
+            KmerSeeds::Wide(seeds) => seeds.get(&seed).copied(),
+        }
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, seed: SeedBits) -> Option<&mut u64> {
+        match self {
+            KmerSeeds::Narrow(seeds) => seeds.get_mut(&(seed as NarrowSeedBits)),
+            KmerSeeds::Wide(seeds) => seeds.get_mut(&seed),
+        }
+    }
+
+    #[inline]
+    pub fn contains_key(&self, seed: SeedBits) -> bool {
+        match self {
+            KmerSeeds::Narrow(seeds) => seeds.contains_key(&(seed as NarrowSeedBits)),
+            KmerSeeds::Wide(seeds) => seeds.contains_key(&seed),
+        }
+    }
+
+    #[inline]
+    pub fn insert(&mut self, seed: SeedBits, tagged_index: u64) {
+        match self {
+            KmerSeeds::Narrow(seeds) => seeds.insert(seed as NarrowSeedBits, tagged_index),
+            KmerSeeds::Wide(seeds) => seeds.insert(seed, tagged_index),
+        };
+    }
+
+    pub fn iter(&self) -> KmerSeedsIter<'_> {
+        match self {
+            KmerSeeds::Narrow(seeds) => KmerSeedsIter::Narrow(seeds.iter()),
+            KmerSeeds::Wide(seeds) => KmerSeedsIter::Wide(seeds.iter()),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = SeedBits> + '_ {
+        self.iter().map(|(seed, _)| seed)
+    }
+}
+
+pub enum KmerSeedsIter<'a> {
+    Narrow(std::collections::hash_map::Iter<'a, NarrowSeedBits, u64>),
+    Wide(std::collections::hash_map::Iter<'a, SeedBits, u64>),
+}
+
+impl Iterator for KmerSeedsIter<'_> {
+    type Item = (SeedBits, u64);
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            KmerSeedsIter::Narrow(iter) => iter.next().map(|(seed, index)| (*seed as SeedBits, *index)),
+            KmerSeedsIter::Wide(iter) => iter.next().map(|(seed, index)| (*seed, *index)),
+        }
+    }
+}
+
 #[derive(Eq, PartialEq, Ord, PartialOrd, Default, Clone, Serialize, Deserialize, Debug)]
 pub struct SeedPosition{
     pub pos: GnPosition,
@@ -258,14 +380,106 @@ pub struct Sketch {
     pub total_sequence_length: usize,
     pub contig_lengths: Vec<GnPosition>,
 
-    //repetitive_kmers isn't being used right now, but we may
-    //make the repeat masking procedure smarter in the future. 
-    //A repetitive k-mer threshold is set in chaining as
-    //2500/c. 
-    //
-    //Also leaving this here so that we users can have 
-    //valid .sketch files across versions, although we may
-    //break that in the future. 
+    //Seeds with more positions than this were repeat-masked (see
+    //`repeat_seeds`); 0 without repeat masking. Chaining also skips
+    //seeds with more than 2500/c positions regardless.
+    pub repetitive_kmers: usize,
+    pub marker_seeds: MMHashSet<MarkerBits>,
+    pub marker_c: usize,
+    pub c: usize,
+    pub k: usize,
+    pub contig_order: usize,
+    pub individual_contig: bool,
+    pub amino_acid: bool,
+    /// Tetranucleotide Z-scores (see tetra.rs); empty for sketches from skani <= v0.3.
+    pub tetra_zscores: TetraProfile,
+    pub genome_stats: GenomeStats,
+    /// Spaced seed the seeds were made with; None for contiguous k-mers.
+    pub spaced_seed: Option<SpacedSeed>,
+    pub marker_k: usize,
+    /// Sample name from a `path<TAB>name` input list, shown instead of `file_name`.
+    pub display_name: Option<String>,
+    /// Bases excluded from seeding by soft-masking or a mask BED file. They do not count
+    /// towards aligned fractions.
+    pub masked_bases: usize,
+    /// Seeds masked as repeats; anchors are not made from them.
+    pub repeat_seeds: MMHashSet<SeedBits>,
+    /// Minimum contig length the sketch was made with.
+    pub min_contig_length: usize,
+}
+
+/// Length of the shortest sequence among the longest ones covering half of the total.
+pub fn n50(lengths: &[GnPosition]) -> GnPosition {
+    let mut lens = lengths.to_vec();
+    lens.sort_unstable_by(|a, b| b.cmp(a));
+    let half = lens.iter().map(|x| *x as u64).sum::<u64>() / 2;
+    let mut running = 0;
+    for len in lens {
+        running += len as u64;
+        if running >= half {
+            return len;
+        }
+    }
+    0
+}
+
+/// Assembly statistics over the sketched contigs, i.e. those passing the minimum length and
+/// header filters. Base composition is not known for sketches from skani <= v0.3.
+#[derive(Eq, PartialEq, Serialize, Deserialize, Clone, Copy, Default, Debug)]
+pub struct GenomeStats {
+    pub length: u64,
+    pub num_contigs: u32,
+    pub n50: GnPosition,
+    pub composition_known: bool,
+    pub acgt_bases: u64,
+    pub gc_bases: u64,
+    /// Contigs, and their bases, left out by the minimum length and header filters.
+    pub dropped_contigs: u32,
+    pub dropped_bases: u64,
+}
+
+impl GenomeStats {
+    /// `base_counts` are A, C, G, T counts.
+    pub fn from_contigs(contig_lengths: &[GnPosition], base_counts: Option<[u64; 4]>) -> GenomeStats {
+        let mut stats = GenomeStats {
+            length: contig_lengths.iter().map(|x| *x as u64).sum(),
+            num_contigs: contig_lengths.len() as u32,
+            n50: n50(contig_lengths),
+            ..Default::default()
+        };
+        if let Some(counts) = base_counts {
+            stats.composition_known = true;
+            stats.acgt_bases = counts.iter().sum();
+            stats.gc_bases = counts[1] + counts[2];
+        }
+        stats
+    }
+    /// GC fraction of the unambiguous bases.
+    pub fn gc(&self) -> Option<f64> {
+        if !self.composition_known || self.acgt_bases == 0 {
+            return None;
+        }
+        Some(self.gc_bases as f64 / self.acgt_bases as f64)
+    }
+    /// Bases other than A, C, G and T (N and other IUPAC codes).
+    pub fn ambiguous_bases(&self) -> Option<u64> {
+        if !self.composition_known {
+            return None;
+        }
+        Some(self.length.saturating_sub(self.acgt_bases))
+    }
+}
+
+/// On-disk layout of sketches written by skani v0.3, before per-genome profiles were
+/// stored. Only used as a fallback when reading old .sketch files and databases.
+#[derive(Deserialize)]
+pub struct SketchV03 {
+    pub file_name: String,
+    pub kmer_seeds_k: Option<NarrowKmerSeeds>,
+    pub multi_position_storage: MultiPositionStorage,
+    pub contigs: Vec<String>,
+    pub total_sequence_length: usize,
+    pub contig_lengths: Vec<GnPosition>,
     pub repetitive_kmers: usize,
     pub marker_seeds: MMHashSet<MarkerBits>,
     pub marker_c: usize,
@@ -276,11 +490,41 @@ pub struct Sketch {
     pub amino_acid: bool
 }
 
+impl From<SketchV03> for Sketch {
+    fn from(old: SketchV03) -> Sketch {
+        let genome_stats = GenomeStats::from_contigs(&old.contig_lengths, None);
+        Sketch {
+            file_name: old.file_name,
+            kmer_seeds_k: old.kmer_seeds_k.map(KmerSeeds::Narrow),
+            multi_position_storage: old.multi_position_storage,
+            contigs: old.contigs,
+            total_sequence_length: old.total_sequence_length,
+            contig_lengths: old.contig_lengths,
+            repetitive_kmers: old.repetitive_kmers,
+            marker_seeds: old.marker_seeds,
+            marker_c: old.marker_c,
+            c: old.c,
+            k: old.k,
+            contig_order: old.contig_order,
+            individual_contig: old.individual_contig,
+            amino_acid: old.amino_acid,
+            tetra_zscores: TetraProfile::default(),
+            genome_stats,
+            spaced_seed: None,
+            marker_k: if old.amino_acid { K_MARKER_AA } else { K_MARKER_DNA },
+            display_name: None,
+            masked_bases: 0,
+            repeat_seeds: MMHashSet::default(),
+            min_contig_length: MIN_LENGTH_CONTIG,
+        }
+    }
+}
+
 impl Sketch{
     /// Add a SeedPosition to the KmerSeeds using tagged index system
     pub fn add_seed_position(&mut self, seed: SeedBits, position: SeedPosition) {
         if let Some(kmer_seeds) = &mut self.kmer_seeds_k {
-            match kmer_seeds.get_mut(&seed) {
+            match kmer_seeds.get_mut(seed) {
                 Some(tagged_index) => {
                     // Check if it's currently a single position
                     if TaggedIndex::is_single(*tagged_index) {
@@ -306,7 +550,7 @@ impl Sketch{
     /// Get SeedPositions for a seed using Cow
     pub fn get_seed_positions(&self, seed: SeedBits) -> Cow<[SeedPosition]> {
         if let Some(kmer_seeds) = &self.kmer_seeds_k {
-            if let Some(&tagged_index) = kmer_seeds.get(&seed) {
+            if let Some(tagged_index) = kmer_seeds.get(seed) {
                 if TaggedIndex::is_single(tagged_index) {
                     let single_pos = TaggedIndex::get_single(tagged_index);
                     return Cow::Owned(vec![single_pos]);
@@ -335,7 +579,15 @@ impl Sketch{
             k : sketch.k,
             contig_order: sketch.contig_order,
             individual_contig: sketch.individual_contig,
-            amino_acid: sketch.amino_acid
+            amino_acid: sketch.amino_acid,
+            tetra_zscores: sketch.tetra_zscores.clone(),
+            genome_stats: sketch.genome_stats,
+            spaced_seed: sketch.spaced_seed.clone(),
+            marker_k: sketch.marker_k,
+            display_name: sketch.display_name.clone(),
+            masked_bases: sketch.masked_bases,
+            repeat_seeds: MMHashSet::default(),
+            min_contig_length: sketch.min_contig_length,
         }
     }
     
@@ -349,6 +601,39 @@ impl Sketch{
         new_sketch.amino_acid = amino_acid;
         new_sketch
     }
+
+    /// Name used in output: the sample name if one was given, otherwise the file name.
+    pub fn name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.file_name)
+    }
+
+    /// Length used as the aligned fraction denominator.
+    pub fn unmasked_length(&self) -> usize {
+        self.total_sequence_length - self.masked_bases
+    }
+
+    /// Number of bases covered by one seed.
+    pub fn seed_span(&self) -> usize {
+        self.spaced_seed.as_ref().map_or(self.k, |s| s.span())
+    }
+
+    /// Seeds and markers of two sketches can only be compared if these settings agree.
+    pub fn same_seed_layout(&self, other: &Sketch) -> bool {
+        self.k == other.k
+            && self.marker_k == other.marker_k
+            && self.spaced_seed == other.spaced_seed
+            && self.amino_acid == other.amino_acid
+    }
+
+    /// Human-readable form of the settings checked by `same_seed_layout`.
+    pub fn seed_layout(&self) -> String {
+        let seed = match &self.spaced_seed {
+            Some(spaced_seed) => format!("seed pattern {}", spaced_seed),
+            None => format!("k = {}", self.k),
+        };
+        let alphabet = if self.amino_acid { "amino acid" } else { "DNA" };
+        format!("{} {}, marker k = {}", alphabet, seed, self.marker_k)
+    }
 }
 
 impl PartialOrd for Sketch {
@@ -386,6 +671,14 @@ impl Default for Sketch {
             contig_order:0,
             individual_contig: false,
             amino_acid: false,
+            tetra_zscores: TetraProfile::default(),
+            genome_stats: GenomeStats::default(),
+            spaced_seed: None,
+            marker_k: K_MARKER_DNA,
+            display_name: None,
+            masked_bases: 0,
+            repeat_seeds: MMHashSet::default(),
+            min_contig_length: MIN_LENGTH_CONTIG,
         }
     }
 }
@@ -505,7 +798,7 @@ pub struct Anchor {
     pub reverse_match: bool,
 }
 
-#[derive(PartialEq, PartialOrd, Debug, Clone, Default)]
+#[derive(PartialEq, PartialOrd, Debug, Clone, Default, Serialize)]
 pub struct ChainInterval {
     pub score: f64,
     pub num_anchors: usize,
@@ -515,7 +808,11 @@ pub struct ChainInterval {
     pub query_contig: usize,
     pub chunk_id: usize,
     pub reverse_chain: bool,
-    pub overlap: u32
+    pub overlap: u32,
+    /// Consecutive anchor pairs in the chain whose query and reference offsets differ,
+    /// and the sum of those offset differences.
+    pub indel_events: u32,
+    pub indel_bases: u32,
 }
 impl ChainInterval {
     pub fn query_range_len(&self) -> GnPosition {
@@ -524,6 +821,23 @@ impl ChainInterval {
     pub fn ref_range_len(&self) -> GnPosition {
         self.interval_on_ref.1 - self.interval_on_ref.0
     }
+    /// Contig index and interval on the original reference genome, undoing the
+    /// query/reference switch that chaining may have made.
+    pub fn ref_coords(&self, switched: bool) -> (usize, (GnPosition, GnPosition)) {
+        if switched {
+            (self.query_contig, self.interval_on_query)
+        } else {
+            (self.ref_contig, self.interval_on_ref)
+        }
+    }
+    /// Same as `ref_coords`, but for the original query genome.
+    pub fn query_coords(&self, switched: bool) -> (usize, (GnPosition, GnPosition)) {
+        if switched {
+            (self.ref_contig, self.interval_on_ref)
+        } else {
+            (self.query_contig, self.interval_on_query)
+        }
+    }
 }
 
 impl Anchor {
@@ -563,6 +877,9 @@ pub struct AniEstResult{
     pub align_fraction_ref: f32,
     pub ref_file: String,
     pub query_file: String,
+    /// Input paths; differ from `ref_file`/`query_file` when sample names were given.
+    pub ref_path: String,
+    pub query_path: String,
     pub query_contig: String,
     pub ref_contig: String,
     pub ci_upper: f32,
@@ -579,4 +896,197 @@ pub struct AniEstResult{
     pub num_contigs_r: u32,
     pub avg_chain_int_len: u32,
     pub total_bases_covered: u32, 
+    pub synteny: SyntenyStats,
+    pub ddh: DdhEstimate,
+    pub indels: IndelStats,
+    pub snps: SnpEstimate,
+    pub tetra: Option<f32>,
+    pub ref_stats: GenomeStats,
+    pub query_stats: GenomeStats,
+    pub both_directions: BothDirections,
+}
+
+/// ANI/AF from chaining in one fixed direction. `ani` is NaN or negative if that
+/// direction gives no estimate or fails the AF cutoff.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DirectionalAni{
+    pub ani: f32,
+    pub align_fraction_ref: f32,
+    pub align_fraction_query: f32,
+    pub aligned_bases: u32,
+}
+
+impl DirectionalAni {
+    pub fn from_result(ani_res: &AniEstResult) -> DirectionalAni {
+        DirectionalAni {
+            ani: ani_res.ani,
+            align_fraction_ref: ani_res.align_fraction_ref,
+            align_fraction_query: ani_res.align_fraction_query,
+            aligned_bases: ani_res.total_bases_covered,
+        }
+    }
+    pub fn is_valid(&self) -> bool {
+        self.ani > 0.
+    }
+}
+
+/// `query_to_ref` maps the query onto the (indexed) reference, `ref_to_query` the other way
+/// around. `ani_symmetric` is the mean of the valid directions weighted by their aligned
+/// bases, so it does not depend on which genome skani would have indexed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BothDirections{
+    pub query_to_ref: DirectionalAni,
+    pub ref_to_query: DirectionalAni,
+    pub ani_symmetric: f32,
+}
+
+impl Default for BothDirections {
+    fn default() -> Self {
+        BothDirections {
+            query_to_ref: DirectionalAni::default(),
+            ref_to_query: DirectionalAni::default(),
+            ani_symmetric: f32::NAN,
+        }
+    }
+}
+
+impl BothDirections {
+    pub fn new(query_to_ref: DirectionalAni, ref_to_query: DirectionalAni) -> BothDirections {
+        let mut weighted = 0.;
+        let mut total = 0.;
+        for dir in [&query_to_ref, &ref_to_query] {
+            if dir.is_valid() {
+                let weight = u32::max(dir.aligned_bases, 1) as f64;
+                weighted += dir.ani as f64 * weight;
+                total += weight;
+            }
+        }
+        let ani_symmetric = if total > 0. { (weighted / total) as f32 } else { f32::NAN };
+        BothDirections {
+            query_to_ref,
+            ref_to_query,
+            ani_symmetric,
+        }
+    }
+}
+
+/// Expected number of SNPs over the aligned bases and the Jukes-Cantor corrected
+/// substitutions per site.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct SnpEstimate{
+    pub snps: f64,
+    pub snps_lower: f64,
+    pub snps_upper: f64,
+    pub jc_distance: f64,
+}
+
+/// Net indels seen between consecutive anchors of the final chains. Several indels between
+/// two anchors count as one event of their net length, so this is a lower bound.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct IndelStats{
+    pub indel_events: u32,
+    pub indel_bases: u32,
+    pub aligned_bases: u32,
+}
+
+impl IndelStats {
+    pub fn rate_per_kb(&self) -> f64 {
+        if self.aligned_bases == 0 {
+            return 0.;
+        }
+        self.indel_events as f64 / self.aligned_bases as f64 * 1000.
+    }
+    /// Gap-aware identity: indel bases are counted as extra alignment columns, as in
+    /// BLAST-style identity, instead of being ignored.
+    pub fn adjusted_identity(&self, ani: f64) -> f64 {
+        if self.aligned_bases == 0 {
+            return ani;
+        }
+        ani * self.aligned_bases as f64 / (self.aligned_bases + self.indel_bases) as f64
+    }
+}
+
+/// GGDC-style dDDH (%) for formulas d0, d4 and d6; see `ddh.rs`.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DdhEstimate{
+    pub d0: f32,
+    pub d4: f32,
+    pub d6: f32,
+    pub d4_ci_lower: f32,
+    pub d4_ci_upper: f32,
+}
+
+/// Rearrangement summary between two genomes, from collinear blocks of chains.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct SyntenyStats{
+    pub collinear_blocks: u32,
+    pub inversions: u32,
+    pub breakpoints: u32,
+    pub block_n50: u32,
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct ProfileWindow{
+    pub ref_contig: usize,
+    pub start: GnPosition,
+    pub end: GnPosition,
+    pub ani: f32,
+    pub coverage: f32,
+    pub num_seeds: usize,
+}
+
+/// Marker k-mer screening counts for a single pair, as used by `screen::check_markers_quickly`.
+#[derive(Default, Clone, Debug, Serialize)]
+pub struct MarkerScreenStats{
+    pub ref_markers: usize,
+    pub query_markers: usize,
+    pub shared_markers: usize,
+    pub min_markers: usize,
+    pub screen_val: f64,
+    pub required_shared: usize,
+    pub marker_identity: f64,
+    pub passed: bool,
+}
+
+#[derive(Default, Clone, Debug, Serialize)]
+pub struct ChunkDiagnostics{
+    pub chunk_id: usize,
+    pub num_anchors: usize,
+    pub num_seeds: usize,
+    pub chains: Vec<ChainInterval>,
+}
+
+#[derive(Default, Clone, Debug, Serialize)]
+pub struct FragmentDiagnostics{
+    pub chunk_id: usize,
+    pub ani: f64,
+    pub weight: usize,
+    pub total_anchors: usize,
+    /// Outside the trim quantiles, so left out of the ANI average.
+    pub trimmed: bool,
+}
+
+/// Structured dump of the intermediate steps of `chain::chain_seeds` for a single pair.
+/// Output by `skani explain`.
+#[derive(Default, Clone, Debug, Serialize)]
+pub struct AniDiagnostics{
+    pub version: String,
+    pub ref_file: String,
+    pub query_file: String,
+    pub screen: MarkerScreenStats,
+    pub switched: bool,
+    pub chunks: Vec<ChunkDiagnostics>,
+    /// Sorted by ANI, the order in which they are trimmed.
+    pub fragments: Vec<FragmentDiagnostics>,
+    pub trim_quantiles: (f64, f64),
+    pub trim_bounds: (f64, f64),
+    pub raw_ani: f32,
+    pub learned_ani: bool,
+    pub ani: f32,
+    pub align_fraction_ref: f32,
+    pub align_fraction_query: f32,
+    pub ci_lower: f32,
+    pub ci_upper: f32,
+    pub std: f32,
+    pub total_bases_covered: u32,
 }
@@ -32,6 +32,25 @@ fn run_skani<'a>(args: &'a [&str], stderr: bool) -> String{
     return out_line
 }
 
+fn run_skani_stdin(args: &[&str], input: Vec<u8>) -> std::process::Output{
+    let mut child = Command::cargo_bin("skani").unwrap()
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = std::thread::spawn(move || {
+        use std::io::Write;
+        //skani may exit without reading stdin, e.g. on argument errors.
+        let _ = stdin.write_all(&input);
+    });
+    let out = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    out
+}
+
 fn get_result_from_out(tsv_res : &str) -> Vec<AniResult>{
     let lines : Vec<&str> = tsv_res.lines().collect();
     let mut ret = vec![];
@@ -161,3 +180,878 @@ fn fast_show_degenerate_inputs(){
     assert!(results.len() == 0);
 
 }
+
+#[test]
+fn fast_test_profile(){
+    let out_line = run_skani(&["profile", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "-w", "20000"], false);
+    let lines : Vec<&str> = out_line.lines().collect();
+    assert!(lines[0].starts_with("Ref_contig\tStart\tEnd\tQuery_file\tANI"));
+    assert!(lines.len() > 100);
+    let mut covered_windows = 0;
+    for line in &lines[1..]{
+        let line = line.split('\t').collect::<Vec<&str>>();
+        assert!(line.len() == 7);
+        let start = line[1].parse::<u32>().unwrap();
+        let end = line[2].parse::<u32>().unwrap();
+        assert!(end > start && end - start <= 20000);
+        if line[4] != "NA"{
+            assert!(line[4].parse::<f32>().unwrap() == 100.0);
+            covered_windows += 1;
+        }
+    }
+    assert!(covered_windows as f64 > 0.9 * (lines.len() - 1) as f64);
+
+    //Overlapping windows; same reference coordinates, roughly twice as many windows.
+    let out_line_step = run_skani(&["profile", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "-w", "20000", "--step", "10000"], false);
+    let num_lines_step = out_line_step.lines().count();
+    assert!(num_lines_step > 2 * lines.len() - 10);
+    assert!(num_lines_step < 2 * lines.len() + 10);
+}
+
+#[test]
+fn fast_test_explain(){
+    let out_line = run_skani(&["explain", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz"], false);
+    let json: serde_json::Value = serde_json::from_str(&out_line).unwrap();
+    assert!(json["screen"]["passed"].as_bool().unwrap());
+    assert!(json["screen"]["shared_markers"] == json["screen"]["min_markers"]);
+    assert!(json["raw_ani"].as_f64().unwrap() == 1.0);
+    assert!(!json["chunks"].as_array().unwrap().is_empty());
+    assert!(!json["fragments"].as_array().unwrap().is_empty());
+
+    let out_line = run_skani(&["explain", "./test_files/o157_plasmid.fasta", "./test_files/e.coli-W.fasta.gz", "--no-learned-ani"], false);
+    let json: serde_json::Value = serde_json::from_str(&out_line).unwrap();
+    assert!(!json["screen"]["passed"].as_bool().unwrap());
+    assert!(!json["learned_ani"].as_bool().unwrap());
+
+    //Fragments are sorted by ANI and the tails are flagged as trimmed.
+    let out_line = run_skani(&["explain", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "--robust"], false);
+    let json: serde_json::Value = serde_json::from_str(&out_line).unwrap();
+    let fragments = json["fragments"].as_array().unwrap();
+    let anis = fragments.iter().map(|x| x["ani"].as_f64().unwrap()).collect::<Vec<f64>>();
+    assert!(anis.windows(2).all(|x| x[0] <= x[1]));
+    let trimmed = fragments.iter().map(|x| x["trimmed"].as_bool().unwrap()).collect::<Vec<bool>>();
+    assert!(trimmed[0] && trimmed[trimmed.len() - 1]);
+    assert!(trimmed.iter().any(|x| !x));
+    let lower = json["trim_bounds"][0].as_f64().unwrap();
+    let upper = json["trim_bounds"][1].as_f64().unwrap();
+    assert!(anis.iter().zip(trimmed.iter()).all(|(ani, t)| *t || (lower <= *ani && *ani <= upper)));
+}
+
+#[test]
+fn fast_test_dotplot(){
+    let out_line = run_skani(&["dotplot", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "--sort"], false);
+    assert!(out_line.starts_with("<svg"));
+    assert!(out_line.trim_end().ends_with("</svg>"));
+    //Self comparison: many forward, high identity chains and no reverse chains.
+    assert!(out_line.matches("<line").count() > 100);
+    assert!(out_line.contains("stroke=\"#08306b\""));
+    assert!(!out_line.contains("stroke=\"#a50f15\""));
+}
+
+#[test]
+fn fast_test_triangle_heatmap(){
+    let svg_file = std::env::temp_dir().join("skani_test_heatmap.svg");
+    let svg_file = svg_file.to_str().unwrap();
+    let out_line = run_skani(&["triangle", "-i", "-E", "./test_files/viruses.fna", "--heatmap", svg_file, "--heatmap-labels", "--heatmap-af"], false);
+    //Heatmap does not change the normal output.
+    let results = get_result_from_out(&out_line);
+    assert!(results.len() == 3);
+    let svg = std::fs::read_to_string(svg_file).unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains("OR649331.1"));
+    assert!(svg.contains("AF of row (upper)"));
+    std::fs::remove_file(svg_file).unwrap();
+}
+
+#[test]
+fn fast_test_synteny(){
+    let out_line = run_skani(&["dist", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "--synteny"], false);
+    let mut lines = out_line.lines();
+    let header = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    assert!(header.len() == 11);
+    assert!(header[7] == "Collinear_blocks");
+    assert!(header[10] == "Block_N50");
+    let res = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    //Self comparison: no rearrangements and at least one block per contig.
+    assert!(res[7].parse::<u32>().unwrap() >= 1);
+    assert!(res[8] == "0");
+    assert!(res[9] == "0");
+    assert!(res[10].parse::<u32>().unwrap() > 100000);
+}
+
+#[test]
+fn fast_test_ddh(){
+    let out_line = run_skani(&["dist", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "--ddh", "--synteny"], false);
+    let mut lines = out_line.lines();
+    let header = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    assert!(header.len() == 16);
+    assert!(header[11] == "dDDH_d0");
+    let res = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    for col in 11..16{
+        assert!(res[col].parse::<f32>().unwrap() > 95.);
+    }
+    assert!(res[13].parse::<f32>().unwrap() <= res[12].parse::<f32>().unwrap());
+}
+
+#[test]
+fn fast_test_indels(){
+    //Insert a base every 1000 bp into the first virus; substitution ANI should stay high
+    //while the indel rate goes up.
+    let fasta = std::fs::read_to_string("./test_files/viruses.fna").unwrap();
+    let seq = fasta.split('>').nth(1).unwrap().lines().skip(1).collect::<String>();
+    let mut indel_seq = String::new();
+    for (i, c) in seq.chars().enumerate(){
+        if i % 1000 == 999{
+            indel_seq.push('A');
+        }
+        indel_seq.push(c);
+    }
+    let ref_file = std::env::temp_dir().join("skani_test_indel_ref.fa");
+    let query_file = std::env::temp_dir().join("skani_test_indel_query.fa");
+    std::fs::write(&ref_file, format!(">ref\n{}\n", seq)).unwrap();
+    std::fs::write(&query_file, format!(">query\n{}\n", indel_seq)).unwrap();
+    let out_line = run_skani(&["dist", ref_file.to_str().unwrap(), query_file.to_str().unwrap(), "--indels", "-c", "30"], false);
+    let mut lines = out_line.lines();
+    let header = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    assert!(header[7] == "Indels_per_kb");
+    let res = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    let ani = res[2].parse::<f32>().unwrap();
+    let rate = res[7].parse::<f32>().unwrap();
+    let adj_ani = res[8].parse::<f32>().unwrap();
+    assert!(rate > 0.5 && rate < 1.5);
+    assert!(adj_ani < ani);
+
+    let out_line = run_skani(&["dist", ref_file.to_str().unwrap(), ref_file.to_str().unwrap(), "--indels", "-c", "30"], false);
+    let res = out_line.lines().nth(1).unwrap().split('\t').collect::<Vec<&str>>();
+    assert!(res[7].parse::<f32>().unwrap() == 0.);
+    std::fs::remove_file(ref_file).unwrap();
+    std::fs::remove_file(query_file).unwrap();
+}
+
+#[test]
+fn fast_test_snps(){
+    //Substitute every 1000th base of the plasmid: ~92 SNPs.
+    let fasta = std::fs::read_to_string("./test_files/o157_plasmid.fasta").unwrap();
+    let seq = fasta.lines().skip(1).collect::<String>();
+    let snp_seq = seq.chars().enumerate().map(|(i, c)| {
+        if i % 1000 == 999{
+            match c { 'A' => 'C', 'C' => 'G', 'G' => 'T', _ => 'A' }
+        }
+        else{
+            c
+        }
+    }).collect::<String>();
+    let ref_file = std::env::temp_dir().join("skani_test_snp_ref.fa");
+    let query_file = std::env::temp_dir().join("skani_test_snp_query.fa");
+    std::fs::write(&ref_file, format!(">ref\n{}\n", seq)).unwrap();
+    std::fs::write(&query_file, format!(">query\n{}\n", snp_seq)).unwrap();
+    for ci in [false, true]{
+        let mut args = vec!["dist", ref_file.to_str().unwrap(), query_file.to_str().unwrap(), "--snps", "-c", "30"];
+        if ci{
+            args.push("--ci");
+        }
+        let out_line = run_skani(&args, false);
+        let mut lines = out_line.lines();
+        let header = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+        let res = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+        let col = header.iter().position(|x| *x == "Est_SNPs").unwrap();
+        let snps = res[col].parse::<f64>().unwrap();
+        let lower = res[col + 1].parse::<f64>().unwrap();
+        let upper = res[col + 2].parse::<f64>().unwrap();
+        let jc = res[col + 3].parse::<f64>().unwrap();
+        assert!(snps > 40. && snps < 200.);
+        assert!(lower <= snps && snps <= upper);
+        assert!(jc > 0. && jc < 0.01);
+    }
+    std::fs::remove_file(ref_file).unwrap();
+    std::fs::remove_file(query_file).unwrap();
+}
+
+#[test]
+fn fast_test_ci_methods(){
+    let ci_cols = |args: &[&str]| {
+        let out_line = run_skani(args, false);
+        let mut lines = out_line.lines();
+        let header = lines.next().unwrap().split('\t').map(|x| x.to_string()).collect::<Vec<String>>();
+        let res = lines.next().unwrap().split('\t').map(|x| x.to_string()).collect::<Vec<String>>();
+        (header[7].clone(), res[7].parse::<f32>().unwrap(), res[8].parse::<f32>().unwrap(), res[2].parse::<f32>().unwrap())
+    };
+    let (header, lower, upper, ani) = ci_cols(&["dist", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "--ci"]);
+    assert!(header == "ANI_5_percentile[bootstrap,n=100]");
+    assert!(lower <= ani && ani <= upper);
+
+    let (header, a_lower, a_upper, _) = ci_cols(&["dist", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "--ci", "--ci-method", "analytic"]);
+    assert!(header == "ANI_5_percentile[analytic]");
+    assert!(a_lower <= a_upper);
+    assert!((a_lower - lower).abs() < 0.1);
+
+    let (header, _, _, _) = ci_cols(&["dist", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "--ci", "--ci-replicates", "500", "--ci-seed", "3"]);
+    assert!(header == "ANI_5_percentile[bootstrap,n=500,seed=3]");
+}
+
+#[test]
+fn fast_test_tetra(){
+    let out_line = run_skani(&["dist", "-q", "./test_files/viruses.fna", "-r", "./test_files/viruses.fna", "--qi", "--ri", "--tetra"], false);
+    let mut lines = out_line.lines();
+    let header = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    assert!(header[7] == "TETRA");
+    for line in lines{
+        let res = line.split('\t').collect::<Vec<&str>>();
+        let tetra = res[7].parse::<f32>().unwrap();
+        if res[5] == res[6]{
+            assert!(tetra > 0.9999);
+        }
+        else if res[5].contains("OR649331.1") && res[6].contains("NC_045512.2"){
+            assert!(tetra > 0.99);
+        }
+    }
+
+    //Pairs failing the screen are still reported with a TETRA value.
+    let out_line = run_skani(&["dist", "./test_files/e.coli-W.fasta.gz", "./test_files/viruses.fna", "--tetra"], false);
+    let res = out_line.lines().nth(1).unwrap().split('\t').collect::<Vec<&str>>();
+    assert!(res[2] == "NaN");
+    assert!(res[7].parse::<f32>().unwrap() < 0.9);
+    let out_line = run_skani(&["dist", "./test_files/e.coli-W.fasta.gz", "./test_files/viruses.fna"], false);
+    assert!(out_line.lines().count() == 1);
+}
+
+#[test]
+fn fast_test_genome_stats(){
+    let out_line = run_skani(&["dist", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "--genome-stats"], false);
+    let mut lines = out_line.lines();
+    let header = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    assert!(header[7] == "Ref_length");
+    assert!(header[13] == "GC_difference");
+    let res = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    assert!(res[7] == "4897452");
+    assert!(res[9] == "4897452");
+    let gc = res[11].parse::<f32>().unwrap();
+    assert!(gc > 50. && gc < 51.);
+    assert!(res[13] == "0.00");
+
+    let out_line = run_skani(&["inspect", "./test_files/viruses.fna", "./test_files/all_ns.fa", "-i"], false);
+    let mut lines = out_line.lines();
+    assert!(lines.next().unwrap() == "File\tName\tNum_contigs\tLength\tN50\tGC\tAmbiguous_bases\tMasked_bases");
+    let rows = lines.map(|x| x.split('\t').map(|y| y.to_string()).collect::<Vec<String>>()).collect::<Vec<Vec<String>>>();
+    assert!(rows.len() == 4);
+    let all_ns = rows.iter().find(|x| x[0].contains("all_ns")).unwrap();
+    assert!(all_ns[5] == "NA");
+    assert!(all_ns[3] == all_ns[6]);
+
+    //Statistics from sketch databases match the fasta.
+    let db = std::env::temp_dir().join("skani_test_genome_stats_db");
+    let db = db.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(db);
+    run_skani(&["sketch", "./test_files/viruses.fna", "-o", db], false);
+    let from_fasta = run_skani(&["inspect", "./test_files/viruses.fna"], false);
+    let from_db = run_skani(&["inspect", db], false);
+    assert!(from_fasta == from_db);
+    std::fs::remove_dir_all(db).unwrap();
+}
+
+#[test]
+fn fast_test_both_directions(){
+    let out_line = run_skani(&["dist", "-q", "./test_files/viruses.fna", "-r", "./test_files/viruses.fna", "--qi", "--ri", "--both-directions"], false);
+    let mut lines = out_line.lines();
+    let header = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    assert!(header[7] == "ANI_query_to_ref");
+    assert!(header[10] == "ANI_ref_to_query");
+    assert!(header[13] == "ANI_symmetric");
+    let mut symmetric = HashMap::new();
+    for line in lines{
+        let res = line.split('\t').collect::<Vec<&str>>();
+        //The main ANI is one of the two directions.
+        assert!(res[2] == res[7] || res[2] == res[10]);
+        let mut pair = [res[5], res[6]];
+        pair.sort();
+        symmetric.entry(pair.join("\t")).or_insert(vec![]).push(res[13].to_string());
+    }
+    for values in symmetric.values(){
+        assert!(values.iter().all(|x| *x == values[0]));
+    }
+}
+
+#[test]
+fn fast_test_markers_only(){
+    let out_line = run_skani(&["dist", "-q", "./test_files/viruses.fna", "-r", "./test_files/viruses.fna", "--qi", "--ri", "--markers-only", "--ci"], false);
+    let mut lines = out_line.lines();
+    let header = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    assert!(header[2] == "ANI_markers");
+    assert!(header[7] == "ANI_markers_5_percentile[wilson]");
+    let mut num_pairs = 0;
+    for line in lines{
+        let res = line.split('\t').collect::<Vec<&str>>();
+        let ani = res[2].parse::<f32>().unwrap();
+        let lower = res[7].parse::<f32>().unwrap();
+        let upper = res[8].parse::<f32>().unwrap();
+        assert!(lower <= ani && ani <= upper);
+        if res[5].contains("OR649331.1") && res[6].contains("NC_045512.2"){
+            assert!(ani > 99.0 && ani < 100.0);
+        }
+        num_pairs += 1;
+    }
+    assert!(num_pairs == 9);
+
+    let out_line = run_skani(&["dist", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "--markers-only", "--synteny"], false);
+    assert!(out_line.is_empty());
+}
+
+#[test]
+fn fast_test_spaced_seeds(){
+    let out_line = run_skani(&["dist", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "--genus"], false);
+    let res = get_result_from_out(&out_line);
+    assert!(res.len() == 1);
+    assert!(res[0].ani == 100.0);
+    assert!(res[0].align_fraction_query > 99.0);
+
+    //Sketches keep the seed pattern, so queries are seeded the same way.
+    let db = std::env::temp_dir().join("skani_test_spaced_seeds_db");
+    let db = db.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(db);
+    run_skani(&["sketch", "./test_files/viruses.fna", "-o", db, "--genus", "-m", "200"], false);
+    let from_db = run_skani(&["search", "-d", db, "./test_files/viruses.fna", "--qi"], false);
+    let from_fasta = run_skani(&["dist", "-r", "./test_files/viruses.fna", "-q", "./test_files/viruses.fna", "--qi", "--genus", "-m", "200", "-s", "80"], false);
+    let mut from_db_lines = from_db.lines().skip(1).collect::<Vec<&str>>();
+    let mut from_fasta_lines = from_fasta.lines().skip(1).collect::<Vec<&str>>();
+    from_db_lines.sort();
+    from_fasta_lines.sort();
+    assert!(!from_db_lines.is_empty());
+    assert!(from_db_lines == from_fasta_lines);
+    std::fs::remove_dir_all(db).unwrap();
+
+    let out_line = run_skani(&["dist", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "--seed-pattern", "0110"], false);
+    assert!(out_line.is_empty());
+
+    let err_line = run_skani(&["dist", "./test_files/o157_plasmid.fasta", "./test_files/o157_plasmid.fasta", "--seed-pattern", "1101", "-k", "15"], true);
+    assert!(err_line.contains("-k value is set but --seed-pattern is also set"));
+}
+
+#[test]
+fn fast_test_wide_seeds(){
+    let out_line = run_skani(&["dist", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "-k", "24", "--marker-k", "31"], false);
+    let res = get_result_from_out(&out_line);
+    assert!(res.len() == 1);
+    assert!(res[0].ani == 100.0);
+
+    //k can not be longer than the marker k-mers.
+    let out_line = run_skani(&["dist", "./test_files/e.coli-W.fasta.gz", "./test_files/e.coli-W.fasta.gz", "-k", "24"], false);
+    assert!(out_line.is_empty());
+    let err_line = run_skani(&["dist", "./test_files/o157_plasmid.fasta", "./test_files/o157_plasmid.fasta", "--marker-k", "40"], true);
+    assert!(err_line.contains("--marker-k must be an integer from 1 to 32"));
+    assert!(!err_line.contains("panicked"));
+
+    //Sketches with different seeds are not compared.
+    let dir = std::env::temp_dir().join("skani_test_wide_seeds");
+    let dir = dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+    let narrow = format!("{}/narrow", dir);
+    let wide = format!("{}/wide", dir);
+    run_skani(&["sketch", "./test_files/o157_plasmid.fasta", "-o", &narrow, "--separate-sketches"], false);
+    run_skani(&["sketch", "./test_files/o157_plasmid.fasta", "-o", &wide, "--separate-sketches", "-k", "20", "--marker-k", "25"], false);
+    let narrow_sketch = format!("{}/o157_plasmid.fasta.sketch", narrow);
+    let wide_sketch = format!("{}/o157_plasmid.fasta.sketch", wide);
+    let out_line = run_skani(&["dist", "-r", &wide_sketch, "-q", "./test_files/o157_plasmid.fasta"], false);
+    let res = get_result_from_out(&out_line);
+    assert!(res.len() == 1);
+    assert!(res[0].ani == 100.0);
+    let err_line = run_skani(&["triangle", &narrow_sketch, &wide_sketch], true);
+    assert!(err_line.contains("different seeds"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fast_test_genome_groups(){
+    let dir = std::env::temp_dir().join("skani_test_genome_groups");
+    let dir = dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).unwrap();
+    let group_file = format!("{}/groups.tsv", dir);
+    std::fs::write(&group_file, "w_with_plasmid\t./test_files/e.coli-W.fasta.gz,./test_files/o157_plasmid.fasta\n").unwrap();
+
+    let out_line = run_skani(&["dist", "-r", "./test_files/e.coli-W.fasta.gz", "--qg", &group_file], false);
+    let res = get_result_from_out(&out_line);
+    assert!(res.len() == 1);
+    assert!(res[0].query_file == "w_with_plasmid");
+    assert!(res[0].ani == 100.0);
+    assert!(res[0].align_fraction_ref == 100.0);
+    assert!(res[0].align_fraction_query < 100.0);
+
+    let sketch_dir = format!("{}/sketches", dir);
+    run_skani(&["sketch", "--groups", &group_file, "-o", &sketch_dir, "--separate-sketches"], false);
+    let group_sketch = format!("{}/w_with_plasmid.sketch", sketch_dir);
+    let out_line = run_skani(&["dist", "-q", &group_sketch, "-r", "./test_files/o157_plasmid.fasta"], false);
+    let res = get_result_from_out(&out_line);
+    assert!(res.len() == 1);
+    assert!(res[0].query_file == "w_with_plasmid");
+    assert!(res[0].align_fraction_ref > 99.0);
+
+    //Groups are whole genomes, so can't be split by contig.
+    let err_line = run_skani(&["triangle", "--groups", &group_file, "-i"], true);
+    assert!(err_line.contains("individual contig mode"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fast_test_sample_names(){
+    let dir = std::env::temp_dir().join("skani_test_sample_names");
+    let dir = dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).unwrap();
+    let list_file = format!("{}/names.tsv", dir);
+    std::fs::write(&list_file, "./test_files/e.coli-W.fasta.gz\tEcW\n./test_files/o157_plasmid.fasta\n").unwrap();
+
+    let out_line = run_skani(&["dist", "--rl", &list_file, "-q", "./test_files/e.coli-W.fasta.gz", "--detailed"], false);
+    let res = get_result_from_out(&out_line);
+    assert!(res.len() == 1);
+    assert!(res[0].ref_file == "EcW");
+    //Original paths are kept in the detailed output.
+    let header = out_line.lines().next().unwrap().split('\t').collect::<Vec<&str>>();
+    let row = out_line.lines().nth(1).unwrap().split('\t').collect::<Vec<&str>>();
+    let path_col = header.iter().position(|x| *x == "Ref_path").unwrap();
+    assert!(row[path_col] == "./test_files/e.coli-W.fasta.gz");
+
+    //Names are stored in the database; unnamed inputs keep their path.
+    let sketch_dir = format!("{}/db", dir);
+    run_skani(&["sketch", "-l", &list_file, "-o", &sketch_dir], false);
+    let out_line = run_skani(&["search", "-d", &sketch_dir, "./test_files/e.coli-W.fasta.gz"], false);
+    let res = get_result_from_out(&out_line);
+    assert!(res.len() == 1);
+    assert!(res[0].ref_file == "EcW");
+
+    let out_line = run_skani(&["triangle", "-l", &list_file], false);
+    assert!(out_line.contains("EcW"));
+    assert!(out_line.contains("./test_files/o157_plasmid.fasta"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fast_test_genbank_input(){
+    let dir = std::env::temp_dir().join("skani_test_genbank_input");
+    let dir = dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).unwrap();
+    let fasta = std::fs::read_to_string("./test_files/o157_plasmid.fasta").unwrap();
+    let seq = fasta.lines().skip(1).collect::<String>().to_lowercase();
+
+    //Annotate forward stop-to-stop stretches of at least 300 bp in all three frames.
+    let mut features = String::new();
+    for frame in 0..3{
+        let mut start = frame;
+        let mut i = frame;
+        while i + 3 <= seq.len(){
+            let codon = &seq[i..i + 3];
+            if codon == "taa" || codon == "tag" || codon == "tga"{
+                if i + 3 - start >= 300{
+                    features.push_str(&format!("     CDS             {}..{}\n", start + 1, i + 3));
+                }
+                start = i + 3;
+            }
+            i += 3;
+        }
+    }
+    let mut origin = String::new();
+    let mut embl_seq = String::new();
+    for (i, chunk) in seq.as_bytes().chunks(60).enumerate(){
+        let chunk = std::str::from_utf8(chunk).unwrap();
+        origin.push_str(&format!("{:>9} {}\n", i * 60 + 1, chunk));
+        embl_seq.push_str(&format!("     {}\n", chunk));
+    }
+    let gbk = format!("{}/plasmid.gbk", dir);
+    std::fs::write(&gbk, format!("LOCUS       plasmid {} bp DNA\nACCESSION   TEST0001\nVERSION     TEST0001.1\nFEATURES             Location/Qualifiers\n{}ORIGIN\n{}//\n", seq.len(), features, origin)).unwrap();
+    let embl = format!("{}/plasmid.embl", dir);
+    std::fs::write(&embl, format!("ID   plasmid; SV 1; circular; genomic DNA; STD; PRO; {} BP.\nAC   TEST0002;\n{}SQ   Sequence;\n{}//\n", seq.len(), features.replace("     CDS", "FT   CDS"), embl_seq)).unwrap();
+
+    for (file, name) in [(&gbk, "TEST0001.1"), (&embl, "TEST0002")]{
+        let out_line = run_skani(&["dist", file, "./test_files/o157_plasmid.fasta"], false);
+        let res = get_result_from_out(&out_line);
+        assert!(res.len() == 1);
+        assert!(res[0].ani == 100.);
+        assert!(out_line.lines().nth(1).unwrap().ends_with(&format!("\t{}", name)));
+    }
+
+    let out_line = run_skani(&["dist", "-a", "--annotated-cds", &gbk, &embl], false);
+    let res = get_result_from_out(&out_line);
+    assert!(res.len() == 1);
+    assert!(res[0].ani > 99.);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fast_test_gfa_input(){
+    let dir = std::env::temp_dir().join("skani_test_gfa_input");
+    let dir = dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).unwrap();
+    let fasta = std::fs::read_to_string("./test_files/o157_plasmid.fasta").unwrap();
+    let seq = fasta.lines().skip(1).collect::<String>();
+
+    //Alternate 3000 bp and 300 bp segments.
+    let mut gfa = "H\tVN:Z:1.0\n".to_string();
+    let mut start = 0;
+    let mut n = 0;
+    while start < seq.len(){
+        let end = usize::min(start + if n % 2 == 0 { 3000 } else { 300 }, seq.len());
+        gfa.push_str(&format!("S\tseg{}\t{}\n", n, &seq[start..end]));
+        if n > 0{
+            gfa.push_str(&format!("L\tseg{}\t+\tseg{}\t+\t0M\n", n - 1, n));
+        }
+        start = end;
+        n += 1;
+    }
+    let gfa_file = format!("{}/graph.gfa", dir);
+    std::fs::write(&gfa_file, gfa).unwrap();
+
+    let out_line = run_skani(&["dist", &gfa_file, "./test_files/o157_plasmid.fasta"], false);
+    let res = get_result_from_out(&out_line);
+    assert!(res.len() == 1);
+    assert!(res[0].ani == 100.);
+    assert!(out_line.lines().nth(1).unwrap().ends_with("\tseg0"));
+    let af_default = res[0].align_fraction_ref;
+
+    //Short segments are only sketched with a lower threshold.
+    let out_line = run_skani(&["dist", &gfa_file, "./test_files/o157_plasmid.fasta", "--min-segment-length", "100"], false);
+    let res = get_result_from_out(&out_line);
+    assert!(res[0].ani == 100.);
+    assert!(res[0].align_fraction_ref > af_default);
+
+    let out_line = run_skani(&["dist", &gfa_file, "./test_files/o157_plasmid.fasta", "--qi", "--min-segment-length", "100"], false);
+    let res = get_result_from_out(&out_line);
+    assert!(res.len() > n / 3);
+    assert!(res.iter().all(|x| x.ani == 100.));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+/// Minimal 2bit (version 0, little endian) writer; lowercase runs become mask blocks.
+fn write_twobit(file: &str, name: &str, seq: &str){
+    let blocks = |f: &dyn Fn(u8) -> bool| {
+        let mut starts = vec![];
+        let mut sizes = vec![];
+        let bytes = seq.as_bytes();
+        let mut i = 0;
+        while i < bytes.len(){
+            if f(bytes[i]){
+                let start = i;
+                while i < bytes.len() && f(bytes[i]){
+                    i += 1;
+                }
+                starts.push(start as u32);
+                sizes.push((i - start) as u32);
+            }
+            i += 1;
+        }
+        let mut out = vec![starts.len() as u32];
+        out.extend(starts);
+        out.extend(sizes);
+        out
+    };
+    let mut words = vec![0x1A412743u32, 0, 1, 0];
+    let mut data = vec![];
+    words.iter().for_each(|x| data.extend(x.to_le_bytes()));
+    data.push(name.len() as u8);
+    data.extend(name.as_bytes());
+    data.extend((16 + 1 + name.len() as u32 + 4).to_le_bytes());
+    words = vec![seq.len() as u32];
+    words.extend(blocks(&|x| x == b'N' || x == b'n'));
+    words.extend(blocks(&|x| x.is_ascii_lowercase()));
+    words.push(0);
+    words.iter().for_each(|x| data.extend(x.to_le_bytes()));
+    for chunk in seq.as_bytes().chunks(4){
+        let mut byte = 0u8;
+        for i in 0..4{
+            let code = match chunk.get(i).map(|x| x.to_ascii_uppercase()){
+                Some(b'C') => 1,
+                Some(b'A') => 2,
+                Some(b'G') => 3,
+                _ => 0,
+            };
+            byte |= code << (6 - 2 * i);
+        }
+        data.push(byte);
+    }
+    std::fs::write(file, data).unwrap();
+}
+
+#[test]
+fn fast_test_twobit_input(){
+    let dir = std::env::temp_dir().join("skani_test_twobit_input");
+    let dir = dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).unwrap();
+    let fasta = std::fs::read_to_string("./test_files/o157_plasmid.fasta").unwrap();
+    let seq = fasta.lines().skip(1).collect::<String>();
+    let seq = format!("{}{}{}{}", &seq[..20000], "N".repeat(500), seq[20500..60000].to_lowercase(), &seq[60000..]);
+    let twobit_file = format!("{}/plasmid.2bit", dir);
+    write_twobit(&twobit_file, "plasmid", &seq);
+    let fasta_file = format!("{}/plasmid.fa", dir);
+    std::fs::write(&fasta_file, format!(">plasmid\n{}\n", seq)).unwrap();
+
+    //Decoded 2bit sketches like the same sequence in fasta.
+    let out_2bit = run_skani(&["dist", &twobit_file, "./test_files/o157_plasmid.fasta"], false);
+    let out_fasta = run_skani(&["dist", &fasta_file, "./test_files/o157_plasmid.fasta"], false);
+    let res_2bit = get_result_from_out(&out_2bit);
+    let res_fasta = get_result_from_out(&out_fasta);
+    assert!(res_2bit.len() == 1);
+    assert!(res_2bit[0].ani == res_fasta[0].ani);
+    assert!(res_2bit[0].align_fraction_ref == res_fasta[0].align_fraction_ref);
+    assert!(out_2bit.lines().nth(1).unwrap().ends_with("\tplasmid"));
+
+    let out_line = run_skani(&["dist", &twobit_file, "./test_files/o157_plasmid.fasta", "--soft-mask"], false);
+    let res = get_result_from_out(&out_line);
+    assert!(res[0].align_fraction_ref < res_2bit[0].align_fraction_ref - 20.);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fast_test_masking(){
+    let dir = std::env::temp_dir().join("skani_test_masking");
+    let dir = dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).unwrap();
+    let fasta = std::fs::read_to_string("./test_files/o157_plasmid.fasta").unwrap();
+    let seq = fasta.lines().skip(1).collect::<String>();
+    let query_file = format!("{}/soft.fa", dir);
+    std::fs::write(&query_file, format!(">plasmid soft-masked\n{}{}{}\n", &seq[..20000], seq[20000..40000].to_lowercase(), &seq[40000..])).unwrap();
+    let bed_file = format!("{}/mask.bed", dir);
+    std::fs::write(&bed_file, "plasmid\t60000\t70000\n").unwrap();
+    //Aligned fractions of the masked genome and of the unmasked plasmid.
+    let afs = |extra: &[&str]| {
+        let mut args = vec!["dist", "./test_files/o157_plasmid.fasta", &query_file];
+        args.extend(extra);
+        let res = get_result_from_out(&run_skani(&args, false));
+        assert!(res.len() == 1);
+        assert!(res[0].ani > 99.5);
+        if res[0].ref_file == query_file{
+            (res[0].align_fraction_ref, res[0].align_fraction_query)
+        }
+        else{
+            (res[0].align_fraction_query, res[0].align_fraction_ref)
+        }
+    };
+
+    //Lowercase is only special with --soft-mask. Masked bases are neither aligned nor
+    //counted in the masked genome's aligned fraction.
+    let (masked_plain, other_plain) = afs(&[]);
+    let (masked_soft, other_soft) = afs(&["--soft-mask"]);
+    assert!(masked_plain > 99. && other_plain > 99.);
+    assert!(masked_soft > 99.);
+    assert!(other_soft < other_plain - 15.);
+    let (masked_both, other_both) = afs(&["--soft-mask", "--mask-bed", &bed_file]);
+    assert!(masked_both > 99.);
+    assert!(other_both < other_soft - 8.);
+
+    //Masked bases are recorded in the database.
+    let db = format!("{}/db", dir);
+    run_skani(&["sketch", &query_file, "-o", &db, "--soft-mask", "--mask-bed", &bed_file], false);
+    let out_line = run_skani(&["inspect", &db], false);
+    let row = out_line.lines().nth(1).unwrap().split('\t').collect::<Vec<&str>>();
+    assert!(row[7] == "30000");
+
+    //The references' mask is not applied to fasta queries.
+    let wide_bed_file = format!("{}/wide_mask.bed", dir);
+    std::fs::write(&wide_bed_file, "plasmid\t0\t80000\n").unwrap();
+    let sketch_dir = format!("{}/sketches", dir);
+    run_skani(&["sketch", &query_file, "-o", &sketch_dir, "--separate-sketches", "--mask-bed", &wide_bed_file], false);
+    let sketch_file = format!("{}/soft.fa.sketch", sketch_dir);
+    let res = get_result_from_out(&run_skani(&["dist", &sketch_file, &query_file], false));
+    assert!(res.len() == 1);
+    assert!(res[0].align_fraction_ref.min(res[0].align_fraction_query) < 20.);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fast_test_repeat_mask(){
+    let dir = std::env::temp_dir().join("skani_test_repeat_mask");
+    let dir = dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).unwrap();
+    let fasta = std::fs::read_to_string("./test_files/o157_plasmid.fasta").unwrap();
+    let seq = fasta.lines().skip(1).collect::<String>();
+    let viruses = std::fs::read_to_string("./test_files/viruses.fna").unwrap();
+    let insert = viruses.lines().skip(1).take(25).collect::<String>();
+
+    //Ten copies of a 1.5 kb insert in the plasmid.
+    let step = seq.len() / 10;
+    let mut repeat_seq = String::new();
+    for i in 0..10{
+        repeat_seq.push_str(&seq[i * step..(i + 1) * step]);
+        repeat_seq.push_str(&insert);
+    }
+    let genome = format!("{}/repeats.fa", dir);
+    std::fs::write(&genome, format!(">repeats\n{}\n", repeat_seq)).unwrap();
+
+    let out_line = run_skani(&["dist", &genome, &genome, "-v"], true);
+    assert!(!out_line.contains("repeat-masked"));
+    for repeat_mask in ["auto", "5"]{
+        let out_line = run_skani(&["dist", &genome, &genome, "-v", "--repeat-mask", repeat_mask], true);
+        assert!(out_line.contains("positions repeat-masked"));
+        assert!(!out_line.contains(": 0 seeds with"));
+        assert!(out_line.contains("Repeat-masked seeds dropped"));
+        let res = get_result_from_out(&run_skani(&["dist", &genome, &genome, "--repeat-mask", repeat_mask], false));
+        assert!(res[0].ani == 100.);
+    }
+
+    //Repeat seeds are stored in the database and used by search.
+    let db = format!("{}/db", dir);
+    run_skani(&["sketch", &genome, "-o", &db, "--repeat-mask", "auto"], false);
+    let out_line = run_skani(&["search", "-d", &db, &genome, "-v"], true);
+    assert!(out_line.contains("Repeat-masked seeds dropped"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fast_test_iupac_codes(){
+    let dir = std::env::temp_dir().join("skani_test_iupac_codes");
+    let dir = dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).unwrap();
+    let fasta = std::fs::read_to_string("./test_files/o157_plasmid.fasta").unwrap();
+    let mut seq = fasta.lines().skip(1).collect::<String>().into_bytes();
+    //An ambiguity code every 500 bases.
+    for (i, code) in (0..seq.len()).step_by(500).zip(b"RYKMSW".iter().cycle()){
+        seq[i] = *code;
+    }
+    let genome = format!("{}/iupac.fa", dir);
+    std::fs::write(&genome, format!(">iupac\n{}\n", std::str::from_utf8(&seq).unwrap())).unwrap();
+
+    let out_line = run_skani(&["dist", "./test_files/o157_plasmid.fasta", &genome], true);
+    assert!(out_line.contains(&format!("{} has {} bases with IUPAC ambiguity codes", genome, (seq.len() + 499) / 500)));
+    assert!(!out_line.contains("o157_plasmid.fasta has"));
+    let res = get_result_from_out(&run_skani(&["dist", "./test_files/o157_plasmid.fasta", &genome], false));
+    assert!(res[0].ani > 99.5);
+
+    let out_line = run_skani(&["sketch", &genome, "-o", &format!("{}/db", dir), "--separate-sketches"], true);
+    assert!(out_line.contains("IUPAC ambiguity codes"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fast_test_contig_filters(){
+    let dir = std::env::temp_dir().join("skani_test_contig_filters");
+    let dir = dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).unwrap();
+    let fasta = std::fs::read_to_string("./test_files/o157_plasmid.fasta").unwrap();
+    let seq = fasta.lines().skip(1).collect::<String>();
+    let genome = format!("{}/contigs.fa", dir);
+    std::fs::write(&genome, format!(
+        ">chunk_1\n{}\n>chunk_2\n{}\n>short_1\n{}\n>phage_1 prophage\n{}\n",
+        &seq[..40000], &seq[40000..80000], &seq[80000..80300], &seq[80300..85300])).unwrap();
+
+    //Dropped contigs and bases of the genome from the --detailed output.
+    let dropped = |extra: &[&str]| {
+        let mut args = vec!["dist", "./test_files/o157_plasmid.fasta", &genome, "--detailed"];
+        args.extend(extra);
+        let out_line = run_skani(&args, false);
+        let mut lines = out_line.lines();
+        let header = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+        let row = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+        let side = if row[0] == genome { "Ref" } else { "Query" };
+        let col = |name: String| row[header.iter().position(|x| *x == name).unwrap()].parse::<u64>().unwrap();
+        (col(format!("{}_dropped_contigs", side)), col(format!("{}_dropped_bases", side)))
+    };
+    assert!(dropped(&[]) == (1, 300));
+    assert!(dropped(&["--min-contig-length", "200"]) == (0, 0));
+    assert!(dropped(&["--min-contig-length", "10000"]) == (2, 5300));
+    assert!(dropped(&["--exclude-contigs", "prophage"]) == (2, 5300));
+    //Filters apply to both genomes; the plasmid's header starts with NZ_.
+    assert!(dropped(&["--include-contigs", "^(chunk_|NZ_)"]) == (2, 5300));
+
+    let out_line = run_skani(&["dist", "./test_files/o157_plasmid.fasta", &genome, "--include-contigs", "^nothing"], true);
+    assert!(out_line.contains("no contigs >= 500 bp passing the contig header filters"));
+    let out_line = run_skani(&["dist", "./test_files/o157_plasmid.fasta", &genome, "--exclude-contigs", "("], true);
+    assert!(out_line.contains("Invalid contig header regex"));
+
+    //The cut-off is stored in sketches, and mixing cut-offs is reported.
+    let db = format!("{}/db", dir);
+    let db_plasmid = format!("{}/db_plasmid", dir);
+    run_skani(&["sketch", &genome, "-o", &db, "--min-contig-length", "1000", "--separate-sketches"], false);
+    run_skani(&["sketch", "./test_files/o157_plasmid.fasta", "-o", &db_plasmid, "--separate-sketches"], false);
+    let sketch_file = format!("{}/contigs.fa.sketch", db);
+    let plasmid_sketch_file = format!("{}/o157_plasmid.fasta.sketch", db_plasmid);
+    let out_line = run_skani(&["triangle", &plasmid_sketch_file, &sketch_file], true);
+    assert!(out_line.contains("different minimum contig lengths (500 and 1000 bp)") || out_line.contains("different minimum contig lengths (1000 and 500 bp)"));
+    let out_line = run_skani(&["dist", &plasmid_sketch_file, &sketch_file], true);
+    assert!(out_line.contains("different minimum contig lengths"));
+    let res = get_result_from_out(&run_skani(&["dist", &plasmid_sketch_file, &sketch_file], false));
+    assert!(res.len() == 1);
+    let out_line = run_skani(&["dist", "./test_files/o157_plasmid.fasta", &sketch_file], true);
+    assert!(!out_line.contains("different minimum contig lengths"));
+    let out_line = run_skani(&["search", "-d", &db, "./test_files/o157_plasmid.fasta"], true);
+    assert!(!out_line.contains("minimum contig length"));
+    let out_line = run_skani(&["search", "-d", &db, "./test_files/o157_plasmid.fasta", "--min-contig-length", "200"], true);
+    assert!(out_line.contains("database was sketched with a minimum contig length of 1000 bp"));
+
+    //Queries don't inherit the database's header filters, and have their own.
+    let db_chunks = format!("{}/db_chunks", dir);
+    run_skani(&["sketch", &genome, "-o", &db_chunks, "--include-contigs", "^chunk_", "--separate-sketches"], false);
+    let search_dropped = |extra: &[&str]| {
+        let mut args = vec!["search", "-d", &db_chunks, &genome, "--detailed"];
+        args.extend(extra);
+        let out_line = run_skani(&args, false);
+        let mut lines = out_line.lines();
+        let header = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+        let col = |row: &[&str], name: &str| row[header.iter().position(|x| *x == name).unwrap()].parse::<u64>().unwrap();
+        lines.map(|line| {
+            let row = line.split('\t').collect::<Vec<&str>>();
+            (col(&row, "Query_dropped_contigs"), col(&row, "Query_dropped_bases"))
+        }).collect::<Vec<(u64, u64)>>()
+    };
+    assert!(search_dropped(&[]) == vec![(1, 300)]);
+    assert!(search_dropped(&["--exclude-contigs", "prophage"]) == vec![(2, 5300)]);
+    let contig_rows = search_dropped(&["--qi"]);
+    assert!(!contig_rows.is_empty());
+    assert!(contig_rows.iter().all(|x| *x == (1, 300)));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fast_test_stdin(){
+    let dir = std::env::temp_dir().join("skani_test_stdin");
+    let dir = dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).unwrap();
+    let fasta = std::fs::read("./test_files/o157_plasmid.fasta").unwrap();
+    let stdout = |out: std::process::Output| String::from_utf8(out.stdout).unwrap();
+
+    //Fasta on stdin for dist and triangle.
+    let res = get_result_from_out(&stdout(run_skani_stdin(&["dist", "./test_files/o157_plasmid.fasta", "-"], fasta.clone())));
+    assert!(res.len() == 1);
+    assert!(res[0].ani == 100.);
+    assert!(res[0].ref_file == "-" || res[0].query_file == "-");
+    let out_line = stdout(run_skani_stdin(&["triangle", "./test_files/o157_plasmid.fasta", "-", "-E"], fasta.clone()));
+    assert!(get_result_from_out(&out_line).len() == 1);
+    let gzipped = std::fs::read("./test_files/e.coli-W.fasta.gz").unwrap();
+    let res = get_result_from_out(&stdout(run_skani_stdin(&["dist", "./test_files/e.coli-W.fasta.gz", "-"], gzipped)));
+    assert!(res.len() == 1);
+    assert!(res[0].ani == 100.);
+
+    //A sketch written to stdout reads back from stdin, and matches the sketch of the file.
+    let sketch = run_skani_stdin(&["sketch", "-", "-o", "-"], fasta.clone()).stdout;
+    assert!(!sketch.is_empty());
+    let res = get_result_from_out(&stdout(run_skani_stdin(&["dist", "./test_files/o157_plasmid.fasta", "-"], sketch.clone())));
+    assert!(res.len() == 1);
+    assert!(res[0].ani == 100.);
+    //Sketch detection doesn't depend on the value of c.
+    let sketch_c300 = run_skani_stdin(&["sketch", "-", "-o", "-", "-c", "300", "-m", "1000"], fasta.clone()).stdout;
+    let res = get_result_from_out(&stdout(run_skani_stdin(&["dist", "./test_files/o157_plasmid.fasta", "-"], sketch_c300)));
+    assert!(res.len() == 1);
+    assert!(res[0].ani == 100.);
+    let file_sketch = run_skani_stdin(&["sketch", "./test_files/o157_plasmid.fasta", "-o", "-"], vec![]).stdout;
+    let db = format!("{}/db", dir);
+    run_skani(&["sketch", "./test_files/o157_plasmid.fasta", "-o", &db, "--separate-sketches"], false);
+    assert!(file_sketch == std::fs::read(format!("{}/o157_plasmid.fasta.sketch", db)).unwrap());
+
+    //Search with a query fasta or sketch on stdin.
+    for input in [fasta.clone(), sketch]{
+        let res = get_result_from_out(&stdout(run_skani_stdin(&["search", "-d", &db, "-"], input)));
+        assert!(res.len() == 1);
+        assert!(res[0].ani == 100.);
+    }
+
+    let out = run_skani_stdin(&["dist", "-", "-"], fasta.clone());
+    assert!(String::from_utf8(out.stderr).unwrap().contains("Only one input can be read from stdin"));
+    let out = run_skani_stdin(&["sketch", "-", "./test_files/o157_plasmid.fasta", "-o", "-"], fasta);
+    assert!(out.stdout.is_empty());
+    assert!(String::from_utf8(out.stderr).unwrap().contains("writes the sketch of a single genome"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
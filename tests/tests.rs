@@ -33,6 +33,18 @@ fn default_params(mode: Mode) -> (CommandParams, SketchParams) {
         rescue_small: true,
         separate_sketches: false,
         short_header: false,
+        window_size: 0,
+        window_step: 0,
+        sort_contigs: false,
+        plot_size: 0,
+        heatmap_file: String::new(),
+        heatmap_labels: false,
+        heatmap_af: false,
+        extra_columns: ExtraColumns::default(),
+        ci_params: CiParams::default(),
+        genome_groups: GenomeGroups::default(),
+        display_names: DisplayNames::default(),
+        query_contig_filters: QueryContigFilters::default(),
     };
 
     let sketch_params = SketchParams::new(1000, 125, 15, false, false);
@@ -59,6 +71,28 @@ fn fast_ecoli_test_simple() {
     assert!(ani_res.align_fraction_ref >= 0.99);
 }
 
+#[test]
+fn fast_extra_stats_only_when_requested() {
+    let (mut command_params, sketch_params) = default_params(Mode::Dist);
+    let files = vec!["./test_files/o157_plasmid.fasta".to_string()];
+    let sketch = fastx_to_sketches(&files, &sketch_params, true)[0].clone();
+
+    let map_params = map_params_from_sketch(&sketch, sketch_params.use_aa, &command_params, &None);
+    let ani_res = chain_seeds(&sketch, &sketch, map_params);
+    assert!(ani_res.synteny == SyntenyStats::default());
+    assert!(ani_res.ddh == DdhEstimate::default());
+    assert!(ani_res.indels == IndelStats::default());
+
+    command_params.extra_columns.synteny = true;
+    command_params.extra_columns.ddh = true;
+    command_params.extra_columns.indels = true;
+    let map_params = map_params_from_sketch(&sketch, sketch_params.use_aa, &command_params, &None);
+    let ani_res = chain_seeds(&sketch, &sketch, map_params);
+    assert!(ani_res.synteny.collinear_blocks > 0);
+    assert!(ani_res.ddh.d4 > 70.);
+    assert!(ani_res.indels.aligned_bases > 0);
+}
+
 //#[test]
 fn fast_ecoli_plasmid_test() {
     let (mut command_params, sketch_params) = default_params(Mode::Dist);
@@ -143,6 +177,67 @@ fn fast_avx2_vs_normal_code(){
     //println!("{:?}", new_sketch1.kmer_seeds_k.unwrap());
 }
 
+#[test]
+fn fast_avx2_vs_normal_code_spaced_seed(){
+    let str1 = b"ATCAGATTTAAAAAAAAATTTTGCTAGCTGATCGATCGATCGATGTGTATATATTAAAAGAGAGAGAGGGGGGGGAAAAAAAAAAAAACTGATCGATCGATGCTAGCTAGTCAGTCGATG";
+    let (_command_params, mut sketch_params) = default_params(Mode::Dist);
+    sketch_params.c = 10;
+    sketch_params.set_spaced_seed(SpacedSeed::parse(GENUS_SEED_PATTERN).unwrap());
+    let mut new_sketch1 = Sketch::default();
+    let mut new_sketch2 = Sketch::default();
+    unsafe{
+        avx2_fmh_seeds(str1, &sketch_params, 0, &mut new_sketch1, true);
+    }
+
+    fmh_seeds(str1, &sketch_params, 0, &mut new_sketch2, true);
+    assert!(new_sketch1 == new_sketch2);
+    assert!(new_sketch1.kmer_seeds_k.unwrap().len() > 0);
+}
+
+#[test]
+fn fast_contiguous_spaced_seed_is_kmer(){
+    let str1 = b"ATCAGATTTAAAAAAAAATTTTGCTAGCTGATCGATCGATCGATGTGTATATATTAAAAGAGAGAGAGGGGGGGGAAAAAAAAAAAAACTGATCGATCGATGCTAGCTAGTCAGTCGATG";
+    let (_command_params, mut sketch_params) = default_params(Mode::Dist);
+    sketch_params.c = 10;
+    let mut new_sketch1 = Sketch::default();
+    fmh_seeds(str1, &sketch_params, 0, &mut new_sketch1, true);
+    sketch_params.set_spaced_seed(SpacedSeed::parse("111111111111111").unwrap());
+    let mut new_sketch2 = Sketch::default();
+    fmh_seeds(str1, &sketch_params, 0, &mut new_sketch2, true);
+    assert!(new_sketch1 == new_sketch2);
+
+    assert!(SpacedSeed::parse("0111").is_err());
+    assert!(SpacedSeed::parse(&"1".repeat(33)).is_err());
+    //Patterns longer than the marker k-mers are rejected when checking the parameters.
+    sketch_params.set_spaced_seed(SpacedSeed::parse(&"1".repeat(22)).unwrap());
+    assert!(sketch_params.check_seed_lengths().is_err());
+    sketch_params.marker_k = 22;
+    assert!(sketch_params.check_seed_lengths().is_ok());
+}
+
+#[test]
+fn fast_avx2_vs_normal_code_wide_seeds(){
+    let str1 = b"ATCAGATTTAAAAAAAAATTTTGCTAGCTGATCGATCGATCGATGTGTATATATTAAAAGAGAGAGAGGGGGGGGAAAAAAAAAAAAACTGATCGATCGATGCTAGCTAGTCAGTCGATGTTGACCATGCAAGT";
+    for (k, marker_k) in [(24, 31), (32, 32), (16, 16)]{
+        //The AVX2 code only seeds a multiple of 4 windows.
+        let str1 = &str1[..marker_k - 1 + (str1.len() - marker_k + 1) / 4 * 4];
+        let (_command_params, mut sketch_params) = default_params(Mode::Dist);
+        sketch_params.c = 10;
+        sketch_params.k = k;
+        sketch_params.marker_k = marker_k;
+        let mut new_sketch1 = Sketch::default();
+        let mut new_sketch2 = Sketch::default();
+        unsafe{
+            avx2_fmh_seeds(str1, &sketch_params, 0, &mut new_sketch1, true);
+        }
+
+        fmh_seeds(str1, &sketch_params, 0, &mut new_sketch2, true);
+        assert!(new_sketch1 == new_sketch2);
+        let seeds = new_sketch1.kmer_seeds_k.unwrap();
+        assert!(seeds.is_wide() == (k > 16));
+        assert!(seeds.len() > 0);
+    }
+}
 
 //Ns are treated as As right now. Luckily, the hash function doesn't accept AAAAAA... 
 //for FMH when c = 30. 
@@ -155,3 +250,225 @@ fn fast_NNN_test_code(){
     fmh_seeds(str1, &sketch_params, 0, &mut new_sketch1, true);
     assert!(new_sketch1.kmer_seeds_k.unwrap().len() == 0);
 }
+
+#[test]
+fn fast_iupac_breaks_kmers(){
+    let str1 = b"RTCAGATTTAAAAAAAAATTTTGCTAGCTGATCGATCGAYCGATGTGTATATATTAAAAGAGAGAGAGGGGGGGGAAAAAAAAAAAAACTGATCGATCGATGCKAGCTAGTCAGTCGATGTTGACCATGCAAGTGCTAGCTAGTCMGTCGATGTTGAC";
+    let (_command_params, mut sketch_params) = default_params(Mode::Dist);
+    sketch_params.c = 2;
+    let str1 = &str1[..sketch_params.marker_k - 1 + (str1.len() - sketch_params.marker_k + 1) / 4 * 4];
+    let with_n = str1.iter().map(|x| if b"ACGT".contains(x) { *x } else { b'N' }).collect::<Vec<u8>>();
+    let with_a = str1.iter().map(|x| if b"ACGT".contains(x) { *x } else { b'A' }).collect::<Vec<u8>>();
+    assert!(count_iupac_bases(str1) == 4);
+    assert!(count_iupac_bases(&with_n) == 0);
+
+    //Ambiguity codes are k-mer breaks like N, not adenines.
+    let mut iupac_sketch = Sketch::default();
+    let mut n_sketch = Sketch::default();
+    let mut a_sketch = Sketch::default();
+    fmh_seeds(str1, &sketch_params, 0, &mut iupac_sketch, true);
+    fmh_seeds(&with_n, &sketch_params, 0, &mut n_sketch, true);
+    fmh_seeds(&with_a, &sketch_params, 0, &mut a_sketch, true);
+    assert!(iupac_sketch == n_sketch);
+    assert!(iupac_sketch != a_sketch);
+    let mut avx2_sketch = Sketch::default();
+    unsafe{
+        avx2_fmh_seeds(str1, &sketch_params, 0, &mut avx2_sketch, true);
+    }
+    assert!(avx2_sketch == iupac_sketch);
+}
+
+#[test]
+fn fast_markers_skip_kmer_breaks(){
+    let (_command_params, mut sketch_params) = default_params(Mode::Dist);
+    sketch_params.c = 2;
+    sketch_params.marker_c = 2;
+    let marker_k = sketch_params.marker_k;
+    let mut state: u64 = 7;
+    let mut str1 = (0..marker_k - 1 + 4 * 60).map(|_| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        b"ACGT"[(state >> 62) as usize]
+    }).collect::<Vec<u8>>();
+    str1[80] = b'N';
+    str1[170] = b'R';
+
+    //Every marker must come from a window lying entirely within an ACGT run.
+    let mut run_markers = std::collections::HashSet::new();
+    for run in str1.split(|x| !b"ACGT".contains(x)) {
+        let mut run_sketch = Sketch::default();
+        fmh_seeds(run, &sketch_params, 0, &mut run_sketch, true);
+        run_markers.extend(run_sketch.marker_seeds.iter().copied());
+    }
+    let mut sketch = Sketch::default();
+    fmh_seeds(&str1, &sketch_params, 0, &mut sketch, true);
+    assert!(!sketch.marker_seeds.is_empty());
+    assert!(sketch.marker_seeds.iter().copied().collect::<std::collections::HashSet<_>>() == run_markers);
+    let mut avx2_sketch = Sketch::default();
+    unsafe{
+        avx2_fmh_seeds(&str1, &sketch_params, 0, &mut avx2_sketch, true);
+    }
+    assert!(avx2_sketch == sketch);
+}
+
+#[test]
+fn fast_flat_file_parse(){
+    use skani::flat_file::*;
+    let dir = std::env::temp_dir().join("skani_test_flat_parse");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let gbk = dir.join("t.gbk");
+    let gbk = gbk.to_str().unwrap();
+    std::fs::write(gbk, "\
+LOCUS       rec1                      24 bp    DNA     linear   BCT 01-JAN-2000
+ACCESSION   AB000001
+VERSION     AB000001.2
+FEATURES             Location/Qualifiers
+     source          1..24
+     CDS             1..9
+                     /product=\"a\"
+     CDS             complement(10..
+                     21)
+     CDS             join(1..3,
+                     10..15)
+     CDS             <1..6
+ORIGIN
+        1 atgaaatagc ccatgggtta aaaa
+//
+LOCUS       rec2                       6 bp    DNA     linear   BCT 01-JAN-2000
+ORIGIN
+        1 acgtnn
+//
+").unwrap();
+    assert!(detect_flat_format(gbk) == Some(FlatFormat::GenBank));
+    let records = read_flat_file(gbk, FlatFormat::GenBank).unwrap();
+    assert!(records.len() == 2);
+    assert!(records[0].name == "AB000001.2");
+    assert!(records[0].seq == b"ATGAAATAGCCCATGGGTTAAAAA");
+    assert!(records[0].cds == vec![Cds{start: 0, end: 9, complement: false}, Cds{start: 9, end: 21, complement: true}]);
+    let orfs = records[0].cds_orfs();
+    assert!(orfs[0].end == 6 && orfs[0].phase == 0);
+    assert!(orfs[1].start == 9 && orfs[1].phase == 3);
+    assert!(records[1].name == "rec2");
+    assert!(records[1].cds.is_empty());
+
+    let embl = dir.join("t.embl");
+    let embl = embl.to_str().unwrap();
+    std::fs::write(embl, "\
+ID   X1; SV 1; linear; genomic DNA; STD; PRO; 12 BP.
+XX
+AC   X00001; X00002;
+XX
+FT   source          1..12
+FT   CDS             4..12
+FT                   /product=\"b\"
+XX
+SQ   Sequence 12 BP;
+     ccc atg aaa taa                                                          12
+//
+").unwrap();
+    assert!(detect_flat_format(embl) == Some(FlatFormat::Embl));
+    let records = read_flat_file(embl, FlatFormat::Embl).unwrap();
+    assert!(records.len() == 1);
+    assert!(records[0].name == "X00001");
+    assert!(records[0].seq == b"CCCATGAAATAA");
+    assert!(records[0].cds == vec![Cds{start: 3, end: 12, complement: false}]);
+
+    assert!(detect_flat_format("./test_files/o157_plasmid.fasta").is_none());
+    std::fs::write(gbk, "LOCUS       rec1\nORIGIN\n        1 acgt\n").unwrap();
+    assert!(read_flat_file(gbk, FlatFormat::GenBank).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fast_gfa_parse(){
+    use skani::gfa::*;
+    let dir = std::env::temp_dir().join("skani_test_gfa_parse");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let gfa1 = dir.join("t1.gfa");
+    let gfa1 = gfa1.to_str().unwrap();
+    std::fs::write(gfa1, "H\tVN:Z:1.0\nS\ts1\tACGT\tLN:i:4\nS\ts2\t*\tLN:i:100\nL\ts1\t+\ts3\t-\t0M\nS\ts3\tGGCC\r\n").unwrap();
+    let gfa2 = dir.join("t2.gfa");
+    let gfa2 = gfa2.to_str().unwrap();
+    std::fs::write(gfa2, "H\tVN:Z:2.0\nS\tu1\t4\tACGT\nE\t*\tu1+\tu1-\t0\t1\t0\t1\t*\n").unwrap();
+    let mut segments = vec![];
+    assert!(is_gfa(gfa1));
+    for_each_segment(gfa1, |id, seq| segments.push((id.to_vec(), seq.to_vec()))).unwrap();
+    assert!(segments == vec![(b"s1".to_vec(), b"ACGT".to_vec()), (b"s3".to_vec(), b"GGCC".to_vec())]);
+    segments.clear();
+    assert!(is_gfa(gfa2));
+    for_each_segment(gfa2, |id, seq| segments.push((id.to_vec(), seq.to_vec()))).unwrap();
+    assert!(segments == vec![(b"u1".to_vec(), b"ACGT".to_vec())]);
+
+    assert!(!is_gfa("./test_files/o157_plasmid.fasta"));
+    std::fs::write(gfa1, "S\ts1\n").unwrap();
+    assert!(for_each_segment(gfa1, |_, _| {}).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fast_twobit_parse(){
+    use skani::twobit::*;
+    let file = std::env::temp_dir().join("skani_test_twobit_parse.2bit");
+    let file = file.to_str().unwrap();
+    //One sequence "ACGTNNacgt": an N block at 4..6 and a mask block at 6..10.
+    let mut data = vec![];
+    for x in [0x1A412743u32, 0, 1, 0]{
+        data.extend(x.to_le_bytes());
+    }
+    data.extend([1, b's']);
+    data.extend(22u32.to_le_bytes());
+    for x in [10u32, 1, 4, 2, 1, 6, 4, 0]{
+        data.extend(x.to_le_bytes());
+    }
+    data.extend([0x9C, 0x09, 0xC0]);
+    std::fs::write(file, &data).unwrap();
+    assert!(is_twobit(file));
+    let mut seqs = vec![];
+    for_each_sequence(file, |name, seq| seqs.push((name.to_vec(), seq.to_vec()))).unwrap();
+    assert!(seqs == vec![(b"s".to_vec(), b"ACGTNNacgt".to_vec())]);
+
+    std::fs::write(file, &data[..data.len() - 1]).unwrap();
+    assert!(for_each_sequence(file, |_, _| {}).is_err());
+    assert!(!is_twobit("./test_files/o157_plasmid.fasta"));
+    std::fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn fast_mask_bed_parse(){
+    let file = std::env::temp_dir().join("skani_test_mask.bed");
+    let file = file.to_str().unwrap();
+    std::fs::write(file, "track name=x\n# comment\nc1\t100\t200\tname\nc1\t150\t300\nc1\t0\t10\nc2 desc\t5\t6\n").unwrap();
+    let regions = read_mask_bed(file).unwrap();
+    assert!(regions["c1"] == vec![(0, 10), (100, 300)]);
+    assert!(regions["c2 desc"] == vec![(5, 6)]);
+    std::fs::write(file, "c1\t200\t100\n").unwrap();
+    assert!(read_mask_bed(file).is_err());
+    std::fs::write(file, "c1 100 200\n").unwrap();
+    assert!(read_mask_bed(file).is_err());
+    std::fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn fast_repeat_mask_threshold(){
+    //Unique seeds only: nothing is masked below the minimum threshold.
+    assert!(adaptive_repeat_threshold(&vec![1; 1000], 125) == REPEAT_MASK_MIN_THRESHOLD);
+    //A ten-copy repeat family stands out, but the chaining band caps the threshold.
+    let mut counts = vec![1; 1000];
+    counts.extend(vec![10; 15]);
+    let threshold = adaptive_repeat_threshold(&counts, 125);
+    assert!(threshold > REPEAT_MASK_MIN_THRESHOLD && threshold < 10);
+    assert!(adaptive_repeat_threshold(&counts, 1000) == REPEAT_MASK_MIN_THRESHOLD);
+
+    let (_command_params, sketch_params) = default_params(Mode::Dist);
+    let str1 = b"ATCAGATTTAAAAAAAAATTTTGCTAGCTGATCGATCGATCGATGTGTATATATTAAAAGAGAGAGGGGGGGGAAAAAAAAAAAAACTGATCGATCGATGCTAGCTAGTCAGTCGATGTTGACCATGCAAGT";
+    let mut sketch = Sketch::default();
+    sketch.c = sketch_params.c;
+    fmh_seeds(&str1.repeat(4), &sketch_params, 0, &mut sketch, true);
+    mask_repeats(&mut sketch, RepeatMask::Threshold(3));
+    assert!(sketch.repetitive_kmers == 3);
+    assert!(!sketch.repeat_seeds.is_empty());
+    assert!(sketch.repeat_seeds.iter().all(|seed| sketch.get_seed_positions(*seed).len() > 3));
+    mask_repeats(&mut sketch, RepeatMask::Threshold(4));
+    assert!(sketch.repeat_seeds.is_empty());
+}